@@ -5,9 +5,28 @@ name="$1"
 
 # 引数が提供されているか確認
 if [ -z "$name" ]; then
-    echo "Usage: $0 <name>"
+    echo "Usage: $0 <name> [options]"
     exit 1
 fi
+shift
 
-# Cargoコマンドを実行
-cargo run --release --bin "$name" > "data/${name}.txt"
\ No newline at end of file
+# シャードを指定した場合はシャードごとのファイルに出力
+output="data/${name}.txt"
+count=""
+prev=""
+for arg in "$@"; do
+    if [ "$prev" = "--shard" ]; then
+        output="data/${name}.shard-${arg%/*}-of-${arg#*/}.txt"
+    fi
+    if [ "$arg" = "--count" ]; then
+        count="1"
+    fi
+    prev="$arg"
+done
+
+# Cargoコマンドを実行（--countの場合は個数を画面に表示する）
+if [ -n "$count" ]; then
+    cargo run --release --bin "$name" -- "$@"
+else
+    cargo run --release --bin "$name" -- "$@" > "$output"
+fi
@@ -3,18 +3,18 @@
 
 // Cのdomainは現実的に{1..sqrt(n*m)}のために制限
 
-use indicatif::{ProgressBar, ProgressStyle};
-use puzzle_check::common::combine::combine;
-use puzzle_check::common::dataclass::{
-    Attribute, BoardSize, Composition, Coordinate, Element, Structure,
-};
-use puzzle_check::common::initialize::initialize;
+use puzzle_check::common::assignment::Assignment;
+use puzzle_check::common::board::{Adjacency, Board};
+use puzzle_check::common::combine::combine_on_board;
+use puzzle_check::common::dataclass::{BoardSize, Composition, Structure};
+use puzzle_check::common::driver::{Domains, Driver, Layout};
 use puzzle_check::common::operate_structures::OperateStructure;
-use puzzle_check::common::relationship::{relationship, Relationship, D, H, M, V};
-use puzzle_check::specific::board_validation::{BoardValidation, BoardValidationFn};
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::relationship::{relationship, M};
+use puzzle_check::common::serialize::BoardRecord;
+use puzzle_check::named;
 use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
-use rayon::prelude::*;
-use std::collections::HashSet;
+use rand::rngs::StdRng;
 
 const n: i32 = 4;
 const m: i32 = 5;
@@ -22,20 +22,14 @@ const board_size: BoardSize = BoardSize(n, m);
 const LOOP_NUMBERS: u64 = 1000;
 
 fn main() {
-    let pb = ProgressBar::new(LOOP_NUMBERS);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("main    {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
-            .unwrap(),
-    );
-
-    let (P, C, Ep, Ec) = initialize(&board_size);
+    let board = Board::new(&board_size);
+    let (_, C, _, _) = board.parts();
 
     // ----------------------------------------------------------------------
-    let R: Vec<Relationship> = vec![H, V];
-    let not_R: Vec<Relationship> = vec![M];
-    let cutoff_functions: Vec<CutoffFn> = vec![Cutoff::non_cutoff];
-    let A = combine(R, not_R, &C, &cutoff_functions);
+    let R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::V];
+    let not_R: Vec<Adjacency> = vec![Adjacency::M];
+    let cutoff_functions: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+    let A = combine_on_board(R, not_R, &C, &cutoff_functions, &board);
 
     // combineの確認---------------------------
     // println!("{:?}", A.len());
@@ -44,28 +38,19 @@ fn main() {
     // }
     // ---------------------------------------
 
-    let board_validation_functions: Vec<BoardValidationFn> = vec![BoardValidation::non_validation];
-
     let max_c = ((board_size.0 * board_size.1) as f64).sqrt() as i32;
 
     let P_domain: Vec<Option<i32>> = vec![None];
     let C_domain: Vec<Option<i32>> = (0..=max_c).map(Some).collect();
     let Ep_domain: Vec<Option<i32>> = vec![None];
     let Ec_domain: Vec<Option<i32>> = vec![None];
-    let A_domain: Vec<Option<i32>> = vec![None];
-
-    let P_domain_size = P_domain.len();
-    let C_domain_size = C_domain.len();
-    let Ep_domain_size = Ep_domain.len();
-    let Ec_domain_size = Ec_domain.len();
-    let A_domain_size = A_domain.len();
 
-    let total_combinations_P = P_domain_size.pow(P.len() as u32);
-    let total_combinations_C = C_domain_size.pow(C.len() as u32);
-    let total_combinations_Ep = Ep_domain_size.pow(Ep.len() as u32);
-    let total_combinations_Ec = P_domain_size.pow(Ec.len() as u32);
+    let driver = Driver::new(
+        "fillomino",
+        Domains::new(P_domain, C_domain, Ep_domain, Ec_domain),
+    );
 
-    (0..LOOP_NUMBERS).into_par_iter().for_each(|_| {
+    let layout_fn = |_: u64, rng: &mut StdRng| {
         let mut B = Structure::Composition(Composition::new(vec![]));
         let mut power_A: Vec<Structure> = vec![];
         'inner: loop {
@@ -74,71 +59,32 @@ fn main() {
                     break 'inner;
                 }
             }
-            let new_area = OperateStructure::extract_random_structure(&A);
+            let new_area = OperateStructure::extract_random_structure(&A, rng);
             if relationship(&new_area, &B, M) {
                 continue 'inner;
             }
             B = OperateStructure::add_up_structures(&B, &new_area);
             power_A.push(new_area);
         }
-        (0..total_combinations_P).into_par_iter().for_each(|pi| {
-            let mut independent_P = P.clone();
-            let mut index_pi = pi;
 
-            for structure_p in independent_P.iter_mut() {
-                if let Structure::Element(ref mut point_content) = structure_p {
-                    let digit = index_pi % P_domain_size;
-                    index_pi /= P_domain_size;
-                    point_content.val = P_domain[digit];
-                }
-            }
-            (0..total_combinations_Ep).into_par_iter().for_each(|epi| {
-                let mut independent_Ep = Ep.clone();
-                let mut index_epi = epi;
-
-                for structure_ep in independent_Ep.iter_mut() {
-                    if let Structure::Element(ref mut ep_content) = structure_ep {
-                        let digit = index_epi % Ep_domain_size;
-                        index_epi /= Ep_domain_size;
-                        ep_content.val = Ep_domain[digit];
-                    }
-                }
-
-                (0..total_combinations_Ec).into_par_iter().for_each(|eci| {
-                    let mut independent_Ec = Ec.clone();
-                    let mut index_eci = eci;
-
-                    for structure_ec in independent_Ec.iter_mut() {
-                        if let Structure::Element(ref mut ec_content) = structure_ec {
-                            let digit = index_eci % Ec_domain_size;
-                            index_eci /= Ec_domain_size;
-                            ec_content.val = Ec_domain[digit];
-                        }
-                    }
-
-                    let mut independent_C = C.clone();
-                    for area in power_A.iter() {
-                        for structure_c in independent_C.iter_mut() {
-                            {
-                                if let Structure::Composition(ref a_content) = area {
-                                    if a_content.entity.iter().any(|cell| {
-                                        OperateStructure::compare_structures(cell, structure_c)
-                                    }) {
-                                        if let Structure::Element(ref mut c_content) = structure_c {
-                                            c_content.val = Some(a_content.entity.len() as i32);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    pb.inc(1);
-                    println!("{:?}", independent_C);
-                    println!("{:?}", power_A);
-                    println!("");
-                })
-            })
-        })
+        // セルの値は所属する領域の大きさで決まる
+        let mut assignment = Assignment::new(&board_size);
+        for area in power_A.iter() {
+            assignment.fill(area, Some(area.elements().len() as i32));
+        }
+        let independent_C = assignment.apply(&C);
+        return Some(Layout::new((independent_C, power_A), vec![]));
+    };
+
+    if driver.counting() {
+        driver.count(LOOP_NUMBERS, layout_fn, |_, _| vec![]);
+        return;
+    }
+
+    driver.run(LOOP_NUMBERS, layout_fn, |(independent_C, power_A), _| {
+        let mut record = BoardRecord::new("fillomino", &board_size);
+        record.add_elements(independent_C);
+        record.add_areas("room", power_A);
+        return Ok(record);
     });
-    pb.finish();
 }
@@ -0,0 +1,85 @@
+// name: frontier
+// 格子上のサイクル・パス・連結な分割の個数をフロンティア法で数える
+// usage: frontier <cycles|paths|partitions> <n> <m> [--vertices P|C] [--check]
+// --vertices: サイクル・パスの頂点の種類（P: 格子点, 辺はEp（既定）, C: セル, 辺はEc）
+// --check: combineの結果の個数と照合する（べき集合を作るので小さな盤面のみ）
+
+use puzzle_check::common::combine::combine;
+use puzzle_check::common::dataclass::{Attribute, BoardSize};
+use puzzle_check::common::frontier::Frontier;
+use puzzle_check::common::initialize::initialize;
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::relationship::{Relationship, D, H, M, V};
+use puzzle_check::named;
+use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
+use std::time::Instant;
+
+fn usage() -> ! {
+    eprintln!("usage: frontier <cycles|paths|partitions> <n> <m> [--vertices P|C] [--check]");
+    std::process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 3 {
+        usage();
+    }
+    let kind = args[0].as_str();
+    let n: i32 = args[1].parse().unwrap_or_else(|_| usage());
+    let m: i32 = args[2].parse().unwrap_or_else(|_| usage());
+    let board_size = BoardSize(n, m);
+    let mut vertices = Attribute::P;
+    let mut check = false;
+    let mut iter = args[3..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--vertices" => {
+                vertices = match iter.next().map(|v| v.as_str()) {
+                    Some("P") => Attribute::P,
+                    Some("C") => Attribute::C,
+                    _ => usage(),
+                }
+            }
+            "--check" => check = true,
+            _ => usage(),
+        }
+    }
+
+    let started = Instant::now();
+    let count = match kind {
+        "cycles" => Frontier::count_cycles(&board_size, &vertices),
+        "paths" => Frontier::count_paths(&board_size, &vertices),
+        "partitions" => Frontier::count_partitions(&board_size),
+        _ => usage(),
+    };
+    println!("{}", count);
+    eprintln!(
+        "{} on {}x{}: {} ({:.3}s)",
+        kind,
+        n,
+        m,
+        count,
+        started.elapsed().as_secs_f64()
+    );
+
+    if check {
+        let cutoff_functions: Vec<Named<CutoffFn>> = match kind {
+            "cycles" => named![Cutoff::only_cycle],
+            "paths" => named![Cutoff::only_line],
+            _ => {
+                eprintln!("--check is available for cycles and paths");
+                std::process::exit(2);
+            }
+        };
+        let (_, _, Ep, Ec) = initialize(&board_size);
+        let edges = if vertices == Attribute::P { Ep } else { Ec };
+        let R: Vec<Relationship> = vec![H, V, D];
+        let not_R: Vec<Relationship> = vec![M];
+        let expected = combine(R, not_R, &edges, &cutoff_functions).len() as u128;
+        if expected != count {
+            eprintln!("mismatch: combine found {}", expected);
+            std::process::exit(1);
+        }
+        eprintln!("combine agrees: {}", expected);
+    }
+}
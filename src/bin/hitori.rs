@@ -1,24 +1,23 @@
 // label: cut-off, sparce expected, random
 // name: hitori
 
-use indicatif::{ProgressBar, ProgressStyle};
-use puzzle_check::common::combine::combine;
-use puzzle_check::common::initialize::initialize;
+use puzzle_check::common::assignment::Assignment;
+use puzzle_check::common::board::{Adjacency, Board};
+use puzzle_check::common::combine::combine_on_board;
+use puzzle_check::common::count::Constraint;
+use puzzle_check::common::driver::{Domains, Driver, Layout};
 use puzzle_check::common::operate_structures::OperateStructure;
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::serialize::BoardRecord;
+use puzzle_check::named;
 use puzzle_check::specific::predicates::Predicates;
 use puzzle_check::specific::structure_functions::StructureFn;
 
-use puzzle_check::common::dataclass::{
-    Attribute, BoardSize, Composition, Coordinate, Element, Structure,
-};
-use puzzle_check::common::relationship::{relationship, Relationship, D, H, M, V};
+use puzzle_check::common::dataclass::{BoardSize, Composition, Structure};
 use puzzle_check::specific::board_validation::{BoardValidation, BoardValidationFn};
-use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
+use puzzle_check::specific::cutoff::CutoffFn;
 
-use rayon::prelude::*;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use rand::rngs::StdRng;
 
 // random_subsetが終了しないためサイズ制限を導入
 fn size_limitation(area: &Structure) -> bool {
@@ -53,34 +52,37 @@ pub fn non_division(area: &Structure) -> bool {
 }
 
 const n: i32 = 3;
-const black: i32 = -1;
 const board_size: BoardSize = BoardSize(n, n);
 const LOOP_NUMBERS: u64 = 1000;
 
 fn main() {
-    let pb = ProgressBar::new(LOOP_NUMBERS);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("main    {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
-            .unwrap(),
-    );
-
-    let print_lock = Arc::new(Mutex::new(()));
-
-    let (P, C, Ep, Ec) = initialize(&board_size);
+    let board = Board::new(&board_size);
+    let (_, C, _, _) = board.parts();
 
     // ----------------------------------------------------------------------
-    let R: Vec<Relationship> = vec![D];
-    let not_R: Vec<Relationship> = vec![M];
-    let row_R: Vec<Relationship> = vec![H];
-    let not_row_R: Vec<Relationship> = vec![D, V, M];
-    let col_R: Vec<Relationship> = vec![V];
-    let not_col_R: Vec<Relationship> = vec![H, D, M];
-    let cutoff_functions: Vec<CutoffFn> = vec![size_limitation, non_division];
-    let cutoff_functions_for_different: Vec<CutoffFn> = vec![size_limitation_n];
-    let A = combine(R, not_R, &C, &cutoff_functions);
-    let row_A = combine(row_R, not_row_R, &C, &cutoff_functions_for_different);
-    let col_A = combine(col_R, not_col_R, &C, &cutoff_functions_for_different);
+    let R: Vec<Adjacency> = vec![Adjacency::D];
+    let not_R: Vec<Adjacency> = vec![Adjacency::M];
+    let row_R: Vec<Adjacency> = vec![Adjacency::H];
+    let not_row_R: Vec<Adjacency> = vec![Adjacency::D, Adjacency::V, Adjacency::M];
+    let col_R: Vec<Adjacency> = vec![Adjacency::V];
+    let not_col_R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::D, Adjacency::M];
+    let cutoff_functions: Vec<Named<CutoffFn>> = named![size_limitation, non_division];
+    let cutoff_functions_for_different: Vec<Named<CutoffFn>> = named![size_limitation_n];
+    let A = combine_on_board(R, not_R, &C, &cutoff_functions, &board);
+    let row_A = combine_on_board(
+        row_R,
+        not_row_R,
+        &C,
+        &cutoff_functions_for_different,
+        &board,
+    );
+    let col_A = combine_on_board(
+        col_R,
+        not_col_R,
+        &C,
+        &cutoff_functions_for_different,
+        &board,
+    );
 
     // combineの確認---------------------------
     // println!("{:?}", row_A.len());
@@ -98,114 +100,68 @@ fn main() {
     let C_domain: Vec<Option<i32>> = (1..=n).map(Some).collect();
     let Ep_domain: Vec<Option<i32>> = vec![None];
     let Ec_domain: Vec<Option<i32>> = vec![None];
-    let A_domain: Vec<Option<i32>> = vec![None];
-
-    let P_domain_size = P_domain.len();
-    let C_domain_size = C_domain.len();
-    let Ep_domain_size = Ep_domain.len();
-    let Ec_domain_size = Ec_domain.len();
-    let A_domain_size = A_domain.len();
-
-    let total_combinations_P = P_domain_size.pow(P.len() as u32);
-    let total_combinations_Ep = Ep_domain_size.pow(Ep.len() as u32);
-    let total_combinations_Ec = P_domain_size.pow(Ec.len() as u32);
-
-    (0..LOOP_NUMBERS).into_par_iter().for_each(|_| {
-        let print_lock_clone = Arc::clone(&print_lock);
-        let mut B = Structure::Composition(Composition::new(vec![]));
-        let mut power_A: Vec<Structure> = vec![];
-
-        let board_validation_fn: Vec<BoardValidationFn> = vec![
-            BoardValidation::non_matching_structures,
-            BoardValidation::non_horizontal_structures,
-            BoardValidation::non_vertical_structures,
-            BoardValidation::non_diagonal_structures,
-        ];
-
-        let power_A = OperateStructure::random_subset_with_validation(&A, &board_validation_fn);
-
-        let readonly_C = C.clone();
-        let mut pseudo_C = Structure::Composition(Composition::new(readonly_C));
+
+    let board_validation_fn: Vec<Named<BoardValidationFn>> = named![
+        BoardValidation::non_matching_structures,
+        BoardValidation::non_horizontal_structures,
+        BoardValidation::non_vertical_structures,
+        BoardValidation::non_diagonal_structures,
+    ];
+
+    let driver = Driver::new(
+        "hitori",
+        Domains::new(P_domain, C_domain, Ep_domain, Ec_domain),
+    );
+
+    // 黒マス以外のセルに数字を割り当てる
+    let layout_fn = |_: u64, rng: &mut StdRng| {
+        let power_A =
+            OperateStructure::random_subset_with_validation(&A, &board_validation_fn, rng);
+
+        let mut pseudo_C = Structure::Composition(Composition::new(C.clone()));
         for area in power_A.iter() {
             pseudo_C = OperateStructure::subtract_structures(&pseudo_C, area);
         }
-
-        (0..total_combinations_P).into_par_iter().for_each(|pi| {
-            let mut independent_P = P.clone();
-            let mut index_pi = pi;
-
-            for structure_p in independent_P.iter_mut() {
-                if let Structure::Element(ref mut point) = structure_p {
-                    let digit = index_pi % P_domain_size;
-                    index_pi /= P_domain_size;
-                    point.val = P_domain[digit];
-                }
+        if let Structure::Composition(pseudo_C_content) = pseudo_C {
+            return Some(Layout::new(power_A, pseudo_C_content.entity));
+        } else {
+            unreachable!()
+        }
+    };
+
+    if driver.counting() {
+        driver.count(LOOP_NUMBERS, layout_fn, |_, independent_C| {
+            let mut constraints = vec![];
+            for row in row_A.iter() {
+                let scope = Constraint::scope_of(independent_C, row);
+                constraints.extend(Constraint::all_different("all_different(row)", &scope));
             }
-
-            (0..total_combinations_Ep).into_par_iter().for_each(|epi| {
-                let mut independent_Ep = Ep.clone();
-                let mut index_epi = epi;
-
-                for structure_ep in independent_Ep.iter_mut() {
-                    if let Structure::Element(ref mut ep_content) = structure_ep {
-                        let digit = index_epi % Ep_domain_size;
-                        index_epi /= Ep_domain_size;
-                        ep_content.val = Ep_domain[digit];
-                    }
-                }
-
-                (0..total_combinations_Ec).into_par_iter().for_each(|eci| {
-                    let mut independent_Ec = Ec.clone();
-                    let mut index_eci = eci;
-
-                    for structure_ec in independent_Ec.iter_mut() {
-                        if let Structure::Element(ref mut ec_content) = structure_ec {
-                            let digit = index_eci % Ec_domain_size;
-                            index_eci /= Ec_domain_size;
-                            ec_content.val = Ec_domain[digit];
-                        }
-                    }
-                    if let Structure::Composition(ref pseudo_C_content) = pseudo_C {
-                        let total_combinations_C =
-                            C_domain_size.pow(pseudo_C_content.entity.len() as u32);
-                        (0..total_combinations_C).into_par_iter().for_each(|ci| {
-                            let mut index_ci = ci;
-                            if let Structure::Composition(ref pseudo_C_content) = pseudo_C {
-                                let mut independent_C = pseudo_C_content.clone();
-                                let mut success = true;
-
-                                for structure_c in independent_C.entity.iter_mut() {
-                                    if let Structure::Element(ref mut c_content) = structure_c {
-                                        let digit = index_ci % C_domain_size;
-                                        index_ci /= C_domain_size;
-                                        c_content.val = C_domain[digit];
-                                    }
-                                }
-                                for row in row_A.iter() {
-                                    if !Predicates::all_different(&independent_C.entity, row) {
-                                        success = false;
-                                    }
-                                }
-                                for col in col_A.iter() {
-                                    if !Predicates::all_different(&independent_C.entity, col) {
-                                        success = false;
-                                    }
-                                }
-
-                                if success {
-                                    let _lock = print_lock_clone.lock().unwrap();
-                                    println!("black: {:?} {:?}", power_A.len(), power_A);
-                                    println!("{:?}", independent_C);
-                                    println!("");
-                                }
-                            }
-                        })
-                    }
-                })
-            })
+            for col in col_A.iter() {
+                let scope = Constraint::scope_of(independent_C, col);
+                constraints.extend(Constraint::all_different("all_different(col)", &scope));
+            }
+            return constraints;
         });
+        return;
+    }
 
-        pb.inc(1);
+    driver.run(LOOP_NUMBERS, layout_fn, |power_A, independent_C| {
+        let assignment = Assignment::from_structures(&board_size, &[independent_C]);
+        for row in row_A.iter() {
+            Driver::require(
+                "all_different(row)",
+                Predicates::all_different(&assignment, row),
+            )?;
+        }
+        for col in col_A.iter() {
+            Driver::require(
+                "all_different(col)",
+                Predicates::all_different(&assignment, col),
+            )?;
+        }
+        let mut record = BoardRecord::new("hitori", &board_size);
+        record.add_elements(independent_C);
+        record.add_areas("black", power_A);
+        return Ok(record);
     });
-    pb.finish();
 }
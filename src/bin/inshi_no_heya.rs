@@ -3,24 +3,22 @@
 
 //長方形のサイズは現実的にsqrt(n*m)程度なのでcut-off
 
-use indicatif::{ProgressBar, ProgressStyle};
-use puzzle_check::common::combine::combine;
-use puzzle_check::common::initialize::initialize;
+use puzzle_check::common::assignment::Assignment;
+use puzzle_check::common::board::{Adjacency, Board};
+use puzzle_check::common::combine::combine_on_board;
+use puzzle_check::common::count::Constraint;
+use puzzle_check::common::driver::{Domains, Driver, Layout};
 use puzzle_check::common::operate_structures::OperateStructure;
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::serialize::BoardRecord;
+use puzzle_check::named;
 use puzzle_check::specific::predicates::Predicates;
-use puzzle_check::specific::structure_functions::StructureFn;
 
-use puzzle_check::common::dataclass::{
-    Attribute, BoardSize, Composition, Coordinate, Element, Structure,
-};
-use puzzle_check::common::relationship::{relationship, Relationship, D, H, M, V};
-use puzzle_check::specific::board_validation::{BoardValidation, BoardValidationFn};
+use puzzle_check::common::dataclass::{BoardSize, Composition, Structure};
+use puzzle_check::common::relationship::{relationship, M};
 use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
 
-use rayon::prelude::*;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use rand::rngs::StdRng;
 
 const n: i32 = 3;
 const board_size: BoardSize = BoardSize(n, n);
@@ -35,30 +33,22 @@ fn size_limitation_n(area: &Structure) -> bool {
 }
 
 fn main() {
-    let pb = ProgressBar::new(LOOP_NUMBERS);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("main    {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
-            .unwrap(),
-    );
-
-    let print_lock = Arc::new(Mutex::new(()));
-
-    let (P, C, Ep, Ec) = initialize(&board_size);
+    let board = Board::new(&board_size);
+    let (_, C, _, _) = board.parts();
 
     // ----------------------------------------------------------------------
-    let room_R: Vec<Relationship> = vec![H, V];
-    let not_room_R: Vec<Relationship> = vec![M];
-    let cutoff_functions_for_room: Vec<CutoffFn> = vec![Cutoff::is_rectangle];
-    let row_R: Vec<Relationship> = vec![H];
-    let not_row_R: Vec<Relationship> = vec![D, V, M];
-    let col_R: Vec<Relationship> = vec![V];
-    let not_col_R: Vec<Relationship> = vec![H, D, M];
-    let cutoff_functions_for_row: Vec<CutoffFn> = vec![size_limitation_n];
-    let cutoff_functions_for_col: Vec<CutoffFn> = vec![size_limitation_n];
-    let room_A = combine(room_R, not_room_R, &C, &cutoff_functions_for_room);
-    let row_A = combine(row_R, not_row_R, &C, &cutoff_functions_for_row);
-    let col_A = combine(col_R, not_col_R, &C, &cutoff_functions_for_col);
+    let room_R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::V];
+    let not_room_R: Vec<Adjacency> = vec![Adjacency::M];
+    let cutoff_functions_for_room: Vec<Named<CutoffFn>> = named![Cutoff::is_rectangle];
+    let row_R: Vec<Adjacency> = vec![Adjacency::H];
+    let not_row_R: Vec<Adjacency> = vec![Adjacency::D, Adjacency::V, Adjacency::M];
+    let col_R: Vec<Adjacency> = vec![Adjacency::V];
+    let not_col_R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::D, Adjacency::M];
+    let cutoff_functions_for_row: Vec<Named<CutoffFn>> = named![size_limitation_n];
+    let cutoff_functions_for_col: Vec<Named<CutoffFn>> = named![size_limitation_n];
+    let room_A = combine_on_board(room_R, not_room_R, &C, &cutoff_functions_for_room, &board);
+    let row_A = combine_on_board(row_R, not_row_R, &C, &cutoff_functions_for_row, &board);
+    let col_A = combine_on_board(col_R, not_col_R, &C, &cutoff_functions_for_col, &board);
 
     // combineの確認---------------------------
     // println!("{:?}", A.len());
@@ -67,132 +57,80 @@ fn main() {
     // }
     // ---------------------------------------
 
-    let board_validation_functions: Vec<BoardValidationFn> = vec![BoardValidation::non_validation];
-
-    let max_a = 99999;
-
     let P_domain: Vec<Option<i32>> = vec![None];
     let C_domain: Vec<Option<i32>> = (1..=n).map(Some).collect();
     let Ep_domain: Vec<Option<i32>> = vec![None];
     let Ec_domain: Vec<Option<i32>> = vec![None];
-    let A_domain: Vec<Option<i32>> = (0..=max_a).map(Some).collect();
-
-    let P_domain_size = P_domain.len();
-    let C_domain_size = C_domain.len();
-    let Ep_domain_size = Ep_domain.len();
-    let Ec_domain_size = Ec_domain.len();
-    let A_domain_size = A_domain.len();
-
-    let total_combinations_P = P_domain_size.pow(P.len() as u32);
-    let total_combinations_C = C_domain_size.pow(C.len() as u32);
-    let total_combinations_Ep = Ep_domain_size.pow(Ep.len() as u32);
-    let total_combinations_Ec = P_domain_size.pow(Ec.len() as u32);
-
-    (0..LOOP_NUMBERS).into_par_iter().for_each(|_| {
-        pb.inc(1);
-        let print_lock_clone = Arc::clone(&print_lock);
+
+    let driver = Driver::new(
+        "inshi_no_heya",
+        Domains::new(P_domain, C_domain, Ep_domain, Ec_domain),
+    );
+
+    let layout_fn = |_: u64, rng: &mut StdRng| {
         let mut B = Structure::Composition(Composition::new(vec![]));
         let mut power_A: Vec<Structure> = vec![];
-        'inner: for i in 0..100000 {
+        'inner: for _ in 0..100000 {
             if let Structure::Composition(ref B_content) = B {
                 if B_content.entity.len() == C.len() {
                     break 'inner;
                 }
             }
-            let new_area = OperateStructure::extract_random_structure(&room_A);
+            let new_area = OperateStructure::extract_random_structure(&room_A, rng);
             if relationship(&new_area, &B, M) {
                 continue 'inner;
             }
             B = OperateStructure::add_up_structures(&B, &new_area);
             power_A.push(new_area);
         }
-        (0..total_combinations_P).into_par_iter().for_each(|pi| {
-            let mut independent_P = P.clone();
-            let mut index_pi = pi;
-
-            for structure_p in independent_P.iter_mut() {
-                if let Structure::Element(ref mut point) = structure_p {
-                    let digit = index_pi % P_domain_size;
-                    index_pi /= P_domain_size;
-                    point.val = P_domain[digit];
-                }
+        return Some(Layout::new(power_A, C.clone()));
+    };
+
+    if driver.counting() {
+        driver.count(LOOP_NUMBERS, layout_fn, |_, independent_C| {
+            let mut constraints = vec![];
+            for row in row_A.iter() {
+                let scope = Constraint::scope_of(independent_C, row);
+                constraints.extend(Constraint::all_different("all_different(row)", &scope));
             }
-            (0..total_combinations_Ep).into_par_iter().for_each(|epi| {
-                let mut independent_Ep = Ep.clone();
-                let mut index_epi = epi;
-
-                for structure_ep in independent_Ep.iter_mut() {
-                    if let Structure::Element(ref mut ep_content) = structure_ep {
-                        let digit = index_epi % Ep_domain_size;
-                        index_epi /= Ep_domain_size;
-                        ep_content.val = Ep_domain[digit];
-                    }
-                }
+            for col in col_A.iter() {
+                let scope = Constraint::scope_of(independent_C, col);
+                constraints.extend(Constraint::all_different("all_different(col)", &scope));
+            }
+            return constraints;
+        });
+        return;
+    }
+
+    driver.run(LOOP_NUMBERS, layout_fn, |power_A, independent_C| {
+        let assignment = Assignment::from_structures(&board_size, &[independent_C]);
+        for row in row_A.iter() {
+            Driver::require(
+                "all_different(row)",
+                Predicates::all_different(&assignment, row),
+            )?;
+        }
+        for col in col_A.iter() {
+            Driver::require(
+                "all_different(col)",
+                Predicates::all_different(&assignment, col),
+            )?;
+        }
+
+        // 部屋の値は部屋に含まれる数字の積
+        let mut independent_power_A = power_A.clone();
+        for area in independent_power_A.iter_mut() {
+            let value = assignment
+                .values_of(area)
+                .iter()
+                .map(|val| val.unwrap())
+                .product();
+            area.set_val(Some(value));
+        }
 
-                (0..total_combinations_Ec).into_par_iter().for_each(|eci| {
-                    let mut independent_Ec = Ec.clone();
-                    let mut index_eci = eci;
-
-                    for structure_ec in independent_Ec.iter_mut() {
-                        if let Structure::Element(ref mut ec_content) = structure_ec {
-                            let digit = index_eci % Ec_domain_size;
-                            index_eci /= Ec_domain_size;
-                            ec_content.val = Ec_domain[digit];
-                        }
-                    }
-
-                    (0..total_combinations_C).into_par_iter().for_each(|ci| {
-                        let mut independent_C = C.clone();
-                        let mut index_ci = ci;
-                        let mut success = true;
-
-                        for structure_c in independent_C.iter_mut() {
-                            if let Structure::Element(ref mut c_content) = structure_c {
-                                let digit = index_ci % C_domain_size;
-                                index_ci /= C_domain_size;
-                                c_content.val = C_domain[digit];
-                            }
-                        }
-                        let mut independent_power_A = power_A.clone();
-                        'inner: for row in row_A.iter() {
-                            if !Predicates::all_different(&independent_C, row) || !success {
-                                success = false;
-                                break 'inner;
-                            }
-                        }
-                        'inner: for col in col_A.iter() {
-                            if !Predicates::all_different(&independent_C, col) || !success {
-                                success = false;
-                                break 'inner;
-                            }
-                        }
-                        if success {
-                            for area in independent_power_A.iter_mut() {
-                                let mut value = 1;
-                                let contains = OperateStructure::extract_contains_structures(
-                                    &independent_C,
-                                    &area,
-                                );
-                                for element in contains.iter() {
-                                    if let Structure::Element(ref element_content) = element {
-                                        value *= element_content.val.unwrap();
-                                    }
-                                }
-                                if let Structure::Composition(ref mut area_content) = area {
-                                    area_content.val = Some(value);
-                                }
-                            }
-
-                            let _lock = print_lock_clone.lock().unwrap();
-
-                            println!("{:?}", independent_power_A);
-                            println!("{:?}", independent_C);
-                            println!("");
-                        }
-                    })
-                })
-            })
-        })
+        let mut record = BoardRecord::new("inshi_no_heya", &board_size);
+        record.add_elements(independent_C);
+        record.add_areas("room", &independent_power_A);
+        return Ok(record);
     });
-    pb.finish();
 }
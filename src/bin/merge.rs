@@ -0,0 +1,82 @@
+// name: merge
+// 分割して実行したシャードの出力を統合する
+// usage: merge <puzzle_name> <k>
+// data/<puzzle_name>.shard-<i>-of-<k>.summary と data/<puzzle_name>.shard-<i>-of-<k>.txt (0 <= i < k) を読み込み,
+// 全てのシャードが揃っていることを確認してから, 盤面を標準出力に, 件数を標準エラー出力に出力する
+
+use puzzle_check::common::shard::ShardSummary;
+use std::fs;
+use std::io::Write;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() != 2 {
+        eprintln!("usage: merge <puzzle_name> <k>");
+        std::process::exit(2);
+    }
+    let rule = &args[0];
+    let count: u64 = match args[1].parse() {
+        Ok(count) if count > 0 => count,
+        _ => {
+            eprintln!("invalid shard count '{}'", args[1]);
+            std::process::exit(2);
+        }
+    };
+
+    let mut summaries: Vec<ShardSummary> = vec![];
+    let mut outputs: Vec<String> = vec![];
+    for index in 0..count {
+        let name = ShardSummary::name(rule, index, count);
+        let summary = match ShardSummary::load(&format!("data/{}.summary", name)) {
+            Ok(summary) => summary,
+            Err(message) => {
+                eprintln!("{} (has shard {}/{} finished?)", message, index, count);
+                std::process::exit(1);
+            }
+        };
+        let output_path = format!("data/{}.txt", name);
+        let output = match fs::read_to_string(&output_path) {
+            Ok(output) => output,
+            Err(e) => {
+                eprintln!("{}: {}", output_path, e);
+                std::process::exit(1);
+            }
+        };
+        let lines = output.lines().filter(|line| !line.is_empty()).count() as u64;
+        if lines != summary.accepted {
+            eprintln!(
+                "{}: {} boards but the summary reports {}",
+                output_path, lines, summary.accepted
+            );
+            std::process::exit(1);
+        }
+        summaries.push(summary);
+        outputs.push(output);
+    }
+
+    let accepted = match ShardSummary::merge(&summaries) {
+        Ok(accepted) => accepted,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    };
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for output in outputs.iter() {
+        for line in output.lines().filter(|line| !line.is_empty()) {
+            writeln!(out, "{}", line).unwrap();
+        }
+    }
+    for summary in summaries.iter() {
+        eprintln!(
+            "shard {}/{}: {}",
+            summary.index, summary.count, summary.accepted
+        );
+    }
+    eprintln!(
+        "{}: {} boards from {} candidates (seed {})",
+        rule, accepted, summaries[0].total, summaries[0].seed
+    );
+}
@@ -1,23 +1,22 @@
 // label: cut-off, sparce expected
 // name: norinori
 
-use indicatif::{ProgressBar, ProgressStyle};
-use puzzle_check::common::combine::combine;
-use puzzle_check::common::initialize::initialize;
+use puzzle_check::common::assignment::Assignment;
+use puzzle_check::common::board::{Adjacency, Board};
+use puzzle_check::common::combine::combine_on_board;
+use puzzle_check::common::count::Constraint;
+use puzzle_check::common::driver::{Domains, Driver, Layout};
 use puzzle_check::common::operate_structures::OperateStructure;
-use puzzle_check::specific::structure_functions::StructureFn;
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::serialize::BoardRecord;
+use puzzle_check::named;
 
-use puzzle_check::common::dataclass::{
-    Attribute, BoardSize, Composition, Coordinate, Element, Structure,
-};
-use puzzle_check::common::relationship::{relationship, Relationship, D, H, M, V};
+use puzzle_check::common::dataclass::{BoardSize, Composition, Structure};
+use puzzle_check::common::relationship::{relationship, M};
 use puzzle_check::specific::board_validation::{BoardValidation, BoardValidationFn};
 use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
 
-use rayon::prelude::*;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use rand::rngs::StdRng;
 
 fn size_limitation(area: &Structure) -> bool {
     if let Structure::Composition(ref area_content) = area {
@@ -33,25 +32,35 @@ const board_size: BoardSize = BoardSize(n, m);
 const LOOP_NUMBERS: u64 = 1000;
 const black: i32 = 1;
 
-fn main() {
-    let pb = ProgressBar::new(LOOP_NUMBERS);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("main    {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
-            .unwrap(),
-    );
+// 各部屋にちょうど2つの黒マスが含まれることを確認する
+fn two_black_cells_per_room(power_room_A: &Vec<Structure>, independent_C: &Vec<Structure>) -> bool {
+    let assignment = Assignment::from_structures(&board_size, &[independent_C]);
+    for room_A in power_room_A.iter() {
+        let black_numbers = assignment
+            .values_of(room_A)
+            .iter()
+            .filter(|&&val| val == Some(black))
+            .count();
+        if black_numbers != 2 {
+            return false;
+        }
+    }
+    return true;
+}
 
-    let (P, C, Ep, Ec) = initialize(&board_size);
+fn main() {
+    let board = Board::new(&board_size);
+    let (_, C, _, _) = board.parts();
 
     // ----------------------------------------------------------------------
-    let room_R: Vec<Relationship> = vec![H, V];
-    let room_not_R: Vec<Relationship> = vec![M];
-    let black_R: Vec<Relationship> = vec![H, V];
-    let black_not_R: Vec<Relationship> = vec![M];
-    let cutoff_functions_room: Vec<CutoffFn> = vec![Cutoff::non_cutoff];
-    let cutoff_functions_black: Vec<CutoffFn> = vec![size_limitation];
-    let room_A = combine(room_R, room_not_R, &C, &cutoff_functions_room);
-    let black_A = combine(black_R, black_not_R, &C, &cutoff_functions_black);
+    let room_R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::V];
+    let room_not_R: Vec<Adjacency> = vec![Adjacency::M];
+    let black_R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::V];
+    let black_not_R: Vec<Adjacency> = vec![Adjacency::M];
+    let cutoff_functions_room: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+    let cutoff_functions_black: Vec<Named<CutoffFn>> = named![size_limitation];
+    let room_A = combine_on_board(room_R, room_not_R, &C, &cutoff_functions_room, &board);
+    let black_A = combine_on_board(black_R, black_not_R, &C, &cutoff_functions_black, &board);
 
     // combineの確認---------------------------
     // println!("{:?}", room_A.len());
@@ -65,32 +74,27 @@ fn main() {
     // }
     // ---------------------------------------
 
-    let board_validation_functions: Vec<BoardValidationFn> = vec![BoardValidation::non_validation];
-
     let P_domain: Vec<Option<i32>> = vec![None];
     let C_domain: Vec<Option<i32>> = vec![None];
     let Ep_domain: Vec<Option<i32>> = vec![None];
     let Ec_domain: Vec<Option<i32>> = vec![None];
-    let room_A_domain: Vec<Option<i32>> = vec![None];
-    let black_A_domain: Vec<Option<i32>> = vec![None];
-
-    let P_domain_size = P_domain.len();
-    let C_domain_size = C_domain.len();
-    let Ep_domain_size = Ep_domain.len();
-    let Ec_domain_size = Ec_domain.len();
-    let room_A_domain_size = room_A_domain.len();
-    let blackA_domain_size = black_A_domain.len();
-
-    let total_combinations_P = P_domain_size.pow(P.len() as u32);
-    let total_combinations_C = C_domain_size.pow(C.len() as u32);
-    let total_combinations_Ep = Ep_domain_size.pow(Ep.len() as u32);
-    let total_combinations_Ec = P_domain_size.pow(Ec.len() as u32);
-
-    (0..LOOP_NUMBERS).into_par_iter().for_each(|_| {
+
+    let board_validation_fn: Vec<Named<BoardValidationFn>> = named![
+        BoardValidation::non_matching_structures,
+        BoardValidation::non_horizontal_structures,
+        BoardValidation::non_vertical_structures,
+    ];
+
+    let driver = Driver::new(
+        "norinori",
+        Domains::new(P_domain, C_domain, Ep_domain, Ec_domain),
+    );
+
+    let layout_fn = |_: u64, rng: &mut StdRng| {
         let mut room_B = Structure::Composition(Composition::new(vec![]));
         let mut power_room_A: Vec<Structure> = vec![];
         let mut next = false;
-        'inner: for i in 0..1000 {
+        'inner: for _ in 0..1000 {
             if let Structure::Composition(ref room_B_content) = room_B {
                 if room_B_content.entity.len() == C.len() {
                     next = true;
@@ -98,7 +102,7 @@ fn main() {
                 }
             }
 
-            let new_area = OperateStructure::extract_random_structure(&room_A);
+            let new_area = OperateStructure::extract_random_structure(&room_A, rng);
 
             if relationship(&new_area, &room_B, M) {
                 continue 'inner;
@@ -106,101 +110,49 @@ fn main() {
             room_B = OperateStructure::add_up_structures(&room_B, &new_area);
             power_room_A.push(new_area);
         }
-        let board_validation_fn: Vec<BoardValidationFn> = vec![
-            BoardValidation::non_matching_structures,
-            BoardValidation::non_horizontal_structures,
-            BoardValidation::non_vertical_structures,
-        ];
-        let mut power_black_A =
-            OperateStructure::random_subset_with_validation(&black_A, &board_validation_fn);
-
-        let mut independent_C = C.clone();
-
-        for black_area in power_black_A.iter_mut() {
-            if let Structure::Composition(ref mut black_area_content) = black_area {
-                for cell in black_area_content.entity.iter() {
-                    for compare_cell in independent_C.iter_mut() {
-                        if OperateStructure::compare_structures(cell, &compare_cell) {
-                            if let Structure::Element(ref mut compare_cell_content) = compare_cell {
-                                compare_cell_content.val = Some(black);
-                            }
-                        }
-                    }
-                }
-            }
+        let power_black_A =
+            OperateStructure::random_subset_with_validation(&black_A, &board_validation_fn, rng);
+        if !next {
+            return None;
         }
-        if next {
-            (0..total_combinations_P).into_par_iter().for_each(|pi| {
-                let mut independent_P = P.clone();
-                let mut index_pi = pi;
-
-                for structure_p in independent_P.iter_mut() {
-                    if let Structure::Element(ref mut point) = structure_p {
-                        let digit = index_pi % P_domain_size;
-                        index_pi /= P_domain_size;
-                        point.val = P_domain[digit];
-                    }
-                }
 
-                (0..total_combinations_Ep).into_par_iter().for_each(|epi| {
-                    let mut independent_Ep = Ep.clone();
-                    let mut index_epi = epi;
-
-                    for structure_ep in independent_Ep.iter_mut() {
-                        if let Structure::Element(ref mut ep_content) = structure_ep {
-                            let digit = index_epi % Ep_domain_size;
-                            index_epi /= Ep_domain_size;
-                            ep_content.val = Ep_domain[digit];
-                        }
-                    }
-
-                    (0..total_combinations_Ec).into_par_iter().for_each(|eci| {
-                        let mut independent_Ec = Ec.clone();
-                        let mut index_eci = eci;
-
-                        for structure_ec in independent_Ec.iter_mut() {
-                            if let Structure::Element(ref mut ec_content) = structure_ec {
-                                let digit = index_eci % Ec_domain_size;
-                                index_eci /= Ec_domain_size;
-                                ec_content.val = Ec_domain[digit];
-                            }
-                        }
-
-                        let mut success = true;
-                        'inner: for room_A in power_room_A.iter() {
-                            let mut black_numbers = 0;
-                            if let Structure::Composition(ref room_A_content) = room_A {
-                                for cell in room_A_content.entity.iter() {
-                                    for compare_cell in independent_C.iter() {
-                                        if OperateStructure::compare_structures(cell, compare_cell)
-                                        {
-                                            if let Structure::Element(ref compare_cell_content) =
-                                                compare_cell
-                                            {
-                                                if compare_cell_content.val == Some(black) {
-                                                    black_numbers += 1;
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            if black_numbers != 2 {
-                                success = false;
-                                break 'inner;
-                            }
-                        }
-
-                        if success {
-                            println!("room: {:?} {:?}", power_room_A.len(), power_room_A);
-                            println!("black: {:?} {:?}", power_black_A.len(), power_black_A);
-                            println!("");
-                        }
-                    })
-                })
-            })
+        let mut assignment = Assignment::new(&board_size);
+        for black_area in power_black_A.iter() {
+            assignment.fill(black_area, Some(black));
         }
-        pb.inc(1);
-    });
-    pb.finish();
+        let independent_C = assignment.apply(&C);
+        return Some(Layout::new(
+            (power_room_A, power_black_A, independent_C),
+            vec![],
+        ));
+    };
+
+    if driver.counting() {
+        driver.count(
+            LOOP_NUMBERS,
+            layout_fn,
+            |(power_room_A, _, independent_C), _| {
+                vec![Constraint::new("two_black_cells_per_room", vec![], |_| {
+                    two_black_cells_per_room(power_room_A, independent_C)
+                })]
+            },
+        );
+        return;
+    }
+
+    driver.run(
+        LOOP_NUMBERS,
+        layout_fn,
+        |(power_room_A, power_black_A, independent_C), _| {
+            Driver::require(
+                "two_black_cells_per_room",
+                two_black_cells_per_room(power_room_A, independent_C),
+            )?;
+
+            let mut record = BoardRecord::new("norinori", &board_size);
+            record.add_areas("room", power_room_A);
+            record.add_areas("black", power_black_A);
+            return Ok(record);
+        },
+    );
 }
@@ -3,23 +3,19 @@
 
 //長方形のサイズは現実的にn*m/2程度なのでcut-off
 
-use indicatif::{ProgressBar, ProgressStyle};
-use puzzle_check::common::combine::combine;
-use puzzle_check::common::initialize::initialize;
+use puzzle_check::common::board::{Adjacency, Board};
+use puzzle_check::common::combine::combine_on_board;
+use puzzle_check::common::driver::{Domains, Driver, Layout};
 use puzzle_check::common::operate_structures::OperateStructure;
-use puzzle_check::specific::structure_functions::StructureFn;
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::serialize::BoardRecord;
+use puzzle_check::named;
 
-use puzzle_check::common::dataclass::{
-    Attribute, BoardSize, Composition, Coordinate, Element, Structure,
-};
-use puzzle_check::common::relationship::{relationship, Relationship, D, H, M, V};
-use puzzle_check::specific::board_validation::{BoardValidation, BoardValidationFn};
+use puzzle_check::common::dataclass::{BoardSize, Composition, Structure};
+use puzzle_check::common::relationship::{relationship, M};
 use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
 
-use rayon::prelude::*;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use rand::rngs::StdRng;
 
 const n: i32 = 4;
 const m: i32 = 4;
@@ -27,20 +23,14 @@ const board_size: BoardSize = BoardSize(n, m);
 const LOOP_NUMBERS: u64 = 1000;
 
 fn main() {
-    let pb = ProgressBar::new(LOOP_NUMBERS);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("main    {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
-            .unwrap(),
-    );
-
-    let (P, C, Ep, Ec) = initialize(&board_size);
+    let board = Board::new(&board_size);
+    let (_, C, _, _) = board.parts();
 
     // ----------------------------------------------------------------------
-    let R: Vec<Relationship> = vec![H, V];
-    let not_R: Vec<Relationship> = vec![M];
-    let cutoff_functions: Vec<CutoffFn> = vec![Cutoff::is_rectangle];
-    let A = combine(R, not_R, &C, &cutoff_functions);
+    let R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::V];
+    let not_R: Vec<Adjacency> = vec![Adjacency::M];
+    let cutoff_functions: Vec<Named<CutoffFn>> = named![Cutoff::is_rectangle];
+    let A = combine_on_board(R, not_R, &C, &cutoff_functions, &board);
 
     // combineの確認---------------------------
     // println!("{:?}", A.len());
@@ -49,28 +39,17 @@ fn main() {
     // }
     // ---------------------------------------
 
-    let board_validation_functions: Vec<BoardValidationFn> = vec![BoardValidation::non_validation];
-
-    let max_a = board_size.0 * board_size.1 / 2 as i32;
-
     let P_domain: Vec<Option<i32>> = vec![None];
     let C_domain: Vec<Option<i32>> = vec![None];
     let Ep_domain: Vec<Option<i32>> = vec![None];
     let Ec_domain: Vec<Option<i32>> = vec![None];
-    let A_domain: Vec<Option<i32>> = (0..=max_a).map(Some).collect();
 
-    let P_domain_size = P_domain.len();
-    let C_domain_size = C_domain.len();
-    let Ep_domain_size = Ep_domain.len();
-    let Ec_domain_size = Ec_domain.len();
-    let A_domain_size = A_domain.len();
-
-    let total_combinations_P = P_domain_size.pow(P.len() as u32);
-    let total_combinations_C = C_domain_size.pow(C.len() as u32);
-    let total_combinations_Ep = Ep_domain_size.pow(Ep.len() as u32);
-    let total_combinations_Ec = P_domain_size.pow(Ec.len() as u32);
+    let driver = Driver::new(
+        "shikaku",
+        Domains::new(P_domain, C_domain, Ep_domain, Ec_domain),
+    );
 
-    (0..LOOP_NUMBERS).into_par_iter().for_each(|_| {
+    let layout_fn = |_: u64, rng: &mut StdRng| {
         let mut B = Structure::Composition(Composition::new(vec![]));
         let mut power_A: Vec<Structure> = vec![];
         'inner: loop {
@@ -79,73 +58,31 @@ fn main() {
                     break 'inner;
                 }
             }
-            let new_area = OperateStructure::extract_random_structure(&A);
+            let new_area = OperateStructure::extract_random_structure(&A, rng);
             if relationship(&new_area, &B, M) {
                 continue 'inner;
             }
             B = OperateStructure::add_up_structures(&B, &new_area);
             power_A.push(new_area);
         }
-        (0..total_combinations_P).into_par_iter().for_each(|pi| {
-            let mut independent_P = P.clone();
-            let mut index_pi = pi;
-
-            for structure_p in independent_P.iter_mut() {
-                if let Structure::Element(ref mut point) = structure_p {
-                    let digit = index_pi % P_domain_size;
-                    index_pi /= P_domain_size;
-                    point.val = P_domain[digit];
-                }
+        // 長方形の値は面積
+        for area in power_A.iter_mut() {
+            if let Structure::Composition(ref mut area_content) = area {
+                let size = area_content.entity.len();
+                area_content.val = Some(size as i32);
             }
-            (0..total_combinations_Ep).into_par_iter().for_each(|epi| {
-                let mut independent_Ep = Ep.clone();
-                let mut index_epi = epi;
-
-                for structure_ep in independent_Ep.iter_mut() {
-                    if let Structure::Element(ref mut ep_content) = structure_ep {
-                        let digit = index_epi % Ep_domain_size;
-                        index_epi /= Ep_domain_size;
-                        ep_content.val = Ep_domain[digit];
-                    }
-                }
-
-                (0..total_combinations_Ec).into_par_iter().for_each(|eci| {
-                    let mut independent_Ec = Ec.clone();
-                    let mut index_eci = eci;
-
-                    for structure_ec in independent_Ec.iter_mut() {
-                        if let Structure::Element(ref mut ec_content) = structure_ec {
-                            let digit = index_eci % Ec_domain_size;
-                            index_eci /= Ec_domain_size;
-                            ec_content.val = Ec_domain[digit];
-                        }
-                    }
-
-                    (0..total_combinations_C).into_par_iter().for_each(|ci| {
-                        let mut independent_C = C.clone();
-                        let mut index_ci = ci;
-
-                        for structure_c in independent_C.iter_mut() {
-                            if let Structure::Element(ref mut c_content) = structure_c {
-                                let digit = index_ci % C_domain_size;
-                                index_ci /= C_domain_size;
-                                c_content.val = C_domain[digit];
-                            }
-                        }
-                        let mut independent_power_A = power_A.clone();
-                        for area in independent_power_A.iter_mut() {
-                            if let Structure::Composition(ref mut area_content) = area {
-                                let size = area_content.entity.len();
-                                area_content.val = Some(size as i32);
-                            }
-                        }
-                        pb.inc(1);
-                        println!("{:?}", independent_power_A);
-                        println!("");
-                    })
-                })
-            })
-        })
+        }
+        return Some(Layout::new(power_A, vec![]));
+    };
+
+    if driver.counting() {
+        driver.count(LOOP_NUMBERS, layout_fn, |_, _| vec![]);
+        return;
+    }
+
+    driver.run(LOOP_NUMBERS, layout_fn, |power_A, _| {
+        let mut record = BoardRecord::new("shikaku", &board_size);
+        record.add_areas("room", power_A);
+        return Ok(record);
     });
-    pb.finish();
 }
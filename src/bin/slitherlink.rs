@@ -1,36 +1,34 @@
 // label: cut-off
 // name: slitherlink
 
-use indicatif::{ProgressBar, ProgressStyle};
-use puzzle_check::common::combine::combine;
-use puzzle_check::common::initialize::initialize;
-use puzzle_check::common::operate_structures::OperateStructure;
+use puzzle_check::common::assignment::Assignment;
+use puzzle_check::common::board::{Adjacency, Board};
+use puzzle_check::common::combine::combine_on_board;
+use puzzle_check::common::count::Constraint;
+use puzzle_check::common::driver::{Domains, Driver, Layout};
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::serialize::BoardRecord;
+use puzzle_check::named;
 use puzzle_check::specific::structure_functions::StructureFn;
 
-use puzzle_check::common::dataclass::{
-    Attribute, BoardSize, Composition, Coordinate, Element, Structure,
-};
-use puzzle_check::common::relationship::{relationship, Relationship, D, H, M, V};
-use puzzle_check::specific::board_validation::{BoardValidation, BoardValidationFn};
+use puzzle_check::common::dataclass::BoardSize;
 use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
 
-use rayon::prelude::*;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use rand::rngs::StdRng;
 
 const n: i32 = 4;
 const m: i32 = 4;
 const board_size: BoardSize = BoardSize(n, m);
 
 fn main() {
-    let (P, C, Ep, Ec) = initialize(&board_size);
+    let board = Board::new(&board_size);
+    let (_, C, Ep, _) = board.parts();
 
     // ----------------------------------------------------------------------
-    let R: Vec<Relationship> = vec![H, D, V];
-    let not_R: Vec<Relationship> = vec![M];
-    let cutoff_functions: Vec<CutoffFn> = vec![Cutoff::only_cycle];
-    let G = combine(R, not_R, &Ep, &cutoff_functions);
+    let R: Vec<Adjacency> = vec![Adjacency::H, Adjacency::D, Adjacency::V];
+    let not_R: Vec<Adjacency> = vec![Adjacency::M];
+    let cutoff_functions: Vec<Named<CutoffFn>> = named![Cutoff::only_cycle];
+    let G = combine_on_board(R, not_R, &Ep, &cutoff_functions, &board);
 
     // combineの確認---------------------------
     // println!("{:?}", G.len());
@@ -39,107 +37,61 @@ fn main() {
     // }
     // ---------------------------------------
 
-    let board_validation_functions: Vec<BoardValidationFn> = vec![BoardValidation::non_validation];
-
     let P_domain: Vec<Option<i32>> = vec![None];
     let C_domain: Vec<Option<i32>> = (0..=4).map(Some).collect();
     let Ep_domain: Vec<Option<i32>> = (0..=1).map(Some).collect();
     let Ec_domain: Vec<Option<i32>> = vec![None];
-    let G_domain: Vec<Option<i32>> = vec![None];
-
-    let P_domain_size = P_domain.len();
-    let C_domain_size = C_domain.len();
-    let Ep_domain_size = Ep_domain.len();
-    let Ec_domain_size = Ec_domain.len();
-    let G_domain_size = G_domain.len();
 
-    let total_combinations_P = P_domain_size.pow(P.len() as u32);
-    let total_combinations_C = C_domain_size.pow(C.len() as u32);
-    let total_combinations_Ep = Ep_domain_size.pow(Ep.len() as u32);
-    let total_combinations_Ec = P_domain_size.pow(Ec.len() as u32);
-
-    let pb = ProgressBar::new(
-        (total_combinations_P
-            * total_combinations_C
-            * total_combinations_Ep
-            * total_combinations_Ec) as u64,
-    );
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("main    {bar:40.cyan/blue} {pos}/{len} {percent}% {eta} ")
-            .unwrap(),
+    let driver = Driver::new(
+        "slitherlink",
+        Domains::new(P_domain, C_domain, Ep_domain, Ec_domain),
     );
 
-    (0..total_combinations_P).into_par_iter().for_each(|pi| {
-        let mut independent_P = P.clone();
-        let mut index_pi = pi;
-
-        for structure_p in independent_P.iter_mut() {
-            if let Structure::Element(ref mut point_content) = structure_p {
-                let digit = index_pi % P_domain_size;
-                index_pi /= P_domain_size;
-                point_content.val = P_domain[digit];
-            }
+    // 閉路ごとに辺の値を決め, セルの数字を全て列挙する
+    let layout_fn = |i: u64, _: &mut StdRng| {
+        let graph = &G[i as usize];
+        let mut assignment = Assignment::new(&board_size);
+        for edge in Ep.iter() {
+            assignment.set(edge, Some(0));
         }
-        (0..total_combinations_C).into_par_iter().for_each(|ci| {
-            let mut independent_C = C.clone();
-            let mut index_ci = ci;
-
-            for structure_c in independent_C.iter_mut() {
-                if let Structure::Element(ref mut cell_content) = structure_c {
-                    let digit = index_ci % C_domain_size;
-                    index_ci /= C_domain_size;
-                    cell_content.val = C_domain[digit];
+        assignment.fill(graph, Some(1));
+        let independent_Ep = assignment.apply(&Ep);
+        return Some(Layout::new(independent_Ep, C.clone()));
+    };
+
+    if driver.counting() {
+        driver.count(
+            G.len() as u64,
+            layout_fn,
+            |independent_Ep, independent_C| {
+                // 各セルの数字はまわりの線の本数
+                let mut constraints = vec![];
+                for (k, cell) in independent_C.iter().enumerate() {
+                    let expected = StructureFn::cycle(cell, independent_Ep, &board);
+                    constraints.push(Constraint::new("cycle", vec![k], move |values| {
+                        values[0] == Some(expected)
+                    }));
                 }
+                return constraints;
+            },
+        );
+        return;
+    }
+
+    driver.run(
+        G.len() as u64,
+        layout_fn,
+        |independent_Ep, independent_C| {
+            for cell in independent_C.iter() {
+                Driver::require(
+                    "cycle",
+                    cell.val().unwrap() == StructureFn::cycle(cell, independent_Ep, &board),
+                )?;
             }
-
-            (0..total_combinations_Ec).into_par_iter().for_each(|eci| {
-                let mut independent_Ec = Ec.clone();
-                let mut index_eci = eci;
-
-                for structure_ec in independent_Ec.iter_mut() {
-                    if let Structure::Element(ref mut ec_content) = structure_ec {
-                        let digit = index_eci % Ec_domain_size;
-                        index_eci /= Ec_domain_size;
-                        ec_content.val = Ec_domain[digit];
-                    }
-                }
-
-                'board_reset: for graph in G.iter() {
-                    let mut independent_Ep = Ep.clone();
-                    for structure_ep in independent_Ep.iter_mut() {
-                        {
-                            if let Structure::Composition(ref g_content) = graph {
-                                if g_content.entity.iter().any(|edge| {
-                                    OperateStructure::compare_structures(edge, structure_ep)
-                                }) {
-                                    if let Structure::Element(ref mut ep_content) = structure_ep {
-                                        ep_content.val = Some(1);
-                                    }
-                                } else {
-                                    if let Structure::Element(ref mut ep_content) = structure_ep {
-                                        ep_content.val = Some(0);
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    for cell in independent_C.iter() {
-                        if let Structure::Element(ref cell_content) = cell {
-                            if cell_content.val.unwrap()
-                                != StructureFn::cycle(cell, &independent_Ep, &board_size)
-                            {
-                                continue 'board_reset;
-                            }
-                        }
-                    }
-                    println!("{:?}", independent_C);
-                    println!("{:?}", independent_Ep);
-                    println!("");
-                }
-            })
-        })
-    });
-    pb.finish();
+            let mut record = BoardRecord::new("slitherlink", &board_size);
+            record.add_elements(independent_C);
+            record.add_elements(independent_Ep);
+            return Ok(record);
+        },
+    );
 }
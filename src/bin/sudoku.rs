@@ -3,24 +3,22 @@
 
 // disabled for combinatorial explosion
 
-use indicatif::{ProgressBar, ProgressStyle};
-use puzzle_check::common::combine::combine;
-use puzzle_check::common::initialize::initialize;
+use puzzle_check::common::assignment::Assignment;
+use puzzle_check::common::board::{Adjacency, Board};
+use puzzle_check::common::combine::combine_on_board;
+use puzzle_check::common::count::Constraint;
+use puzzle_check::common::driver::{Domains, Driver, Layout};
 use puzzle_check::common::operate_structures::OperateStructure;
+use puzzle_check::common::profile::Named;
+use puzzle_check::common::serialize::BoardRecord;
+use puzzle_check::named;
 use puzzle_check::specific::predicates::Predicates;
-use puzzle_check::specific::structure_functions::StructureFn;
 
-use puzzle_check::common::dataclass::{
-    Attribute, BoardSize, Composition, Coordinate, Element, Structure,
-};
-use puzzle_check::common::relationship::{relationship, Relationship, D, H, M, V};
-use puzzle_check::specific::board_validation::{BoardValidation, BoardValidationFn};
+use puzzle_check::common::dataclass::{BoardSize, Composition, Structure};
+use puzzle_check::common::relationship::{relationship, M};
 use puzzle_check::specific::cutoff::{Cutoff, CutoffFn};
 
-use rayon::prelude::*;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
-use std::thread;
+use rand::rngs::StdRng;
 
 fn size_limitation(area: &Structure) -> bool {
     if let Structure::Composition(ref area_content) = area {
@@ -36,28 +34,22 @@ const board_size: BoardSize = BoardSize(n, m);
 const LOOP_NUMBERS: u64 = 1000;
 
 fn main() {
-    let pb = ProgressBar::new(LOOP_NUMBERS);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("main    {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
-            .unwrap(),
-    );
-
-    let (P, C, Ep, Ec) = initialize(&board_size);
+    let board = Board::new(&board_size);
+    let (_, C, _, _) = board.parts();
 
     // ----------------------------------------------------------------------
-    let R1: Vec<Relationship> = vec![H];
-    let R2: Vec<Relationship> = vec![V];
-    let R3: Vec<Relationship> = vec![H, V];
-    let not_R1: Vec<Relationship> = vec![M];
-    let not_R2: Vec<Relationship> = vec![M];
-    let not_R3: Vec<Relationship> = vec![M];
-    let cutoff_functions1: Vec<CutoffFn> = vec![size_limitation];
-    let cutoff_functions2: Vec<CutoffFn> = vec![size_limitation];
-    let cutoff_functions3: Vec<CutoffFn> = vec![size_limitation, Cutoff::is_square];
-    let A1 = combine(R1, not_R1, &C, &cutoff_functions1);
-    let A2 = combine(R2, not_R2, &C, &cutoff_functions2);
-    let A3 = combine(R3, not_R3, &C, &cutoff_functions3);
+    let R1: Vec<Adjacency> = vec![Adjacency::H];
+    let R2: Vec<Adjacency> = vec![Adjacency::V];
+    let R3: Vec<Adjacency> = vec![Adjacency::H, Adjacency::V];
+    let not_R1: Vec<Adjacency> = vec![Adjacency::M];
+    let not_R2: Vec<Adjacency> = vec![Adjacency::M];
+    let not_R3: Vec<Adjacency> = vec![Adjacency::M];
+    let cutoff_functions1: Vec<Named<CutoffFn>> = named![size_limitation];
+    let cutoff_functions2: Vec<Named<CutoffFn>> = named![size_limitation];
+    let cutoff_functions3: Vec<Named<CutoffFn>> = named![size_limitation, Cutoff::is_square];
+    let A1 = combine_on_board(R1, not_R1, &C, &cutoff_functions1, &board);
+    let A2 = combine_on_board(R2, not_R2, &C, &cutoff_functions2, &board);
+    let A3 = combine_on_board(R3, not_R3, &C, &cutoff_functions3, &board);
 
     // combineの確認---------------------------
     // println!("{:?}", A.len());
@@ -66,133 +58,62 @@ fn main() {
     // }
     // ---------------------------------------
 
-    let board_validation_functions: Vec<BoardValidationFn> = vec![BoardValidation::non_validation];
-
     let P_domain: Vec<Option<i32>> = vec![None];
     let C_domain: Vec<Option<i32>> = (0..=4).map(Some).collect();
     let Ep_domain: Vec<Option<i32>> = vec![None];
     let Ec_domain: Vec<Option<i32>> = vec![None];
-    let A_domain: Vec<Option<i32>> = vec![None];
-
-    let P_domain_size = P_domain.len();
-    let C_domain_size = C_domain.len();
-    let Ep_domain_size = Ep_domain.len();
-    let Ec_domain_size = Ec_domain.len();
-    let A_domain_size = A_domain.len();
 
-    let total_combinations_P = P_domain_size.pow(P.len() as u32);
-    let total_combinations_C = C_domain_size.pow(C.len() as u32);
-    let total_combinations_Ep = Ep_domain_size.pow(Ep.len() as u32);
-    let total_combinations_Ec = P_domain_size.pow(Ec.len() as u32);
-
-    let progress_size = total_combinations_P
-        * total_combinations_C
-        * total_combinations_Ep
-        * total_combinations_Ec
-        * LOOP_NUMBERS as usize;
-    let pb = ProgressBar::new(progress_size as u64);
+    let driver = Driver::new(
+        "sudoku",
+        Domains::new(P_domain, C_domain, Ep_domain, Ec_domain),
+    );
 
-    (0..LOOP_NUMBERS).into_par_iter().for_each(|_| {
+    let layout_fn = |_: u64, rng: &mut StdRng| {
         let mut B = Structure::Composition(Composition::new(vec![]));
         let mut power_A: Vec<Structure> = vec![];
         let mut next = false;
-        'inner: for i in 0..1000 {
+        'inner: for _ in 0..1000 {
             if let Structure::Composition(ref B_content) = B {
                 if B_content.entity.len() == C.len() {
                     next = true;
                     break 'inner;
                 }
             }
-            let new_area = OperateStructure::extract_random_structure(&A3);
+            let new_area = OperateStructure::extract_random_structure(&A3, rng);
             if relationship(&new_area, &B, M) {
                 continue 'inner;
             }
             B = OperateStructure::add_up_structures(&B, &new_area);
             power_A.push(new_area);
         }
-        if next {
-            (0..total_combinations_P).into_par_iter().for_each(|pi| {
-                let mut independent_P = P.clone();
-                let mut index_pi = pi;
-
-                for structure_p in independent_P.iter_mut() {
-                    if let Structure::Element(ref mut point_content) = structure_p {
-                        let digit = index_pi % P_domain_size;
-                        index_pi /= P_domain_size;
-                        point_content.val = P_domain[digit];
-                    }
-                }
-                (0..total_combinations_Ep).into_par_iter().for_each(|epi| {
-                    let mut independent_Ep = Ep.clone();
-                    let mut index_epi = epi;
-
-                    for structure_ep in independent_Ep.iter_mut() {
-                        if let Structure::Element(ref mut ep_content) = structure_ep {
-                            let digit = index_epi % Ep_domain_size;
-                            index_epi /= Ep_domain_size;
-                            ep_content.val = Ep_domain[digit];
-                        }
-                    }
-
-                    (0..total_combinations_Ec).into_par_iter().for_each(|eci| {
-                        let mut independent_Ec = Ec.clone();
-                        let mut index_eci = eci;
-
-                        for structure_ec in independent_Ec.iter_mut() {
-                            if let Structure::Element(ref mut ec_content) = structure_ec {
-                                let digit = index_eci % Ec_domain_size;
-                                index_eci /= Ec_domain_size;
-                                ec_content.val = Ec_domain[digit];
-                            }
-                        }
-
-                        (0..total_combinations_C).into_par_iter().for_each(|ci| {
-                            let mut independent_C = C.clone();
-                            let mut index_ci = ci;
-
-                            for structure_c in independent_C.iter_mut() {
-                                if let Structure::Element(ref mut c_content) = structure_c {
-                                    let digit = index_ci % C_domain_size;
-                                    index_ci /= C_domain_size;
-                                    c_content.val = C_domain[digit];
-                                }
-                            }
+        if !next {
+            return None;
+        }
+        return Some(Layout::new(power_A, C.clone()));
+    };
+
+    if driver.counting() {
+        driver.count(LOOP_NUMBERS, layout_fn, |power_A, independent_C| {
+            let mut constraints = vec![];
+            for area in power_A.iter().chain(A1.iter()).chain(A2.iter()) {
+                let scope = Constraint::scope_of(independent_C, area);
+                constraints.extend(Constraint::all_different("all_different", &scope));
+            }
+            return constraints;
+        });
+        return;
+    }
 
-                            let mut is_correct = true;
-                            'inner: for area in power_A.iter() {
-                                if !is_correct {
-                                    break 'inner;
-                                }
-                                if !Predicates::all_different(&independent_C, area) {
-                                    is_correct = false;
-                                }
-                            }
-                            'inner: for area in A1.iter() {
-                                if !is_correct {
-                                    break 'inner;
-                                }
-                                if !Predicates::all_different(&independent_C, area) {
-                                    is_correct = false;
-                                }
-                            }
-                            'inner: for area in A2.iter() {
-                                if !is_correct {
-                                    break 'inner;
-                                }
-                                if !Predicates::all_different(&independent_C, area) {
-                                    is_correct = false;
-                                }
-                            }
-                            if is_correct {
-                                println!("{:?}", independent_C);
-                                println!("");
-                            }
-                            pb.inc(1);
-                        })
-                    })
-                })
-            })
+    driver.run(LOOP_NUMBERS, layout_fn, |power_A, independent_C| {
+        let assignment = Assignment::from_structures(&board_size, &[independent_C]);
+        for area in power_A.iter().chain(A1.iter()).chain(A2.iter()) {
+            Driver::require(
+                "all_different",
+                Predicates::all_different(&assignment, area),
+            )?;
         }
+        let mut record = BoardRecord::new("sudoku", &board_size);
+        record.add_elements(independent_C);
+        return Ok(record);
     });
-    pb.finish();
 }
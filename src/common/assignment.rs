@@ -0,0 +1,170 @@
+use crate::common::board::Board;
+use crate::common::dataclass::{BoardSize, Element, Structure};
+use std::sync::Arc;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 盤面の元素への値の割り当て
+// 値は元素の番号（Boardの番号）ごとの列に持ち, 構造体は値を読み書きするキーとして使う
+// 構造体の値を読むときに値の入った元素列と照合し直す必要がなく, 割り当ての複製は値の列の複製だけで済む
+// 構造体と値の分離は途中までで, 元素はまだElement::valに値を持つ:
+//   Driverは定義域の値を空いている元素のvalに書き込んで候補を作り, check_fnにはその元素列を渡す
+//   バイナリは受け取った元素列からfrom_structuresで割り当てを作り, 述語（Predicates::all_different）はそれを読む
+//   レイアウトは割り当てに値を書き込んだ後, applyで元素列のvalに書き戻してDriverに渡す
+//   BoardRecordの出力, Cutoff, BoardValidationは構造体だけを受け取るので, 値は構造体から読む
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone)]
+pub struct Assignment {
+    pub board: Arc<Board>,
+    pub values: Vec<Option<i32>>,
+}
+
+impl Assignment {
+    // 長方形の盤面（Board::shared）の割り当てを作るコンストラクタ, 全ての元素の値はNone
+    pub fn new(board_size: &BoardSize) -> Self {
+        return Assignment::on_board(Board::shared(board_size));
+    }
+
+    // 与えた盤面（穴の空いた盤面, 端の繋がった盤面など）の割り当てを作るコンストラクタ, 全ての元素の値はNone
+    pub fn on_board(board: Arc<Board>) -> Self {
+        let values = vec![None; board.elements.len()];
+        Assignment { board, values }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 長方形の盤面で, 値の入った元素列（independent_Cなど）から割り当てを作る関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面サイズ
+    // structures: &[&Vec<Structure>] - 値の入った元素列（複数可）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Assignment - 元素列の値を写した割り当て
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn from_structures(board_size: &BoardSize, structures: &[&Vec<Structure>]) -> Self {
+        return Assignment::from_structures_on(Board::shared(board_size), structures);
+    }
+
+    // 与えた盤面で, 値の入った元素列から割り当てを作る
+    pub fn from_structures_on(board: Arc<Board>, structures: &[&Vec<Structure>]) -> Self {
+        let mut assignment = Assignment::on_board(board);
+        for group in structures.iter() {
+            for structure in group.iter() {
+                for element in structure.elements() {
+                    let id = assignment.id(element);
+                    assignment.values[id] = element.val;
+                }
+            }
+        }
+        return assignment;
+    }
+
+    // 元素の番号
+    fn id(&self, element: &Element) -> usize {
+        return self
+            .board
+            .index_of_element(element)
+            .expect("盤面にない元素です！");
+    }
+
+    // 元素の値
+    pub fn get(&self, element: &Structure) -> Option<i32> {
+        let element = element.as_element().expect("元素ではありません！");
+        return self.values[self.id(element)];
+    }
+
+    // 元素に値を割り当てる
+    pub fn set(&mut self, element: &Structure, val: Option<i32>) {
+        let element = element.as_element().expect("元素ではありません！");
+        let id = self.id(element);
+        self.values[id] = val;
+    }
+
+    // 構造体に含まれる元素の値（入れ子の構造体も辿る, 構造体の元素の順）
+    pub fn values_of(&self, structure: &Structure) -> Vec<Option<i32>> {
+        return structure
+            .elements()
+            .into_iter()
+            .map(|element| self.values[self.id(element)])
+            .collect();
+    }
+
+    // 構造体に含まれる元素全てに同じ値を割り当てる
+    pub fn fill(&mut self, structure: &Structure, val: Option<i32>) {
+        for element in structure.elements() {
+            let id = self.id(element);
+            self.values[id] = val;
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 割り当ての値を元素列に書き込む関数（出力など, 値を持つ構造体が必要なところで使う）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // structures: &Vec<Structure> - 元素列（値は問わない）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<Structure> - 値を書き込んだ元素列
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn apply(&self, structures: &Vec<Structure>) -> Vec<Structure> {
+        let mut applied = structures.clone();
+        for structure in applied.iter_mut() {
+            structure.for_each_element_mut(&mut |element| {
+                element.val = self.values[self.id(element)];
+            });
+        }
+        return applied;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::dataclass::{Attribute, Coordinate};
+    use crate::common::initialize::initialize;
+    use crate::common::topology::Topology;
+
+    #[test]
+    fn test_assignment() {
+        let board_size = BoardSize(2, 2);
+        let (_, C, _, _) = initialize(&board_size);
+        let cell = |i, j| Structure::Element(Element::new(Attribute::C, Coordinate(i, j)));
+        let row: Structure = vec![cell(1, 1), cell(1, 2)].into();
+
+        let mut independent_C = C.clone();
+        for (k, c) in independent_C.iter_mut().enumerate() {
+            c.set_val(Some(k as i32 + 1));
+        }
+        let assignment = Assignment::from_structures(&board_size, &[&independent_C]);
+        // 値のない構造体（キー）のまま値を読める
+        assert_eq!(assignment.get(&cell(2, 1)), Some(3));
+        assert_eq!(assignment.values_of(&row), vec![Some(1), Some(2)]);
+        assert_eq!(assignment.apply(&C), independent_C);
+
+        // 複製しても盤面は共有する
+        let mut copy = assignment.clone();
+        copy.fill(&row, Some(9));
+        assert_eq!(copy.values_of(&row), vec![Some(9), Some(9)]);
+        assert_eq!(assignment.values_of(&row), vec![Some(1), Some(2)]);
+        assert!(Arc::ptr_eq(&copy.board, &assignment.board));
+        assert_eq!(Assignment::new(&board_size).get(&cell(1, 1)), None);
+    }
+
+    #[test]
+    fn test_assignment_on_board() {
+        // 端の繋がった盤面: 長方形の盤面にない元素（円筒の端を越える辺Hc(1, 3)）にも値を割り当てられる
+        let board = Arc::new(Board::with_topology(&BoardSize(2, 3), Topology::Cylinder));
+        let (_, _, _, Ec) = board.parts();
+        let wrapping = Structure::Element(Element::new(Attribute::Hc, Coordinate(1, 3)));
+        assert!(Ec.contains(&wrapping));
+        assert_eq!(Board::shared(&BoardSize(2, 3)).index_of(&wrapping), None);
+
+        let mut assignment = Assignment::on_board(board.clone());
+        assignment.set(&wrapping, Some(1));
+        assert_eq!(assignment.get(&wrapping), Some(1));
+        let applied = assignment.apply(&Ec);
+        let copied = Assignment::from_structures_on(board, &[&applied]);
+        assert_eq!(copied.values, assignment.values);
+    }
+}
@@ -0,0 +1,694 @@
+use crate::common::dataclass::{Attribute, BoardSize, Coordinate, Element, Structure};
+use crate::common::initialize::{initialize_with_mask, initialize_with_topology, Mask};
+use crate::common::relationship::{
+    borders, corner, dual, endpoint, position, relationship, Relation, Relationship, D, H, M, V,
+};
+use crate::common::topology::Topology;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+// Boardの隣接リストを引く鍵
+// 既定の隣接関係はrelationship.rsの同名の関数に対応し, with_relationで追加した隣接関係は名前で区別する
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Adjacency {
+    H,
+    V,
+    D,
+    M,
+    Borders,
+    Corner,
+    Endpoint,
+    Dual,
+    Named(&'static str),
+}
+
+// 盤面ごとに作る隣接関係の既定の集合（いずれも2倍の座標で距離2以内の元素どうしの関係）
+const LOCAL_RELATIONS: [(Adjacency, Relationship); 8] = [
+    (Adjacency::H, H),
+    (Adjacency::V, V),
+    (Adjacency::D, D),
+    (Adjacency::M, M),
+    (Adjacency::Borders, borders),
+    (Adjacency::Corner, corner),
+    (Adjacency::Endpoint, endpoint),
+    (Adjacency::Dual, dual),
+];
+
+// Board::sharedで共有する盤面
+static SHARED: Mutex<BTreeMap<(i32, i32), Arc<Board>>> = Mutex::new(BTreeMap::new());
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 盤面の元素と隣接関係を前もって計算しておく構造体
+// 元素はinitializeの(P, C, Ep, Ec)をこの順に並べたもので, 以降は元素の番号で扱う
+// 隣接関係ごとに各元素の隣接する元素の番号を持つので, 隣接の問い合わせは隣接する元素の個数だけで済む
+// 隣接関係はAdjacencyで区別する
+// 番号と隣接リストは複製した盤面どうしで共有するので, cloneで複製されるのは元素（値）だけ
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone)]
+pub struct Board {
+    pub board_size: BoardSize,
+    pub mask: Mask,
+    pub topology: Topology,
+    pub elements: Vec<Structure>,
+    // groups[k]: P, C, Ep, Ecの先頭の元素の番号
+    groups: Vec<usize>,
+    // kinds[k]: (属性, 元素の番号の範囲), initializeの返り値の順
+    kinds: Vec<(Attribute, std::ops::Range<usize>)>,
+    // 元素の識別子（属性, 座標, 層）から番号
+    index: Arc<HashMap<(Attribute, Coordinate, i32), usize>>,
+    neighbours: Arc<HashMap<Adjacency, Vec<Vec<usize>>>>,
+}
+
+impl Board {
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // initializeの元素から盤面を作り, H, V, D, Mと接続関係の隣接リストを計算する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面サイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Board - 盤面
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn new(board_size: &BoardSize) -> Self {
+        return Board::with_mask(board_size, &Mask::full(board_size));
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 長方形でない盤面を作る関数, 元素はinitialize_with_maskのもので, 隣接関係は盤面の形に沿う
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面を囲む長方形のサイズ
+    // mask: &Mask - 盤面の形
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Board - 盤面
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn with_mask(board_size: &BoardSize, mask: &Mask) -> Self {
+        let parts = initialize_with_mask(board_size, mask);
+        return Board::build(board_size, mask, Topology::Plane, parts);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 端が繋がった盤面を作る関数, 元素はinitialize_with_topologyのもので, 隣接関係は端を越えても成り立つ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面サイズ
+    // topology: Topology - 盤面の位相
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Board - 盤面
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn with_topology(board_size: &BoardSize, topology: Topology) -> Self {
+        let parts = initialize_with_topology(board_size, &topology);
+        return Board::build(board_size, &Mask::full(board_size), topology, parts);
+    }
+
+    // 元素の列から盤面を作り, 既定の隣接関係の隣接リストを計算する
+    fn build(
+        board_size: &BoardSize,
+        mask: &Mask,
+        topology: Topology,
+        (P, C, Ep, Ec): (
+            Vec<Structure>,
+            Vec<Structure>,
+            Vec<Structure>,
+            Vec<Structure>,
+        ),
+    ) -> Self {
+        let mut elements: Vec<Structure> = vec![];
+        let mut groups = vec![];
+        let mut kinds: Vec<(Attribute, std::ops::Range<usize>)> = vec![];
+        for group in [P, C, Ep, Ec] {
+            groups.push(elements.len());
+            for structure in group {
+                let k = elements.len();
+                let attr = Board::element(&structure).attr.clone();
+                match kinds.last_mut() {
+                    Some((last, range)) if *last == attr => range.end = k + 1,
+                    _ => kinds.push((attr, k..k + 1)),
+                }
+                elements.push(structure);
+            }
+        }
+        let index: HashMap<(Attribute, Coordinate, i32), usize> = elements
+            .iter()
+            .enumerate()
+            .map(|(k, s)| (Board::element(s).key(), k))
+            .collect();
+        let mut board = Board {
+            board_size: BoardSize(board_size.0, board_size.1),
+            mask: mask.clone(),
+            topology,
+            elements,
+            groups,
+            kinds,
+            index: Arc::new(index),
+            neighbours: Arc::new(HashMap::new()),
+        };
+
+        // 近くの元素だけを比べる: 2倍の座標で縦横2以内（端が繋がる盤面では一周した先の像の近くも）
+        let mut at: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (k, s) in board.elements.iter().enumerate() {
+            at.entry(position(Board::element(s))).or_default().push(k);
+        }
+        let mut neighbours = HashMap::new();
+        for (a, r) in LOCAL_RELATIONS {
+            let relation = topology.relation(r, board_size);
+            let lists = board
+                .elements
+                .iter()
+                .map(|s| {
+                    let x = Board::element(s);
+                    let mut centres = vec![position(x)];
+                    centres.extend(topology.images(x, board_size).iter().map(position));
+                    let mut list: Vec<usize> = vec![];
+                    for (i, j) in centres {
+                        for di in -2..=2 {
+                            for dj in -2..=2 {
+                                for &k in at.get(&(i + di, j + dj)).into_iter().flatten() {
+                                    let y = Board::element(&board.elements[k]);
+                                    if relation.holds(x, y) && mask.connects(x, y) {
+                                        list.push(k);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    list.sort();
+                    list.dedup();
+                    list
+                })
+                .collect();
+            neighbours.insert(a, lists);
+        }
+        board.neighbours = Arc::new(neighbours);
+        return board;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面サイズごとに1つだけ作った盤面を返す関数（2回目以降は作り直さない）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面サイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Arc<Board> - 盤面
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn shared(board_size: &BoardSize) -> Arc<Board> {
+        let mut shared = SHARED.lock().unwrap();
+        return shared
+            .entry((board_size.0, board_size.1))
+            .or_insert_with(|| Arc::new(Board::new(board_size)))
+            .clone();
+    }
+
+    // 既定の集合にない隣接関係の隣接リストを, Adjacency::Named(name)として追加する（全ての組を比べる）
+    pub fn with_relation(mut self, name: &'static str, r: Relationship) -> Self {
+        // 端が繋がる盤面は全てのセルが存在し, 端を越える辺はマスクの外にあるので, マスクは長方形の盤面でだけ使う
+        let mask = self.mask.clone();
+        let plane = self.topology == Topology::Plane;
+        let wrapped = self.topology.relation(r, &self.board_size);
+        let relation = move |X: &Element, Y: &Element| {
+            wrapped.holds(X, Y) && (!plane || (mask.has(X) && mask.has(Y) && mask.connects(X, Y)))
+        };
+        let lists = self
+            .elements
+            .iter()
+            .map(|x| {
+                (0..self.elements.len())
+                    .filter(|&k| relationship(x, &self.elements[k], &relation))
+                    .collect()
+            })
+            .collect();
+        Arc::make_mut(&mut self.neighbours).insert(Adjacency::Named(name), lists);
+        return self;
+    }
+
+    fn element(structure: &Structure) -> &Element {
+        if let Structure::Element(ref element) = structure {
+            return element;
+        }
+        panic!("Boardの元素は元素でなければなりません！");
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 番号
+
+    // 元素の番号（値は問わない）, 盤面にない元素ならNone
+    pub fn index_of(&self, structure: &Structure) -> Option<usize> {
+        if let Structure::Element(ref element) = structure {
+            return self.index_of_element(element);
+        }
+        return None;
+    }
+
+    // 元素の番号（値は問わない）, 盤面にない元素ならNone
+    pub fn index_of_element(&self, element: &Element) -> Option<usize> {
+        return self.index.get(&element.key()).copied();
+    }
+
+    // 属性ごとの元素の番号の範囲
+    pub fn range_of(&self, attr: &Attribute) -> std::ops::Range<usize> {
+        return self
+            .kinds
+            .iter()
+            .find(|(a, _)| a == attr)
+            .map(|(_, range)| range.clone())
+            .unwrap_or(0..0);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 元素の, initializeが返す列（P, C, Ep, Ecのいずれか）の中での位置を求める関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // k: usize - 元素の番号
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // usize - 列の中での位置
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn offset(&self, k: usize) -> usize {
+        let group = match Board::element(&self.elements[k]).attr {
+            Attribute::P | Attribute::Xp | Attribute::Gp | Attribute::P3 => 0,
+            Attribute::C | Attribute::Xc | Attribute::Gc | Attribute::C3 => 1,
+            Attribute::Ds | Attribute::Db => 1,
+            Attribute::Tnw | Attribute::Tne | Attribute::Tsw | Attribute::Tse => 1,
+            Attribute::Hp | Attribute::Vp | Attribute::Xep | Attribute::Gep => 2,
+            Attribute::Fl | Attribute::Fr | Attribute::Fc => 2,
+            Attribute::Hc | Attribute::Vc | Attribute::Xec | Attribute::Gec => 3,
+        };
+        return k - self.groups[group];
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 座標による参照（盤面の外ならNone）
+
+    fn lookup(&self, attr: Attribute, r: i32, c: i32) -> Option<usize> {
+        return self.index.get(&(attr, Coordinate(r, c), 0)).copied();
+    }
+
+    // 格子点P(r, c)の番号
+    pub fn point(&self, r: i32, c: i32) -> Option<usize> {
+        return self.lookup(Attribute::P, r, c);
+    }
+
+    // セルC(r, c)の番号
+    pub fn cell(&self, r: i32, c: i32) -> Option<usize> {
+        return self.lookup(Attribute::C, r, c);
+    }
+
+    // 格子点P(r, c)とP(r, c+1)を結ぶ辺Hp(r, c)の番号
+    pub fn h_edge(&self, r: i32, c: i32) -> Option<usize> {
+        return self.lookup(Attribute::Hp, r, c);
+    }
+
+    // 格子点P(r, c)とP(r+1, c)を結ぶ辺Vp(r, c)の番号
+    pub fn v_edge(&self, r: i32, c: i32) -> Option<usize> {
+        return self.lookup(Attribute::Vp, r, c);
+    }
+
+    // セルC(r, c)とC(r, c+1)を結ぶ辺Hc(r, c)の番号
+    pub fn h_cell_edge(&self, r: i32, c: i32) -> Option<usize> {
+        return self.lookup(Attribute::Hc, r, c);
+    }
+
+    // セルC(r, c)とC(r+1, c)を結ぶ辺Vc(r, c)の番号
+    pub fn v_cell_edge(&self, r: i32, c: i32) -> Option<usize> {
+        return self.lookup(Attribute::Vc, r, c);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 値
+
+    // 元素kの値
+    pub fn val(&self, k: usize) -> Option<i32> {
+        return self.elements[k].val();
+    }
+
+    // 元素kに値を割り当てる
+    pub fn set_val(&mut self, k: usize, val: Option<i32>) {
+        self.elements[k].set_val(val);
+    }
+
+    // 全ての元素の値（番号の順）
+    pub fn assignment(&self) -> Vec<Option<i32>> {
+        return (0..self.elements.len()).map(|k| self.val(k)).collect();
+    }
+
+    // 全ての元素に値を割り当てる（assignmentの逆）
+    pub fn assign(&mut self, values: &[Option<i32>]) {
+        assert_eq!(
+            values.len(),
+            self.elements.len(),
+            "値の個数が元素の個数と違います！"
+        );
+        for (k, &val) in values.iter().enumerate() {
+            self.set_val(k, val);
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 種類ごとの元素
+
+    // 属性attrの元素（番号の順）
+    pub fn kind(&self, attr: &Attribute) -> &[Structure] {
+        return &self.elements[self.range_of(attr)];
+    }
+
+    // 格子点P
+    pub fn points(&self) -> &[Structure] {
+        return &self.elements[self.groups[0]..self.groups[1]];
+    }
+
+    // セルC
+    pub fn cells(&self) -> &[Structure] {
+        return &self.elements[self.groups[1]..self.groups[2]];
+    }
+
+    // 格子点辺Ep（Hp, Vpの順）
+    pub fn point_edges(&self) -> &[Structure] {
+        return &self.elements[self.groups[2]..self.groups[3]];
+    }
+
+    // セル辺Ec（Hc, Vcの順）
+    pub fn cell_edges(&self) -> &[Structure] {
+        return &self.elements[self.groups[3]..];
+    }
+
+    // initializeと同じ(P, C, Ep, Ec)の組（値も含めて複製する）
+    pub fn parts(
+        &self,
+    ) -> (
+        Vec<Structure>,
+        Vec<Structure>,
+        Vec<Structure>,
+        Vec<Structure>,
+    ) {
+        return (
+            self.points().to_vec(),
+            self.cells().to_vec(),
+            self.point_edges().to_vec(),
+            self.cell_edges().to_vec(),
+        );
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 隣接
+
+    // 元素kとaの関係にある元素の番号（昇順）
+    pub fn neighbours(&self, k: usize, a: Adjacency) -> &Vec<usize> {
+        match self.neighbours.get(&a) {
+            Some(lists) => return &lists[k],
+            None => {
+                panic!("隣接関係がBoardに登録されていません！（with_relationで追加してください）")
+            }
+        }
+    }
+
+    // 元素kとaの関係にある, 属性attrの元素の番号
+    pub fn neighbours_with(&self, k: usize, a: Adjacency, attr: &Attribute) -> Vec<usize> {
+        let range = self.range_of(attr);
+        return self
+            .neighbours(k, a)
+            .iter()
+            .copied()
+            .filter(|x| range.contains(x))
+            .collect();
+    }
+
+    // 元素xとyがRのいずれかの関係にあるか
+    pub fn is_adjacent(&self, x: usize, y: usize, R: &Vec<Adjacency>) -> bool {
+        return R
+            .iter()
+            .any(|&a| self.neighbours(x, a).binary_search(&y).is_ok());
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 構造体がRのいずれかの関係で連結であるかを確認する関数（BFS）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // structure: &Structure - 盤面の元素からなる構造体
+    // R: &Vec<Adjacency> - 満たすべき隣接関係
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // bool - 連結であればtrue
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn is_connected(&self, structure: &Structure, R: &Vec<Adjacency>) -> bool {
+        let members: Vec<usize> = match structure {
+            Structure::Composition(ref composition) => composition
+                .entity
+                .iter()
+                .map(|s| self.index_of(s).expect("盤面にない元素です！"))
+                .collect(),
+            Structure::Element(_) => return true,
+        };
+        if members.is_empty() {
+            return false;
+        }
+        let mut inside: HashMap<usize, bool> = members.iter().map(|&k| (k, false)).collect();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        queue.push_back(members[0]);
+        inside.insert(members[0], true);
+        let mut visited = 1;
+        while let Some(current) = queue.pop_front() {
+            for &a in R.iter() {
+                for &next in self.neighbours(current, a).iter() {
+                    if let Some(seen) = inside.get_mut(&next) {
+                        if !*seen {
+                            *seen = true;
+                            visited += 1;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+            }
+        }
+        return visited == inside.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::{combine, combine_on_board, combine_with_topology};
+    use crate::common::dataclass::Composition;
+    use crate::common::initialize::initialize;
+    use crate::common::profile::Named;
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+
+    #[test]
+    fn test_neighbours() {
+        let board = Board::new(&BoardSize(2, 3));
+        let (P, C, Ep, Ec) = initialize(&BoardSize(2, 3));
+        assert_eq!(
+            board.elements.len(),
+            P.len() + C.len() + Ep.len() + Ec.len()
+        );
+
+        // 隣接リストは全ての組を比べた結果と一致する
+        for (a, r) in LOCAL_RELATIONS {
+            let full = Board::new(&BoardSize(2, 3)).with_relation("full", r);
+            for k in 0..board.elements.len() {
+                assert_eq!(
+                    board.neighbours(k, a),
+                    full.neighbours(k, Adjacency::Named("full"))
+                );
+            }
+        }
+
+        let c = |i, j| Structure::Element(Element::new(Attribute::C, Coordinate(i, j)));
+        let c12 = board.index_of(&c(1, 2)).unwrap();
+        assert_eq!(board.offset(c12), 1);
+        assert_eq!(
+            board
+                .neighbours_with(c12, Adjacency::Borders, &Attribute::Hp)
+                .len(),
+            2
+        );
+        assert_eq!(
+            board
+                .neighbours_with(c12, Adjacency::Borders, &Attribute::Vp)
+                .len(),
+            2
+        );
+        for k in board.neighbours_with(c12, Adjacency::Borders, &Attribute::Vp) {
+            assert!(Ep[board.offset(k)] == board.elements[k]);
+        }
+        assert_eq!(board.neighbours(c12, Adjacency::H).len(), 4);
+        assert_eq!(
+            board
+                .neighbours_with(c12, Adjacency::H, &Attribute::C)
+                .len(),
+            2
+        );
+        assert!(board.is_adjacent(
+            c12,
+            board.index_of(&c(2, 2)).unwrap(),
+            &vec![Adjacency::H, Adjacency::V]
+        ));
+
+        let area = |cells: Vec<Structure>| Structure::Composition(Composition::new(cells));
+        assert!(board.is_connected(
+            &area(vec![c(1, 1), c(1, 2), c(2, 2)]),
+            &vec![Adjacency::H, Adjacency::V]
+        ));
+        assert!(!board.is_connected(
+            &area(vec![c(1, 1), c(2, 2)]),
+            &vec![Adjacency::H, Adjacency::V]
+        ));
+        assert!(board.is_connected(
+            &area(vec![c(1, 1), c(2, 2)]),
+            &vec![Adjacency::H, Adjacency::V, Adjacency::D]
+        ));
+        // 隣接リストを使った組成演算は, 全ての組を比べる組成演算と同じ結果になる
+        let no_cutoff: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+        assert_eq!(
+            combine_on_board(
+                vec![Adjacency::H, Adjacency::V],
+                vec![Adjacency::M],
+                &C,
+                &no_cutoff,
+                &board
+            ),
+            combine(vec![H, V], vec![M], &C, &no_cutoff)
+        );
+        assert!(Arc::ptr_eq(
+            &Board::shared(&BoardSize(2, 3)),
+            &Board::shared(&BoardSize(2, 3))
+        ));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut board = Board::new(&BoardSize(2, 3));
+        let (P, C, Ep, Ec) = initialize(&BoardSize(2, 3));
+        let c12 = board
+            .index_of(&Structure::Element(Element::new(
+                Attribute::C,
+                Coordinate(1, 2),
+            )))
+            .unwrap();
+        // 座標による参照と値
+        assert_eq!(board.cell(1, 2), Some(c12));
+        assert_eq!(board.cell(3, 1), None);
+        assert_eq!(board.elements[board.point(3, 4).unwrap()], P[P.len() - 1]);
+        assert_eq!(board.elements[board.h_edge(3, 3).unwrap()], Ep[8]);
+        assert_eq!(board.elements[board.v_edge(1, 1).unwrap()], Ep[9]);
+        assert_eq!(board.elements[board.h_cell_edge(1, 1).unwrap()], Ec[0]);
+        assert_eq!(board.elements[board.v_cell_edge(1, 3).unwrap()], Ec[6]);
+        assert_eq!(
+            board.parts(),
+            (P.clone(), C.clone(), Ep.clone(), Ec.clone())
+        );
+        assert_eq!(board.kind(&Attribute::Vc), &Ec[4..]);
+
+        let copy = board.clone();
+        board.set_val(c12, Some(3));
+        assert_eq!(board.val(c12), Some(3));
+        assert_eq!(copy.val(c12), None);
+        assert!(Arc::ptr_eq(&board.neighbours, &copy.neighbours));
+        let mut values = board.assignment();
+        values[0] = Some(1);
+        board.assign(&values);
+        assert_eq!(board.points()[0], {
+            let mut p = P[0].clone();
+            if let Structure::Element(ref mut e) = p {
+                e.val = Some(1);
+            }
+            p
+        });
+    }
+
+    #[test]
+    fn test_mask() {
+        let c = |i, j| Structure::Element(Element::new(Attribute::C, Coordinate(i, j)));
+        let area = |cells: Vec<Structure>| Structure::Composition(Composition::new(cells));
+        // 穴の空いた盤面では, 穴を挟む格子点どうしは横に隣接しない
+        let (hole_size, hole) = Mask::parse("#.#");
+        let holed = Board::with_mask(&hole_size, &hole);
+        let p12 = holed.point(1, 2).unwrap();
+        assert_eq!(holed.cell(1, 2), None);
+        assert_eq!(holed.h_edge(1, 2), None);
+        assert_eq!(
+            holed.neighbours_with(p12, Adjacency::H, &Attribute::P),
+            vec![holed.point(1, 1).unwrap()]
+        );
+        assert!(!holed.is_connected(
+            &area(vec![c(1, 1), c(1, 3)]),
+            &vec![Adjacency::H, Adjacency::V]
+        ));
+    }
+
+    #[test]
+    fn test_topology() {
+        // 端が繋がった盤面: 隣接リストは全ての組を比べた結果と一致し, 全てのセルが上下左右に4つのセルと隣接する
+        for topology in [Topology::Cylinder, Topology::Torus, Topology::Mobius] {
+            let wrapped = Board::with_topology(&BoardSize(3, 4), topology);
+            for (a, r) in LOCAL_RELATIONS {
+                let full =
+                    Board::with_topology(&BoardSize(3, 4), topology).with_relation("full", r);
+                for k in 0..wrapped.elements.len() {
+                    assert_eq!(
+                        wrapped.neighbours(k, a),
+                        full.neighbours(k, Adjacency::Named("full"))
+                    );
+                }
+            }
+            for k in wrapped.range_of(&Attribute::C) {
+                let around = wrapped
+                    .neighbours_with(k, Adjacency::H, &Attribute::C)
+                    .len()
+                    + wrapped
+                        .neighbours_with(k, Adjacency::V, &Attribute::C)
+                        .len();
+                let row = Board::element(&wrapped.elements[k]).coor.0;
+                let expected = if topology == Topology::Torus || row == 2 {
+                    4
+                } else {
+                    3
+                };
+                assert_eq!(around, expected);
+                // 各セルの周りには4本の格子点辺がある
+                assert_eq!(wrapped.neighbours(k, Adjacency::Borders).len(), 4);
+            }
+        }
+        let torus = Board::with_topology(&BoardSize(3, 4), Topology::Torus);
+        assert_eq!(
+            (
+                torus.points().len(),
+                torus.point_edges().len(),
+                torus.cell_edges().len()
+            ),
+            (12, 24, 24)
+        );
+
+        // 端が繋がった盤面の組成演算は, 位相を渡した組成演算と同じ結果になる
+        let no_cutoff: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+        let cylinder = Board::with_topology(&BoardSize(2, 3), Topology::Cylinder);
+        let (_, ring, _, _) = cylinder.parts();
+        assert_eq!(
+            combine_on_board(
+                vec![Adjacency::H, Adjacency::V],
+                vec![Adjacency::M],
+                &ring,
+                &no_cutoff,
+                &cylinder
+            ),
+            combine_with_topology(
+                vec![H, V],
+                vec![M],
+                &ring,
+                &no_cutoff,
+                &vec![],
+                &Topology::Cylinder,
+                &BoardSize(2, 3)
+            )
+        );
+    }
+}
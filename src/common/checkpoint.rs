@@ -0,0 +1,277 @@
+use std::fs;
+use std::io::Write;
+
+const HEADER: &str = "puzzle_check checkpoint v1";
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 列挙の途中経過（チェックポイント）
+// 列挙は通し番号（探索空間全体のインデックス）で管理し, 完了した番号の区間と, その区間で見つかった盤面を保存する
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    pub rule: String,
+    pub seed: u64,
+    pub total: u64,
+    // 列挙する区間[start, end), 分割しない場合は[0, total)
+    pub range: (u64, u64),
+    // 完了した区間[start, end)の列（昇順, 重なりなし）
+    pub done: Vec<(u64, u64)>,
+    // 見つかった盤面（通し番号, JSON Linesの1行）
+    pub records: Vec<(u64, String)>,
+}
+
+impl Checkpoint {
+    // コンストラクタ, 完了した区間は空
+    pub fn new(rule: &str, seed: u64, total: u64) -> Self {
+        Checkpoint {
+            rule: rule.to_string(),
+            seed,
+            total,
+            range: (0, total),
+            done: Vec::new(),
+            records: Vec::new(),
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 完了した区間を追加する関数, 隣接・重複する区間は結合する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // start: u64 - 区間の始点
+    // end: u64 - 区間の終点（含まない）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn mark_done(&mut self, start: u64, end: u64) {
+        if start >= end {
+            return;
+        }
+        let mut merged: Vec<(u64, u64)> = Vec::new();
+        let mut current = (start, end);
+        let mut inserted = false;
+        for &(s, e) in self.done.iter() {
+            if e < current.0 {
+                merged.push((s, e));
+            } else if current.1 < s {
+                if !inserted {
+                    merged.push(current);
+                    inserted = true;
+                }
+                merged.push((s, e));
+            } else {
+                current = (std::cmp::min(s, current.0), std::cmp::max(e, current.1));
+            }
+        }
+        if !inserted {
+            merged.push(current);
+        }
+        self.done = merged;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 区間[start, end)が全て完了しているかを確認する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // bool - 完了しているときtrue
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn is_done(&self, start: u64, end: u64) -> bool {
+        self.done.iter().any(|&(s, e)| s <= start && end <= e)
+    }
+
+    // startから途切れずに完了している区間の終点, startが完了していない場合はstart
+    pub fn done_until(&self, start: u64) -> u64 {
+        return self
+            .done
+            .iter()
+            .find(|&&(s, e)| s <= start && start < e)
+            .map_or(start, |&(_, e)| e);
+    }
+
+    // 完了した通し番号の個数
+    pub fn done_count(&self) -> u64 {
+        self.done.iter().map(|&(s, e)| e - s).sum()
+    }
+
+    // 別のルールのチェックポイントでないかを確かめる
+    pub fn check_rule(&self, rule: &str) -> Result<(), String> {
+        if self.rule != rule {
+            return Err(format!(
+                "checkpoint of rule '{}', not '{}'",
+                self.rule, rule
+            ));
+        }
+        return Ok(());
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // この実行と同じ探索空間と区間のチェックポイントであるかを確かめる関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // total: u64 - この実行の探索空間の大きさ
+    // range: (u64, u64) - この実行で列挙する区間
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<(), String> - 一致しない場合はエラーメッセージ
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn check_range(&self, total: u64, range: (u64, u64)) -> Result<(), String> {
+        if self.total != total {
+            return Err(format!(
+                "checkpoint covers {} candidates but this run has {}",
+                self.total, total
+            ));
+        }
+        if self.range != range {
+            return Err(format!(
+                "checkpoint of another shard ([{}, {}), this run is [{}, {}))",
+                self.range.0, self.range.1, range.0, range.1
+            ));
+        }
+        return Ok(());
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // チェックポイントをファイルに保存する関数
+    // 途中で中断されても壊れないように, 一時ファイルに書いてから置き換える
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // path: &str - 保存先
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let tmp_path = format!("{}.tmp", path);
+        let mut file = fs::File::create(&tmp_path)?;
+        writeln!(file, "{}", HEADER)?;
+        writeln!(file, "rule {}", self.rule)?;
+        writeln!(file, "seed {}", self.seed)?;
+        writeln!(file, "total {}", self.total)?;
+        writeln!(file, "range {} {}", self.range.0, self.range.1)?;
+        for (start, end) in self.done.iter() {
+            writeln!(file, "done {} {}", start, end)?;
+        }
+        for (index, line) in self.records.iter() {
+            writeln!(file, "record {} {}", index, line)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)?;
+        return Ok(());
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 保存されたチェックポイントを読み込む関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // path: &str - 保存先
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<Checkpoint, String> - 読み込みに失敗した場合はエラーメッセージ
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn load(path: &str) -> Result<Checkpoint, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(format!("{}: not a checkpoint file", path));
+        }
+        let mut checkpoint = Checkpoint::new("", 0, 0);
+        for line in lines {
+            let (key, value) = match line.split_once(' ') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let parse = |s: &str| -> Result<u64, String> {
+                s.parse::<u64>()
+                    .map_err(|_| format!("{}: invalid number in '{}'", path, line))
+            };
+            match key {
+                "rule" => checkpoint.rule = value.to_string(),
+                "seed" => checkpoint.seed = parse(value)?,
+                "total" => checkpoint.total = parse(value)?,
+                "range" => {
+                    let (start, end) = value
+                        .split_once(' ')
+                        .ok_or(format!("{}: invalid range '{}'", path, line))?;
+                    checkpoint.range = (parse(start)?, parse(end)?);
+                }
+                "done" => {
+                    let (start, end) = value
+                        .split_once(' ')
+                        .ok_or(format!("{}: invalid range '{}'", path, line))?;
+                    checkpoint.mark_done(parse(start)?, parse(end)?);
+                }
+                "record" => {
+                    let (index, record) = value
+                        .split_once(' ')
+                        .ok_or(format!("{}: invalid record '{}'", path, line))?;
+                    checkpoint.records.push((parse(index)?, record.to_string()));
+                }
+                _ => return Err(format!("{}: unknown entry '{}'", path, key)),
+            }
+        }
+        return Ok(checkpoint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_done() {
+        let mut checkpoint = Checkpoint::new("test", 0, 100);
+        checkpoint.mark_done(10, 20);
+        checkpoint.mark_done(30, 40);
+        checkpoint.mark_done(0, 5);
+        assert_eq!(checkpoint.done, vec![(0, 5), (10, 20), (30, 40)]);
+        checkpoint.mark_done(20, 30);
+        assert_eq!(checkpoint.done, vec![(0, 5), (10, 40)]);
+        checkpoint.mark_done(3, 12);
+        assert_eq!(checkpoint.done, vec![(0, 40)]);
+        assert!(checkpoint.is_done(5, 40));
+        assert!(!checkpoint.is_done(39, 41));
+        assert_eq!(checkpoint.done_count(), 40);
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir()
+            .join("puzzle_check_test_save_load.checkpoint")
+            .to_string_lossy()
+            .to_string();
+        let mut checkpoint = Checkpoint::new("test", 7, 100);
+        checkpoint.range = (50, 100);
+        checkpoint.mark_done(50, 60);
+        checkpoint.mark_done(70, 80);
+        checkpoint
+            .records
+            .push((52, "{\"rule\":\"test\"}".to_string()));
+        checkpoint
+            .records
+            .push((71, "{\"a b\":[1, 2]}".to_string()));
+        checkpoint.save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+
+        // 何も完了していないチェックポイントも読み戻せる
+        let empty = Checkpoint::new("test", 0, 0);
+        empty.save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), empty);
+
+        fs::write(&path, "rule test\n").unwrap();
+        assert!(Checkpoint::load(&path).is_err());
+        fs::write(&path, format!("{}\ntotal x\n", HEADER)).unwrap();
+        assert!(Checkpoint::load(&path).is_err());
+        let _ = fs::remove_file(&path);
+        assert!(Checkpoint::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let mut checkpoint = Checkpoint::new("test", 0, 100);
+        checkpoint.range = (0, 50);
+        assert!(checkpoint.check_rule("test").is_ok());
+        assert!(checkpoint.check_rule("other").is_err());
+        assert!(checkpoint.check_range(100, (0, 50)).is_ok());
+        assert!(checkpoint.check_range(99, (0, 50)).is_err());
+        assert!(checkpoint.check_range(100, (50, 100)).is_err());
+    }
+}
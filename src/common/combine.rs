@@ -1,9 +1,13 @@
-use crate::common::dataclass::{Attribute, Composition, Coordinate, Element, Structure};
-use crate::common::relationship::{relationship, Relationship, D, H, M, V};
-use crate::specific::cutoff::CutoffFn;
+use crate::common::board::{Adjacency, Board};
+use crate::common::dataclass::{Attribute, BoardSize, Composition, Coordinate, Element, Structure};
+use crate::common::initialize::Mask;
+use crate::common::profile::{Named, Profile};
+use crate::common::relationship::{related, Relation, Relationship, D, H, M, V};
+use crate::common::topology::Topology;
+use crate::specific::cutoff::{CutoffFn, TopologyCutoffFn};
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 // ---------------------------------------------------------------------------------------------------------------------
 // [overview]
@@ -13,7 +17,7 @@ use std::collections::{HashSet, VecDeque};
 // R: Vec<Relationship> - グラフ構造を展開するための隣接関係
 // not_R: Vec<Relationship> - サブグラフの内部で満たしてはいけない隣接関係
 // E: &Vec<Structure> - 構造体全てを含む集合
-// cutoff_fn: &Vec<CutoffFn> - constraintsから, 現れないことが分かっている構造体をあらかじめ省くための関数列（カットオフを行わないときはnon_cutoffを入力）
+// cutoff_fn: &Vec<Named<CutoffFn>> - constraintsから, 現れないことが分かっている構造体をあらかじめ省くための関数列（named!で名前と組にする, カットオフを行わないときはnon_cutoffを入力）
 // ---------------------------------------------------------------------------------------------------------------------
 // [return]
 // Vec<Structure> - 組成演算の結果, 作成される構造体全てを含む集合
@@ -22,16 +26,244 @@ pub fn combine(
     R: Vec<Relationship>,
     not_R: Vec<Relationship>,
     E: &Vec<Structure>,
-    cutoff_fn: &Vec<CutoffFn>,
+    cutoff_fn: &Vec<Named<CutoffFn>>,
 ) -> Vec<Structure> {
-    let pb_E = ProgressBar::new(2usize.pow(E.len() as u32) as u64);
+    // 隣接関係は部分集合ごとに計算し直さず, Eの番号の組ごとに前もって計算しておく
+    let related = adjacency(E, &R);
+    let forbidden = adjacency(E, &not_R);
+    return combine_adjacency(&related, &forbidden, E, cutoff_fn);
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 盤面の形（マスク）に沿って組成演算を行う関数
+// 穴を挟む格子点どうしなど, マスクの上で繋がっていない元素の組は隣接しないものとして扱う
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// R: Vec<Relationship> - グラフ構造を展開するための隣接関係
+// not_R: Vec<Relationship> - サブグラフの内部で満たしてはいけない隣接関係
+// E: &Vec<Structure> - 構造体全てを含む集合（initialize_with_maskで作ったもの）
+// cutoff_fn: &Vec<Named<CutoffFn>> - カットオフを行う関数列
+// mask: &Mask - 盤面のマスク
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Structure> - 組成演算の結果, 作成される構造体全てを含む集合
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn combine_with_mask(
+    R: Vec<Relationship>,
+    not_R: Vec<Relationship>,
+    E: &Vec<Structure>,
+    cutoff_fn: &Vec<Named<CutoffFn>>,
+    mask: &Mask,
+) -> Vec<Structure> {
+    let masked_R: Vec<_> = R.iter().map(|&r| mask.relation(r)).collect();
+    let masked_not_R: Vec<_> = not_R.iter().map(|&r| mask.relation(r)).collect();
+    return combine_with_relations(masked_R, masked_not_R, E, cutoff_fn);
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 端の繋がった盤面の上で組成演算を行う関数, 隣接関係は端を越えても成り立つ
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// R: Vec<Relationship> - グラフ構造を展開するための隣接関係
+// not_R: Vec<Relationship> - サブグラフの内部で満たしてはいけない隣接関係
+// E: &Vec<Structure> - 構造体全てを含む集合（initialize_with_topologyで作ったもの）
+// cutoff_fn: &Vec<Named<CutoffFn>> - カットオフを行う関数列
+// topology_cutoff_fn: &Vec<Named<TopologyCutoffFn>> - 位相を考慮したカットオフを行う関数列（only_cycle_on, only_line_onなど）
+// topology: &Topology - 盤面の位相
+// board_size: &BoardSize - 盤面サイズ
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Structure> - 組成演算の結果, 作成される構造体全てを含む集合
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn combine_with_topology(
+    R: Vec<Relationship>,
+    not_R: Vec<Relationship>,
+    E: &Vec<Structure>,
+    cutoff_fn: &Vec<Named<CutoffFn>>,
+    topology_cutoff_fn: &Vec<Named<TopologyCutoffFn>>,
+    topology: &Topology,
+    board_size: &BoardSize,
+) -> Vec<Structure> {
+    let wrapped_R: Vec<_> = R
+        .iter()
+        .map(|&r| topology.relation(r, board_size))
+        .collect();
+    let wrapped_not_R: Vec<_> = not_R
+        .iter()
+        .map(|&r| topology.relation(r, board_size))
+        .collect();
+
+    let found = combine_with_relations(wrapped_R, wrapped_not_R, E, cutoff_fn);
+    return filter_on_topology(found, topology_cutoff_fn, topology, board_size);
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// fnではない隣接関係（impl Relation, 平面グラフの隣接関係など）で組成演算を行う関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// R: Vec<S> - グラフ構造を展開するための隣接関係
+// not_R: Vec<T> - サブグラフの内部で満たしてはいけない隣接関係
+// E: &Vec<Structure> - 構造体全てを含む集合
+// cutoff_fn: &Vec<Named<CutoffFn>> - カットオフを行う関数列
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Structure> - 組成演算の結果, 作成される構造体全てを含む集合
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn combine_with_relations<S: Relation, T: Relation>(
+    R: Vec<S>,
+    not_R: Vec<T>,
+    E: &Vec<Structure>,
+    cutoff_fn: &Vec<Named<CutoffFn>>,
+) -> Vec<Structure> {
+    let related = adjacency(E, &R);
+    let forbidden = adjacency(E, &not_R);
+    return combine_adjacency(&related, &forbidden, E, cutoff_fn);
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 盤面の隣接リストを使って組成演算を行う関数, 隣接行列を全ての組を比べずに隣接する元素の個数だけで作る
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// R: Vec<Adjacency> - グラフ構造を展開するための隣接関係（boardに登録されているもの）
+// not_R: Vec<Adjacency> - サブグラフの内部で満たしてはいけない隣接関係（boardに登録されているもの）
+// E: &Vec<Structure> - 構造体全てを含む集合（boardの元素, 値は問わない）
+// cutoff_fn: &Vec<Named<CutoffFn>> - カットオフを行う関数列
+// board: &Board - 盤面
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Structure> - 組成演算の結果, 作成される構造体全てを含む集合
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn combine_on_board(
+    R: Vec<Adjacency>,
+    not_R: Vec<Adjacency>,
+    E: &Vec<Structure>,
+    cutoff_fn: &Vec<Named<CutoffFn>>,
+    board: &Board,
+) -> Vec<Structure> {
+    return combine_on_board_with_topology_cutoff(R, not_R, E, cutoff_fn, &vec![], board);
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 盤面の隣接リストを使って組成演算を行い, 盤面の位相を考慮したカットオフで絞り込む関数
+// 端の繋がった盤面でonly_cycle, only_lineの代わりにonly_cycle_on, only_line_onを渡す
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// R: Vec<Adjacency> - グラフ構造を展開するための隣接関係（boardに登録されているもの）
+// not_R: Vec<Adjacency> - サブグラフの内部で満たしてはいけない隣接関係（boardに登録されているもの）
+// E: &Vec<Structure> - 構造体全てを含む集合（boardの元素, 値は問わない）
+// cutoff_fn: &Vec<Named<CutoffFn>> - カットオフを行う関数列
+// topology_cutoff_fn: &Vec<Named<TopologyCutoffFn>> - 位相を考慮したカットオフを行う関数列
+// board: &Board - 盤面
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Structure> - 組成演算の結果, 作成される構造体全てを含む集合
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn combine_on_board_with_topology_cutoff(
+    R: Vec<Adjacency>,
+    not_R: Vec<Adjacency>,
+    E: &Vec<Structure>,
+    cutoff_fn: &Vec<Named<CutoffFn>>,
+    topology_cutoff_fn: &Vec<Named<TopologyCutoffFn>>,
+    board: &Board,
+) -> Vec<Structure> {
+    let indices: Vec<usize> = E
+        .iter()
+        .map(|s| board.index_of(s).expect("盤面にない元素です！"))
+        .collect();
+    let at: HashMap<usize, usize> = indices.iter().enumerate().map(|(x, &k)| (k, x)).collect();
+    let adjacency_on_board = |R: &Vec<Adjacency>| {
+        let mut matrix = vec![vec![false; E.len()]; E.len()];
+        for (x, &k) in indices.iter().enumerate() {
+            for &a in R.iter() {
+                for n in board.neighbours(k, a) {
+                    match at.get(n) {
+                        Some(&y) if y != x => matrix[x][y] = true,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        return matrix;
+    };
+    let related = adjacency_on_board(&R);
+    let forbidden = adjacency_on_board(&not_R);
+    let found = combine_adjacency(&related, &forbidden, E, cutoff_fn);
+    return filter_on_topology(
+        found,
+        topology_cutoff_fn,
+        &board.topology,
+        &board.board_size,
+    );
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 組成演算の結果を, 盤面の位相を受け取るカットオフ関数で絞り込む関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// found: Vec<Structure> - 組成演算の結果
+// topology_cutoff_fn: &Vec<Named<TopologyCutoffFn>> - 位相を考慮したカットオフを行う関数列
+// topology: &Topology - 盤面の位相
+// board_size: &BoardSize - 盤面サイズ
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Structure> - 全てのカットオフ関数を満たす構造体
+// ---------------------------------------------------------------------------------------------------------------------
+fn filter_on_topology(
+    found: Vec<Structure>,
+    topology_cutoff_fn: &Vec<Named<TopologyCutoffFn>>,
+    topology: &Topology,
+    board_size: &BoardSize,
+) -> Vec<Structure> {
+    if topology_cutoff_fn.is_empty() {
+        return found;
+    }
+    return found
+        .into_par_iter()
+        .filter(|s| {
+            topology_cutoff_fn.iter().all(|(name, validate)| {
+                Profile::check_named("cutoff", name, validate(s, topology, board_size), || {
+                    format!("{:?}", s)
+                })
+            })
+        })
+        .collect();
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 前もって計算した隣接行列から組成演算を行う関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// related: &Vec<Vec<bool>> - グラフ構造を展開するための隣接行列
+// forbidden: &Vec<Vec<bool>> - サブグラフの内部で満たしてはいけない隣接行列
+// E: &Vec<Structure> - 構造体全てを含む集合
+// cutoff_fn: &Vec<Named<CutoffFn>> - カットオフを行う関数列
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Structure> - 組成演算の結果, 作成される構造体全てを含む集合
+// ---------------------------------------------------------------------------------------------------------------------
+fn combine_adjacency(
+    related: &Vec<Vec<bool>>,
+    forbidden: &Vec<Vec<bool>>,
+    E: &Vec<Structure>,
+    cutoff_fn: &Vec<Named<CutoffFn>>,
+) -> Vec<Structure> {
+    // power_setは再帰の各段で部分集合の個数だけ進むので, 合計は2^|E| - 1
+    let pb_E = ProgressBar::new((1u64 << E.len()) - 1);
     pb_E.set_style(
         ProgressStyle::default_bar()
             .template("subset  {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
             .unwrap(),
     );
 
-    let power_E = power_set(&E, &pb_E);
+    let indices: Vec<usize> = (0..E.len()).collect();
+    let power_E = power_set(&indices, &pb_E);
     pb_E.finish();
     let pb = ProgressBar::new(power_E.len() as u64);
     pb.set_style(
@@ -49,57 +281,44 @@ pub fn combine(
                 return None;
             }
 
+            let s = Structure::Composition(Composition {
+                val: None,
+                entity: e.iter().map(|&k| E[k].clone()).collect(),
+            });
             if e.len() == 1 {
                 pb.inc(1);
-                let s = Structure::Composition(Composition {
-                    val: None,
-                    entity: e.clone(),
-                });
-                for validate in cutoff_fn {
-                    if !validate(&s) {
+                for (name, validate) in cutoff_fn {
+                    if !Profile::check_named("cutoff", name, validate(&s), || format!("{:?}", s)) {
                         return None;
                     }
                 }
                 return Some(s);
             }
 
-            'outer: for x in e {
-                let mut related = false;
-                for y in e {
+            for &x in e {
+                let mut related_any = false;
+                for &y in e {
                     if x != y {
-                        let mut not_related = true;
-                        for &r in &not_R {
-                            if relationship(x, y, r) {
-                                pb.inc(1);
-                                return None;
-                            }
-                        }
-
-                        for &r in &R {
-                            if relationship(x, y, r) {
-                                related = true;
-                                continue 'outer;
-                            }
+                        if forbidden[x][y] {
+                            pb.inc(1);
+                            return None;
                         }
+                        related_any |= related[x][y];
                     }
                 }
-                if !related {
+                if !related_any {
                     pb.inc(1);
                     return None;
                 }
             }
 
             pb.inc(1);
-            let s = Structure::Composition(Composition {
-                val: None,
-                entity: e.clone(),
-            });
-            if !is_connected_graph(&s, &R) {
+            if !is_connected_graph(e, related) {
                 return None;
             }
 
-            for validate in cutoff_fn {
-                if !validate(&s) {
+            for (name, validate) in cutoff_fn {
+                if !Profile::check_named("cutoff", name, validate(&s), || format!("{:?}", s)) {
                     return None;
                 }
             }
@@ -114,38 +333,51 @@ pub fn combine(
 
 // ---------------------------------------------------------------------------------------------------------------------
 // [overview]
-// 構造体が連結グラフであることを保証する関数(BFS)
+// 構造体の列の全ての組について, 隣接関係のいずれかを満たすかを計算する関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// E: &Vec<Structure> - 構造体の列
+// R: &Vec<R> - 隣接関係（Relationshipやマスクに沿う隣接関係）
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Vec<Vec<bool>> - [x][y]がtrueのときE[x]とE[y]は隣接する
+// ---------------------------------------------------------------------------------------------------------------------
+fn adjacency<R: Relation>(E: &Vec<Structure>, R: &Vec<R>) -> Vec<Vec<bool>> {
+    return E
+        .par_iter()
+        .map(|x| {
+            E.iter()
+                .map(|y| x != y && R.iter().any(|r| related(x, y, r)))
+                .collect()
+        })
+        .collect();
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 部分集合が連結グラフであることを保証する関数(BFS)
 // ---------------------------------------------------------------------------------------------------------------------
 // [params]
-// structure: &Structure - チェックしたい構造体
-// R: &Vec<Relationship> - 満たすべき隣接関係
+// subset: &Vec<usize> - チェックしたい部分集合（Eの番号）
+// related: &Vec<Vec<bool>> - Eの番号の組ごとの隣接関係
 // ---------------------------------------------------------------------------------------------------------------------
 // [return]
 // bool - 連結グラフであればtrue
 // ---------------------------------------------------------------------------------------------------------------------
-fn is_connected_graph(structure: &Structure, R: &Vec<Relationship>) -> bool {
-    if let Structure::Composition(ref composition) = structure {
-        let mut visited: HashSet<Structure> = HashSet::new();
-        let mut queue: VecDeque<Structure> = VecDeque::new();
-        let start_node = composition.entity[0].clone();
-        queue.push_back(start_node);
-        while let Some(current_node) = queue.pop_front() {
-            'outer: for s in composition.entity.iter() {
-                if visited.contains(&s) {
-                    continue 'outer;
-                }
-                for &r in R {
-                    if relationship(&s, &current_node, r) {
-                        queue.push_back(s.clone());
-                        visited.insert(s.clone());
-                        continue 'outer;
-                    }
-                }
+fn is_connected_graph(subset: &Vec<usize>, related: &Vec<Vec<bool>>) -> bool {
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    queue.push_back(subset[0]);
+    visited.insert(subset[0]);
+    while let Some(current) = queue.pop_front() {
+        for &s in subset.iter() {
+            if !visited.contains(&s) && related[current][s] {
+                queue.push_back(s);
+                visited.insert(s);
             }
         }
-        return visited.len() == composition.entity.len();
     }
-    unreachable!();
+    return visited.len() == subset.len();
 }
 
 // ---------------------------------------------------------------------------------------------------------------------
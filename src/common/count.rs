@@ -0,0 +1,269 @@
+use crate::common::dataclass::Structure;
+use crate::common::operate_structures::OperateStructure;
+use crate::common::profile::Profile;
+use std::collections::HashMap;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 値を割り当てる元素（変数）の間の制約
+// name: 制約の名前（集計に使う）
+// scope: 制約に関わる変数の番号
+// check: scopeの順に並べた値を受け取り, 制約を満たすときtrueを返す関数
+// scopeの変数が全て割り当てられた時点で判定される（scopeが空の制約は最初に1度だけ判定される）
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct Constraint<'a> {
+    pub name: &'static str,
+    pub scope: Vec<usize>,
+    pub check: ConstraintFn<'a>,
+}
+
+pub type ConstraintFn<'a> = Box<dyn Fn(&[Option<i32>]) -> bool + Send + Sync + 'a>;
+
+// キャッシュのキー: (成分の変数, 成分と制約を共有する割り当て済みの変数とその値)
+type CacheKey = (Vec<usize>, Vec<(usize, Option<i32>)>);
+
+impl<'a> Constraint<'a> {
+    // コンストラクタ
+    pub fn new<F>(name: &'static str, scope: Vec<usize>, check: F) -> Self
+    where
+        F: Fn(&[Option<i32>]) -> bool + Send + Sync + 'a,
+    {
+        Constraint {
+            name,
+            scope,
+            check: Box::new(check),
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // scopeの変数の値が全て異なるという制約を作る関数
+    // 2変数ずつの制約に分けるので, 途中まで割り当てた段階で枝刈りできる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // name: &'static str - 制約の名前
+    // scope: &Vec<usize> - 変数の番号
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<Constraint<'a>> - 2変数ずつの制約の列
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn all_different(name: &'static str, scope: &Vec<usize>) -> Vec<Constraint<'a>> {
+        let mut constraints = vec![];
+        for (k, &x) in scope.iter().enumerate() {
+            for &y in scope[k + 1..].iter() {
+                constraints.push(Constraint::new(name, vec![x, y], |values| {
+                    values[0] != values[1]
+                }));
+            }
+        }
+        return constraints;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 構造体に含まれる変数の番号を求める関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // free: &Vec<Structure> - 値を割り当てる元素列（番号は列の位置）
+    // parent: &Structure - 構造体
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<usize> - parentに含まれる元素の番号
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn scope_of(free: &Vec<Structure>, parent: &Structure) -> Vec<usize> {
+        let contains = OperateStructure::extract_contains_structures(free, parent);
+        return free
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| {
+                contains
+                    .iter()
+                    .any(|y| OperateStructure::compare_structures(x, y))
+            })
+            .map(|(k, _)| k)
+            .collect();
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 制約を満たす値の割り当ての個数を数える（モデル計数）
+// 変数を1つずつ割り当て, 残りの変数が制約で繋がっていない成分に分かれたら成分ごとに数えて掛け合わせる
+// 成分の個数は, 成分の変数と, 成分と制約を共有する割り当て済みの変数の値だけで決まるのでキャッシュする
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct ModelCounter<'c, 'a> {
+    domains: Vec<Vec<Option<i32>>>,
+    constraints: &'c Vec<Constraint<'a>>,
+    // watch[v]: 変数vを含む制約の番号
+    watch: Vec<Vec<usize>>,
+    values: Vec<Option<Option<i32>>>,
+    cache: HashMap<CacheKey, u128>,
+}
+
+impl<'c, 'a> ModelCounter<'c, 'a> {
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 割り当ての個数を数える関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // domains: Vec<Vec<Option<i32>>> - 各変数の定義域
+    // constraints: &Vec<Constraint> - 制約の列
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // u128 - 全ての制約を満たす割り当ての個数
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn count(domains: Vec<Vec<Option<i32>>>, constraints: &'c Vec<Constraint<'a>>) -> u128 {
+        let n = domains.len();
+        let mut watch = vec![vec![]; n];
+        for (k, constraint) in constraints.iter().enumerate() {
+            if constraint.scope.is_empty() {
+                if !Profile::check_named(
+                    "constraint",
+                    constraint.name,
+                    (constraint.check)(&[]),
+                    || "[]".to_string(),
+                ) {
+                    return 0;
+                }
+            }
+            for &v in constraint.scope.iter() {
+                if !watch[v].contains(&k) {
+                    watch[v].push(k);
+                }
+            }
+        }
+        let mut counter = ModelCounter {
+            domains,
+            constraints,
+            watch,
+            values: vec![None; n],
+            cache: HashMap::new(),
+        };
+        let vars: Vec<usize> = (0..n).collect();
+        let mut total: u128 = 1;
+        for component in counter.components(&vars) {
+            total *= counter.count_component(component);
+            if total == 0 {
+                break;
+            }
+        }
+        return total;
+    }
+
+    // 未割り当ての変数を, 未判定の制約で繋がった成分に分ける
+    fn components(&self, vars: &[usize]) -> Vec<Vec<usize>> {
+        let mut component_of: HashMap<usize, usize> = HashMap::new();
+        let mut components: Vec<Vec<usize>> = vec![];
+        for &start in vars.iter() {
+            if component_of.contains_key(&start) {
+                continue;
+            }
+            let id = components.len();
+            let mut component = vec![start];
+            component_of.insert(start, id);
+            let mut k = 0;
+            while k < component.len() {
+                let v = component[k];
+                for &c in self.watch[v].iter() {
+                    for &w in self.constraints[c].scope.iter() {
+                        if self.values[w].is_none() && !component_of.contains_key(&w) {
+                            component_of.insert(w, id);
+                            component.push(w);
+                        }
+                    }
+                }
+                k += 1;
+            }
+            component.sort();
+            components.push(component);
+        }
+        return components;
+    }
+
+    fn count_component(&mut self, vars: Vec<usize>) -> u128 {
+        // 成分と制約を共有する割り当て済みの変数の値
+        let mut boundary: Vec<(usize, Option<i32>)> = vec![];
+        for &v in vars.iter() {
+            for &c in self.watch[v].iter() {
+                for &w in self.constraints[c].scope.iter() {
+                    if let Some(value) = self.values[w] {
+                        boundary.push((w, value));
+                    }
+                }
+            }
+        }
+        boundary.sort();
+        boundary.dedup();
+        let key = (vars, boundary);
+        if let Some(&count) = self.cache.get(&key) {
+            return count;
+        }
+        let vars = &key.0;
+
+        // 制約の多い変数から割り当てる
+        let &var = vars
+            .iter()
+            .max_by_key(|&&v| (self.watch[v].len(), std::cmp::Reverse(v)))
+            .unwrap();
+        let rest: Vec<usize> = vars.iter().copied().filter(|&v| v != var).collect();
+        let mut total: u128 = 0;
+        for value in self.domains[var].clone() {
+            self.values[var] = Some(value);
+            if self.consistent(var) {
+                let mut product: u128 = 1;
+                for component in self.components(&rest) {
+                    product *= self.count_component(component);
+                    if product == 0 {
+                        break;
+                    }
+                }
+                total += product;
+            }
+        }
+        self.values[var] = None;
+
+        self.cache.insert(key, total);
+        return total;
+    }
+
+    // 変数varを含み, 全ての変数が割り当てられた制約を判定する
+    fn consistent(&self, var: usize) -> bool {
+        for &c in self.watch[var].iter() {
+            let constraint = &self.constraints[c];
+            let values: Option<Vec<Option<i32>>> =
+                constraint.scope.iter().map(|&w| self.values[w]).collect();
+            if let Some(values) = values {
+                let passed = (constraint.check)(&values);
+                if !Profile::check_named("constraint", constraint.name, passed, || {
+                    format!("{:?}", values)
+                }) {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        // 3変数が全て異なる: 3! = 6通り, 独立な変数が1つ加わると3倍
+        let domains: Vec<Vec<Option<i32>>> = vec![(1..=3).map(Some).collect(); 4];
+        let constraints = Constraint::all_different("all_different", &vec![0, 1, 2]);
+        assert_eq!(ModelCounter::count(domains.clone(), &constraints), 18);
+
+        // 4変数の鎖 x0 != x1 != x2 != x3 は 3 * 2^3 = 24通り, 末尾を1に固定すると8通り
+        let mut constraints: Vec<Constraint> = (0..3)
+            .flat_map(|k| Constraint::all_different("chain", &vec![k, k + 1]))
+            .collect();
+        assert_eq!(ModelCounter::count(domains.clone(), &constraints), 24);
+        constraints.push(Constraint::new("fixed", vec![3], |values| {
+            values[0] == Some(1)
+        }));
+        assert_eq!(ModelCounter::count(domains.clone(), &constraints), 8);
+        constraints.push(Constraint::new("never", vec![], |_| false));
+        assert_eq!(ModelCounter::count(domains, &constraints), 0);
+    }
+}
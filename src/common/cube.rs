@@ -0,0 +1,216 @@
+use crate::common::dataclass::{Attribute, BoardSize3, Coordinate3, Element, Structure};
+use crate::common::relationship::Relationship;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 立体の盤面（層×行×列の立方体の格子）
+// 元素の座標は(層, 行, 列)で, 平面の盤面と同じく1から数える
+//   P3(l, i, j): 格子点
+//   C3(l, i, j): 立方体のセル
+//   Fl(l, i, j): セルC3(l, i, j)の層の向きの手前（l-1の側）の面
+//   Fr(l, i, j): セルC3(l, i, j)の行の向きの手前（上の側）の面
+//   Fc(l, i, j): セルC3(l, i, j)の列の向きの手前（左の側）の面
+// 2倍の座標: P3 -> (2l-1, 2i-1, 2j-1), C3 -> (2l, 2i, 2j),
+//           Fl -> (2l-1, 2i, 2j), Fr -> (2l, 2i-1, 2j), Fc -> (2l, 2i, 2j-1)
+// ---------------------------------------------------------------------------------------------------------------------
+
+// 2倍の座標(層, 行, 列)
+pub fn position3(X: &Element) -> (i32, i32, i32) {
+    let Coordinate3(l, i, j) = X.coor3();
+    match X.attr {
+        Attribute::P3 => return (2 * l - 1, 2 * i - 1, 2 * j - 1),
+        Attribute::C3 => return (2 * l, 2 * i, 2 * j),
+        Attribute::Fl => return (2 * l - 1, 2 * i, 2 * j),
+        Attribute::Fr => return (2 * l, 2 * i - 1, 2 * j),
+        Attribute::Fc => return (2 * l, 2 * i, 2 * j - 1),
+        _ => panic!("立体の盤面の元素ではありません！"),
+    }
+}
+
+// 2倍の座標での差(層, 行, 列), どちらかが立体の盤面の元素でなければNone
+fn difference3(X: &Element, Y: &Element) -> Option<(i32, i32, i32)> {
+    if !X.is_3d() || !Y.is_3d() {
+        return None;
+    }
+    let (x, y) = (position3(X), position3(Y));
+    return Some((y.0 - x.0, y.1 - x.1, y.2 - x.2));
+}
+
+// 同じ属性の元素が, 2倍の座標でaxisの向きにだけ2離れているか
+fn along(X: &Element, Y: &Element, axis: usize) -> bool {
+    if X.attr != Y.attr {
+        return false;
+    }
+    match difference3(X, Y) {
+        Some(d) => {
+            let d = [d.0, d.1, d.2];
+            return (0..3).all(|k| {
+                if k == axis {
+                    d[k].abs() == 2
+                } else {
+                    d[k] == 0
+                }
+            });
+        }
+        None => return false,
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 比較した2つの元素が立体の盤面で列の向きに隣接しているかを確認する関数（平面のHに当たる）
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// X: &Element - 比較したい元素 1
+// Y: &Element - 比較したい元素 2
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// bool - 隣接であるときtrue
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn H3(X: &Element, Y: &Element) -> bool {
+    return along(X, Y, 2);
+}
+
+// 比較した2つの元素が立体の盤面で行の向きに隣接しているか（平面のVに当たる）
+pub fn V3(X: &Element, Y: &Element) -> bool {
+    return along(X, Y, 1);
+}
+
+// 比較した2つの元素が立体の盤面で層の向きに隣接しているか
+pub fn U3(X: &Element, Y: &Element) -> bool {
+    return along(X, Y, 0);
+}
+
+// 6近傍の隣接関係（combineのRにそのまま渡せる）
+pub const SIX_NEIGHBOURS: [Relationship; 3] = [H3, V3, U3];
+
+// 比較した2つの元素がセルとその周りの6つの面であるか
+pub fn borders3(X: &Element, Y: &Element) -> bool {
+    let faces = [Attribute::Fl, Attribute::Fr, Attribute::Fc];
+    let pair = (X.attr == Attribute::C3 && faces.contains(&Y.attr))
+        || (Y.attr == Attribute::C3 && faces.contains(&X.attr));
+    match difference3(X, Y) {
+        Some((a, b, c)) => return pair && a.abs() + b.abs() + c.abs() == 1,
+        None => return false,
+    }
+}
+
+// 比較した2つの元素がセルとその8つの角の格子点であるか
+pub fn corner3(X: &Element, Y: &Element) -> bool {
+    let pair = (X.attr == Attribute::C3 && Y.attr == Attribute::P3)
+        || (X.attr == Attribute::P3 && Y.attr == Attribute::C3);
+    match difference3(X, Y) {
+        Some((a, b, c)) => return pair && a.abs() == 1 && b.abs() == 1 && c.abs() == 1,
+        None => return false,
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 立体の盤面の元素列の初期化を行う関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// board_size: &BoardSize3 - 盤面サイズ(層, 行, 列)
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// (Vec<Structure>, Vec<Structure>, Vec<Structure>) - (P3, C3, F)（FはFl, Fr, Fcの順）
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn initialize3(board_size: &BoardSize3) -> (Vec<Structure>, Vec<Structure>, Vec<Structure>) {
+    let (k, n, m) = (board_size.0, board_size.1, board_size.2);
+    let grid = |attr: Attribute, layers: i32, rows: i32, columns: i32| {
+        let mut elements: Vec<Structure> = Vec::new();
+        for l in 0..layers {
+            for i in 0..rows {
+                for j in 0..columns {
+                    let coordinate = Coordinate3(l + 1, i + 1, j + 1);
+                    elements.push(Structure::Element(Element::new3(attr.clone(), coordinate)));
+                }
+            }
+        }
+        return elements;
+    };
+
+    let P = grid(Attribute::P3, k + 1, n + 1, m + 1);
+    let C = grid(Attribute::C3, k, n, m);
+    let mut F = grid(Attribute::Fl, k + 1, n, m);
+    F.extend(grid(Attribute::Fr, k, n + 1, m));
+    F.extend(grid(Attribute::Fc, k, n, m + 1));
+    return (P, C, F);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::combine;
+    use crate::common::count::Constraint;
+    use crate::common::operate_structures::OperateStructure;
+    use crate::common::profile::Named;
+    use crate::common::relationship::{H, M};
+    use crate::common::zdd::Zdd;
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+
+    #[test]
+    fn test_cube() {
+        let (P, C, F) = initialize3(&BoardSize3(2, 2, 2));
+        assert_eq!((P.len(), C.len(), F.len()), (27, 8, 36));
+
+        // 3×3×3の中央のセルは6つのセルと隣接する
+        let (_, C27, _) = initialize3(&BoardSize3(3, 3, 3));
+        let centre = Element::new3(Attribute::C3, Coordinate3(2, 2, 2));
+        let around = |r: Relationship, items: &Vec<Structure>| {
+            items
+                .iter()
+                .filter(|s| r(&centre, s.as_element().unwrap()))
+                .count()
+        };
+        let six: usize = SIX_NEIGHBOURS.iter().map(|&r| around(r, &C27)).sum();
+        assert_eq!(six, 6);
+        let corner = Element::new3(Attribute::C3, Coordinate3(1, 1, 1));
+        let count = |r: Relationship, items: &Vec<Structure>| {
+            items
+                .iter()
+                .filter(|s| r(&corner, s.as_element().unwrap()))
+                .count()
+        };
+        assert_eq!(count(borders3, &F), 6);
+        assert_eq!(count(corner3, &P), 8);
+        assert_eq!(count(U3, &C), 1);
+
+        // 層が違えば一致しない, 平面の隣接関係Hは立体の元素には成り立たない
+        let upper = Element::new3(Attribute::C3, Coordinate3(2, 1, 1));
+        assert!(!M(&corner, &upper) && M(&corner, &corner));
+        assert!(!H(
+            &corner,
+            &Element::new3(Attribute::C3, Coordinate3(1, 1, 2))
+        ));
+
+        // 行と列が同じでも層が違えば別の元素として扱う（構造体の比較, 変数の番号, ZDDの変数）
+        let lower = Element::new3(Attribute::C3, Coordinate3(1, 2, 1));
+        let above = Element::new3(Attribute::C3, Coordinate3(2, 2, 1));
+        assert_ne!(lower.key(), above.key());
+        assert!(!OperateStructure::compare_structures(
+            &Structure::Element(lower.clone()),
+            &Structure::Element(above.clone())
+        ));
+        let pair: Vec<Structure> = vec![lower.clone().into(), above.clone().into()];
+        let only_above: Structure = vec![Structure::Element(above.clone())].into();
+        assert_eq!(Constraint::scope_of(&pair, &only_above), vec![1]);
+        let mut zdd = Zdd::new(&pair);
+        assert_eq!(zdd.vars_of(&only_above), vec![1]);
+        let family = zdd.from_structures(&vec![only_above.clone()]);
+        assert_eq!(zdd.count(family), 1);
+
+        // combineはそのまま使える: 2×2×1（4つのセルの輪）の連結な部分集合は13個, 1×1×3の列は6個
+        let no_cutoff: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+        let (_, ring, _) = initialize3(&BoardSize3(1, 2, 2));
+        assert_eq!(
+            combine(SIX_NEIGHBOURS.to_vec(), vec![M], &ring, &no_cutoff).len(),
+            13
+        );
+        let (_, column, _) = initialize3(&BoardSize3(3, 1, 1));
+        assert_eq!(
+            combine(SIX_NEIGHBOURS.to_vec(), vec![M], &column, &no_cutoff).len(),
+            6
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::common::diagonal;
+use crate::common::hex;
 use std::fmt;
 
 // 元素ではない構造体
@@ -13,6 +15,8 @@ pub struct Element {
     pub val: Option<i32>,
     pub attr: Attribute,
     pub coor: Coordinate,
+    // 立体の盤面の層（cube.rs）, 平面の盤面の元素は0
+    pub layer: i32,
 }
 
 // 構造体enum
@@ -31,15 +35,47 @@ pub enum Attribute {
     Vp,
     Hc,
     Vc,
+    // 六角形の盤面の頂点, セル, 頂点どうしの辺, セルどうしの辺（hex.rs）
+    Xp,
+    Xc,
+    Xep,
+    Xec,
+    // 平面グラフの頂点, 面, 辺, 双対辺（graph.rs）
+    Gp,
+    Gc,
+    Gep,
+    Gec,
+    // 立体の盤面の格子点, 立方体のセル, 層・行・列の向きに垂直な面（cube.rs）
+    P3,
+    C3,
+    Fl,
+    Fr,
+    Fc,
+    // セルの斜線（/と\）, 直角の角（北西・北東・南西・南東）で区別したセルの半分の三角形（diagonal.rs）
+    Ds,
+    Db,
+    Tnw,
+    Tne,
+    Tsw,
+    Tse,
 }
 
 // 元素の座標
 #[derive(Clone, PartialEq, Hash, Eq)]
 pub struct Coordinate(pub i32, pub i32);
 
+// 立体の盤面の元素の座標(層, 行, 列)
+#[derive(Clone, PartialEq, Hash, Eq)]
+pub struct Coordinate3(pub i32, pub i32, pub i32);
+
 // 盤面のサイズ
+#[derive(Clone)]
 pub struct BoardSize(pub i32, pub i32);
 
+// 立体の盤面サイズ(層, 行, 列)
+#[derive(Clone)]
+pub struct BoardSize3(pub i32, pub i32, pub i32);
+
 impl Element {
     // コンストラクタ, 初期解はNone
     pub fn new(attr: Attribute, coor: Coordinate) -> Self {
@@ -47,8 +83,70 @@ impl Element {
             val: None,
             attr,
             coor,
+            layer: 0,
+        }
+    }
+
+    // 立体の盤面の元素のコンストラクタ, 初期解はNone
+    pub fn new3(attr: Attribute, coor: Coordinate3) -> Self {
+        Element {
+            val: None,
+            attr,
+            coor: Coordinate(coor.1, coor.2),
+            layer: coor.0,
         }
     }
+
+    // 立体の座標(層, 行, 列)
+    pub fn coor3(&self) -> Coordinate3 {
+        return Coordinate3(self.layer, self.coor.0, self.coor.1);
+    }
+
+    // 値を除いた元素の識別子（属性, 座標, 層）
+    pub fn key(&self) -> (Attribute, Coordinate, i32) {
+        return (self.attr.clone(), self.coor.clone(), self.layer);
+    }
+
+    // 値を除いて同じ元素か（立体の盤面では層も比べる）
+    pub fn same(&self, other: &Element) -> bool {
+        return self.attr == other.attr && self.coor == other.coor && self.layer == other.layer;
+    }
+
+    // 立体の盤面の元素か
+    pub fn is_3d(&self) -> bool {
+        return matches!(
+            self.attr,
+            Attribute::P3 | Attribute::C3 | Attribute::Fl | Attribute::Fr | Attribute::Fc
+        );
+    }
+
+    // 辺の元素の両端の元素（格子点辺なら格子点, セル辺ならセル）, 辺でなければNone
+    pub fn endpoints(&self) -> Option<(Element, Element)> {
+        let (attr, (a, b)) = match self.attr {
+            Attribute::Hp => (Attribute::P, self.coor.horizon_points()),
+            Attribute::Vp => (Attribute::P, self.coor.vertical_points()),
+            Attribute::Hc => (Attribute::C, self.coor.horizon_points()),
+            Attribute::Vc => (Attribute::C, self.coor.vertical_points()),
+            Attribute::Xep => (Attribute::Xp, hex::ends(&self.coor, true)),
+            Attribute::Xec => (Attribute::Xc, hex::ends(&self.coor, false)),
+            // 平面グラフの辺の座標は両端の番号の組
+            Attribute::Gep | Attribute::Gec => {
+                let attr = if self.attr == Attribute::Gep {
+                    Attribute::Gp
+                } else {
+                    Attribute::Gc
+                };
+                (
+                    attr,
+                    (Coordinate(self.coor.0, 0), Coordinate(self.coor.1, 0)),
+                )
+            }
+            // セルの斜線の両端は格子点
+            Attribute::Ds | Attribute::Db => (Attribute::P, diagonal::ends(self)),
+            _ => return None,
+        };
+        return Some((Element::new(attr.clone(), a), Element::new(attr, b)));
+    }
 }
 
 impl Composition {
@@ -58,7 +156,120 @@ impl Composition {
     }
 }
 
-//TODO: StructureにCompositon, Elementに対してそれぞれvalのsetter, getterの実装
+// Structureの値と中身を扱うメソッド
+impl Structure {
+    // 値
+    pub fn val(&self) -> Option<i32> {
+        match self {
+            Structure::Composition(ref composition) => return composition.val,
+            Structure::Element(ref element) => return element.val,
+        }
+    }
+
+    // 値を割り当てる
+    pub fn set_val(&mut self, val: Option<i32>) {
+        match self {
+            Structure::Composition(ref mut composition) => composition.val = val,
+            Structure::Element(ref mut element) => element.val = val,
+        }
+    }
+
+    // 元素ならSome
+    pub fn as_element(&self) -> Option<&Element> {
+        if let Structure::Element(ref element) = self {
+            return Some(element);
+        }
+        return None;
+    }
+
+    // 元素ならSome（書き換え用）
+    pub fn as_element_mut(&mut self) -> Option<&mut Element> {
+        if let Structure::Element(ref mut element) = self {
+            return Some(element);
+        }
+        return None;
+    }
+
+    // 元素ではない構造体ならSome
+    pub fn as_composition(&self) -> Option<&Composition> {
+        if let Structure::Composition(ref composition) = self {
+            return Some(composition);
+        }
+        return None;
+    }
+
+    // 元素ではない構造体ならSome（書き換え用）
+    pub fn as_composition_mut(&mut self) -> Option<&mut Composition> {
+        if let Structure::Composition(ref mut composition) = self {
+            return Some(composition);
+        }
+        return None;
+    }
+
+    // 含まれる元素の個数（入れ子の構造体も辿る）, 元素なら1
+    pub fn len(&self) -> usize {
+        match self {
+            Structure::Composition(ref composition) => {
+                return composition.entity.iter().map(|s| s.len()).sum()
+            }
+            Structure::Element(_) => return 1,
+        }
+    }
+
+    // 元素を1つも含まない
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    // 含まれる元素（入れ子の構造体も辿る）, 元素なら自身
+    pub fn elements(&self) -> Vec<&Element> {
+        let mut elements = vec![];
+        self.collect_elements(&mut elements);
+        return elements;
+    }
+
+    fn collect_elements<'a>(&'a self, elements: &mut Vec<&'a Element>) {
+        match self {
+            Structure::Composition(ref composition) => {
+                for s in composition.entity.iter() {
+                    s.collect_elements(elements);
+                }
+            }
+            Structure::Element(ref element) => elements.push(element),
+        }
+    }
+
+    // 含まれる元素を書き換える（入れ子の構造体も辿る）
+    pub fn for_each_element_mut<F: FnMut(&mut Element)>(&mut self, f: &mut F) {
+        match self {
+            Structure::Composition(ref mut composition) => {
+                for s in composition.entity.iter_mut() {
+                    s.for_each_element_mut(f);
+                }
+            }
+            Structure::Element(ref mut element) => f(element),
+        }
+    }
+}
+
+impl From<Element> for Structure {
+    fn from(element: Element) -> Self {
+        Structure::Element(element)
+    }
+}
+
+impl From<Composition> for Structure {
+    fn from(composition: Composition) -> Self {
+        Structure::Composition(composition)
+    }
+}
+
+// 構造体の列を1つの構造体にまとめる
+impl From<Vec<Structure>> for Structure {
+    fn from(entity: Vec<Structure>) -> Self {
+        Structure::Composition(Composition::new(entity))
+    }
+}
 
 // 座標を操るうえで便利なメソッド
 impl Coordinate {
@@ -113,6 +324,9 @@ impl fmt::Debug for Composition {
 
 impl fmt::Debug for Element {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_3d() {
+            return write!(f, "({:?}{:?}, {:?})", self.attr, self.coor3(), self.val);
+        }
         write!(f, "({:?}{:?}, {:?})", self.attr, self.coor, self.val)
     }
 }
@@ -126,12 +340,78 @@ impl fmt::Debug for Attribute {
             Attribute::Vp => write!(f, "vp"),
             Attribute::Hc => write!(f, "hc"),
             Attribute::Vc => write!(f, "vc"),
+            Attribute::Xp => write!(f, "xp"),
+            Attribute::Xc => write!(f, "xc"),
+            Attribute::Xep => write!(f, "xep"),
+            Attribute::Xec => write!(f, "xec"),
+            Attribute::Gp => write!(f, "gp"),
+            Attribute::Gc => write!(f, "gc"),
+            Attribute::Gep => write!(f, "gep"),
+            Attribute::Gec => write!(f, "gec"),
+            Attribute::P3 => write!(f, "p3"),
+            Attribute::C3 => write!(f, "c3"),
+            Attribute::Fl => write!(f, "fl"),
+            Attribute::Fr => write!(f, "fr"),
+            Attribute::Fc => write!(f, "fc"),
+            Attribute::Ds => write!(f, "ds"),
+            Attribute::Db => write!(f, "db"),
+            Attribute::Tnw => write!(f, "tnw"),
+            Attribute::Tne => write!(f, "tne"),
+            Attribute::Tsw => write!(f, "tsw"),
+            Attribute::Tse => write!(f, "tse"),
         }
     }
 }
 
+impl fmt::Debug for Coordinate3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{},{})", self.0, self.1, self.2)
+    }
+}
+
 impl fmt::Debug for Coordinate {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "({},{})", self.0, self.1)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structure() {
+        let c11: Structure = Element::new(Attribute::C, Coordinate(1, 1)).into();
+        let c12: Structure = Element::new(Attribute::C, Coordinate(1, 2)).into();
+        let c21: Structure = Element::new(Attribute::C, Coordinate(2, 1)).into();
+        let inner: Structure = vec![c11.clone(), c12.clone()].into();
+        let mut area: Structure = vec![inner, c21.clone()].into();
+
+        assert_eq!(area.len(), 3);
+        assert!(!area.is_empty());
+        assert!(area.as_element().is_none());
+        assert_eq!(area.as_composition().unwrap().entity.len(), 2);
+        assert_eq!(
+            area.elements()
+                .iter()
+                .map(|e| e.coor.clone())
+                .collect::<Vec<_>>(),
+            vec![Coordinate(1, 1), Coordinate(1, 2), Coordinate(2, 1)]
+        );
+
+        area.set_val(Some(5));
+        assert_eq!(area.val(), Some(5));
+        area.for_each_element_mut(&mut |e| e.val = Some(e.coor.1));
+        assert_eq!(
+            area.elements().iter().map(|e| e.val).collect::<Vec<_>>(),
+            vec![Some(1), Some(2), Some(1)]
+        );
+
+        let mut cell = c11;
+        cell.set_val(Some(3));
+        assert_eq!(cell.val(), Some(3));
+        assert_eq!(cell.len(), 1);
+        cell.as_element_mut().unwrap().val = None;
+        assert_eq!(cell.as_element().unwrap().val, None);
+    }
+}
@@ -0,0 +1,289 @@
+use crate::common::dataclass::{Attribute, Coordinate, Element, Structure};
+use crate::common::initialize::Mask;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 斜線で分けたセル
+// 元素の座標はそれを含むセルC(i, j)と同じ(i, j)
+//   Ds : / の斜線, 格子点P(i+1, j)とP(i, j+1)を結ぶ
+//   Db : \ の斜線, 格子点P(i, j)とP(i+1, j+1)を結ぶ
+//   Tnw, Tne, Tsw, Tse: 斜線で分けたセルの半分の三角形, 直角の角（北西・北東・南西・南東）で区別する
+//                       Tnw, Tseは / で, Tne, Tswは \ で分けた半分
+// ごきげんななめの斜線, シャカシャカの黒い三角形などを表す
+// 斜線は格子点を結ぶ辺なので, Element::endpointsを通してonly_cycle, only_lineなどがそのまま使える
+// ---------------------------------------------------------------------------------------------------------------------
+
+// 斜線の元素か
+pub fn is_diagonal(element: &Element) -> bool {
+    return matches!(element.attr, Attribute::Ds | Attribute::Db);
+}
+
+// 三角形の元素か
+pub fn is_triangle(element: &Element) -> bool {
+    return matches!(
+        element.attr,
+        Attribute::Tnw | Attribute::Tne | Attribute::Tsw | Attribute::Tse
+    );
+}
+
+// 斜線の両端の格子点の座標
+pub fn ends(element: &Element) -> (Coordinate, Coordinate) {
+    let (i, j) = (element.coor.0, element.coor.1);
+    match element.attr {
+        Attribute::Ds => return (Coordinate(i + 1, j), Coordinate(i, j + 1)),
+        Attribute::Db => return (Coordinate(i, j), Coordinate(i + 1, j + 1)),
+        _ => panic!("斜線ではない元素です！"),
+    }
+}
+
+// 三角形の斜辺になる斜線の属性
+pub fn hypotenuse(element: &Element) -> Attribute {
+    match element.attr {
+        Attribute::Tnw | Attribute::Tse => return Attribute::Ds,
+        Attribute::Tne | Attribute::Tsw => return Attribute::Db,
+        _ => panic!("三角形ではない元素です！"),
+    }
+}
+
+// 三角形の直角の角の(行, 列)の向き（北西なら(0, 0), 南東なら(1, 1)）
+fn right_angle(element: &Element) -> (i32, i32) {
+    match element.attr {
+        Attribute::Tnw => return (0, 0),
+        Attribute::Tne => return (0, 1),
+        Attribute::Tsw => return (1, 0),
+        Attribute::Tse => return (1, 1),
+        _ => panic!("三角形ではない元素です！"),
+    }
+}
+
+// 三角形の3つの頂点の格子点の座標（セルの角のうち直角の向かいの角を除いたもの）
+pub fn vertices(element: &Element) -> Vec<Coordinate> {
+    let (i, j) = (element.coor.0, element.coor.1);
+    let (a, b) = right_angle(element);
+    let mut corners = vec![];
+    for (di, dj) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+        if (di, dj) != (1 - a, 1 - b) {
+            corners.push(Coordinate(i + di, j + dj));
+        }
+    }
+    return corners;
+}
+
+// 三角形の2つの直角をはさむ辺（格子点辺HpとVp）
+pub fn legs(element: &Element) -> Vec<Element> {
+    let (i, j) = (element.coor.0, element.coor.1);
+    let (a, b) = right_angle(element);
+    return vec![
+        Element::new(Attribute::Hp, Coordinate(i + a, j)),
+        Element::new(Attribute::Vp, Coordinate(i, j + b)),
+    ];
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 比較した2つの元素が端を共有する斜線であるかを確認する関数（斜線が繋がって線になる）
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// X: &Element - 比較したい元素 1
+// Y: &Element - 比較したい元素 2
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// bool - 隣接であるときtrue
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn diagonal_adjacent(X: &Element, Y: &Element) -> bool {
+    if !is_diagonal(X) || !is_diagonal(Y) || X == Y {
+        return false;
+    }
+    let (a, b) = ends(X);
+    let (c, d) = ends(Y);
+    return a == c || a == d || b == c || b == d;
+}
+
+// 比較した2つの元素が同じセルで交わる斜線（DsとDb）であるか
+pub fn diagonal_cross(X: &Element, Y: &Element) -> bool {
+    return is_diagonal(X) && is_diagonal(Y) && X.attr != Y.attr && X.coor == Y.coor;
+}
+
+// 比較した2つの元素が斜線とその端の格子点であるか
+pub fn diagonal_endpoint(X: &Element, Y: &Element) -> bool {
+    if is_diagonal(X) && Y.attr == Attribute::P {
+        let (a, b) = ends(X);
+        return Y.coor == a || Y.coor == b;
+    }
+    if X.attr == Attribute::P && is_diagonal(Y) {
+        return diagonal_endpoint(Y, X);
+    }
+    return false;
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 比較した2つの元素が辺を共有する三角形であるかを確認する関数
+// 同じセルでは斜辺を共有する残りの半分, 隣のセルでは同じ格子点辺を直角をはさむ辺に持つ三角形と隣接する
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// X: &Element - 比較したい元素 1
+// Y: &Element - 比較したい元素 2
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// bool - 隣接であるときtrue
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn triangle_adjacent(X: &Element, Y: &Element) -> bool {
+    if !is_triangle(X) || !is_triangle(Y) || X == Y {
+        return false;
+    }
+    if X.coor == Y.coor {
+        return X.attr != Y.attr && hypotenuse(X) == hypotenuse(Y);
+    }
+    let x_legs = legs(X);
+    return legs(Y).iter().any(|leg| x_legs.contains(leg));
+}
+
+// 比較した2つの元素が三角形とその斜辺の斜線であるか
+pub fn triangle_cut(X: &Element, Y: &Element) -> bool {
+    if is_triangle(X) && is_diagonal(Y) {
+        return X.coor == Y.coor && hypotenuse(X) == Y.attr;
+    }
+    if is_diagonal(X) && is_triangle(Y) {
+        return triangle_cut(Y, X);
+    }
+    return false;
+}
+
+// 比較した2つの元素が三角形とその頂点の格子点であるか
+pub fn triangle_vertex(X: &Element, Y: &Element) -> bool {
+    if is_triangle(X) && Y.attr == Attribute::P {
+        return vertices(X).contains(&Y.coor);
+    }
+    if X.attr == Attribute::P && is_triangle(Y) {
+        return triangle_vertex(Y, X);
+    }
+    return false;
+}
+
+// 比較した2つの元素が三角形とその直角をはさむ辺の格子点辺であるか
+pub fn triangle_leg(X: &Element, Y: &Element) -> bool {
+    if is_triangle(X) && matches!(Y.attr, Attribute::Hp | Attribute::Vp) {
+        let y = Element::new(Y.attr.clone(), Y.coor.clone());
+        return legs(X).contains(&y);
+    }
+    if matches!(X.attr, Attribute::Hp | Attribute::Vp) && is_triangle(Y) {
+        return triangle_leg(Y, X);
+    }
+    return false;
+}
+
+// 比較した2つの元素がセルとそれを分ける斜線または三角形であるか
+pub fn split(X: &Element, Y: &Element) -> bool {
+    if X.attr == Attribute::C && (is_diagonal(Y) || is_triangle(Y)) {
+        return X.coor == Y.coor;
+    }
+    if (is_diagonal(X) || is_triangle(X)) && Y.attr == Attribute::C {
+        return split(Y, X);
+    }
+    return false;
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 斜線と三角形の元素列の初期化を行う関数, 盤面に存在するセルごとに作る
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// mask: &Mask - 盤面の形（長方形の盤面ならMask::full）
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// (Vec<Structure>, Vec<Structure>) - (Dl, T)（DlはDs, Dbの順, TはTnw, Tne, Tsw, Tseの順）
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn initialize_diagonal(mask: &Mask) -> (Vec<Structure>, Vec<Structure>) {
+    let per_cell = |attrs: Vec<Attribute>| {
+        let mut elements: Vec<Structure> = Vec::new();
+        for attr in attrs.iter() {
+            for (i, row) in mask.cells.iter().enumerate() {
+                for (j, &exists) in row.iter().enumerate() {
+                    if exists {
+                        let coordinate = Coordinate(i as i32 + 1, j as i32 + 1);
+                        elements.push(Structure::Element(Element::new(attr.clone(), coordinate)));
+                    }
+                }
+            }
+        }
+        return elements;
+    };
+
+    let Dl = per_cell(vec![Attribute::Ds, Attribute::Db]);
+    let T = per_cell(vec![
+        Attribute::Tnw,
+        Attribute::Tne,
+        Attribute::Tsw,
+        Attribute::Tse,
+    ]);
+    return (Dl, T);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::combine;
+    use crate::common::dataclass::BoardSize;
+    use crate::common::profile::Named;
+    use crate::common::relationship::{Relationship, M};
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+
+    #[test]
+    fn test_diagonal() {
+        let board_size = BoardSize(2, 2);
+        let (Dl, T) = initialize_diagonal(&Mask::full(&board_size));
+        assert_eq!((Dl.len(), T.len()), (8, 16));
+        let (Dl1, T1) =
+            initialize_diagonal(&Mask::from_cells(&board_size, &vec![Coordinate(1, 1)]));
+        assert_eq!((Dl1.len(), T1.len()), (2, 4));
+
+        let slash = Element::new(Attribute::Ds, Coordinate(1, 1));
+        let (a, b) = slash.endpoints().unwrap();
+        assert_eq!((a.coor, b.coor), (Coordinate(2, 1), Coordinate(1, 2)));
+
+        let count = |r: Relationship, X: &Element, items: &Vec<Structure>| {
+            items
+                .iter()
+                .filter(|s| r(X, s.as_element().unwrap()))
+                .count()
+        };
+        let ne = Element::new(Attribute::Tne, Coordinate(1, 1));
+        let points: Vec<Structure> = (1..4)
+            .flat_map(|i| {
+                (1..4)
+                    .map(move |j| Structure::Element(Element::new(Attribute::P, Coordinate(i, j))))
+            })
+            .collect();
+        assert_eq!(count(triangle_vertex, &ne, &points), 3);
+        assert_eq!(count(diagonal_endpoint, &slash, &points), 2);
+        assert_eq!(
+            count(
+                triangle_leg,
+                &ne,
+                &vec![
+                    Structure::Element(Element::new(Attribute::Hp, Coordinate(1, 1))),
+                    Structure::Element(Element::new(Attribute::Vp, Coordinate(1, 2))),
+                    Structure::Element(Element::new(Attribute::Vp, Coordinate(1, 1))),
+                ]
+            ),
+            2
+        );
+        assert_eq!(count(triangle_cut, &ne, &Dl), 1);
+        assert_eq!(count(diagonal_cross, &slash, &Dl), 1);
+        assert_eq!(
+            count(split, &Element::new(Attribute::C, Coordinate(1, 1)), &T),
+            4
+        );
+
+        // 左上のセルの北東の三角形は, 同じセルの南西の三角形と, 右のセルの北西・南西の三角形の3つと辺を共有する
+        assert_eq!(count(triangle_adjacent, &ne, &T), 3);
+
+        // 2×2の盤面で斜線が作る閉曲線は, 中央の格子点を囲むひし形の1つだけ
+        let only_cycle: Vec<Named<CutoffFn>> = named![Cutoff::only_cycle];
+        assert_eq!(
+            combine(vec![diagonal_adjacent], vec![M], &Dl, &only_cycle).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,804 @@
+use crate::common::checkpoint::Checkpoint;
+use crate::common::count::{Constraint, ModelCounter};
+use crate::common::dataclass::{Attribute, Structure};
+use crate::common::profile::Profile;
+use crate::common::serialize::BoardRecord;
+use crate::common::shard::ShardSummary;
+use crate::common::sink::{Sink, SinkBackend};
+use indicatif::{ProgressBar, ProgressStyle};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// 1チャンクあたりの通し番号の個数, チェックポイントはチャンク単位で記録する
+const CHUNK_SIZE: u64 = 4096;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 元素の属性ごとの定義域
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct Domains {
+    pub P: Vec<Option<i32>>,
+    pub C: Vec<Option<i32>>,
+    pub Ep: Vec<Option<i32>>,
+    pub Ec: Vec<Option<i32>>,
+}
+
+impl Domains {
+    // コンストラクタ
+    pub fn new(
+        P: Vec<Option<i32>>,
+        C: Vec<Option<i32>>,
+        Ep: Vec<Option<i32>>,
+        Ec: Vec<Option<i32>>,
+    ) -> Self {
+        Domains { P, C, Ep, Ec }
+    }
+
+    // 属性に対応する定義域を返す
+    pub fn of(&self, attr: &Attribute) -> &Vec<Option<i32>> {
+        match attr {
+            Attribute::P | Attribute::Xp | Attribute::Gp | Attribute::P3 => &self.P,
+            Attribute::C | Attribute::Xc | Attribute::Gc | Attribute::C3 => &self.C,
+            // セルの斜線と三角形はセルの中身なのでセルの定義域を使う
+            Attribute::Ds | Attribute::Db => &self.C,
+            Attribute::Tnw | Attribute::Tne | Attribute::Tsw | Attribute::Tse => &self.C,
+            Attribute::Hp | Attribute::Vp | Attribute::Xep | Attribute::Gep => &self.Ep,
+            // 立体の盤面の面はセルを隔てるので格子点辺の定義域を使う
+            Attribute::Fl | Attribute::Fr | Attribute::Fc => &self.Ep,
+            Attribute::Hc | Attribute::Vc | Attribute::Xec | Attribute::Gec => &self.Ec,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 盤面の配置（ランダムに選んだ構造体など）と, その配置の上で値を列挙する元素の組
+// data: T - 配置ごとにルールが保持するデータ
+// free: Vec<Structure> - 値を列挙する元素, 各元素は属性に対応する定義域の値を取る
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct Layout<T> {
+    pub data: T,
+    pub free: Vec<Structure>,
+}
+
+impl<T> Layout<T> {
+    // コンストラクタ
+    pub fn new(data: T, free: Vec<Structure>) -> Self {
+        Layout { data, free }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 配置の上での値の組み合わせの総数を計算する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // domains: &Domains - 定義域
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Option<u64> - 組み合わせの総数, u64に収まらない場合はNone
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn size(&self, domains: &Domains) -> Option<u64> {
+        let mut size: u64 = 1;
+        for structure in self.free.iter() {
+            if let Structure::Element(ref element) = structure {
+                size = size.checked_mul(domains.of(&element.attr).len() as u64)?;
+            } else {
+                unreachable!()
+            }
+        }
+        return Some(size);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 番号indexの値の組み合わせを元素に割り当てる関数（先頭の元素が最下位の桁になる）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // domains: &Domains - 定義域
+    // index: u64 - 組み合わせの番号, 0 <= index < size
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<Structure> - 値が割り当てられた元素
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn assign(&self, domains: &Domains, index: u64) -> Vec<Structure> {
+        let mut assigned = self.free.clone();
+        let mut rest = index;
+        for structure in assigned.iter_mut() {
+            if let Structure::Element(ref mut element) = structure {
+                let domain = domains.of(&element.attr);
+                let digit = rest % domain.len() as u64;
+                rest /= domain.len() as u64;
+                element.val = domain[digit as usize];
+            }
+        }
+        return assigned;
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// コマンドライン引数で指定する列挙の設定
+// --seed <u64>: 配置の乱数シード（省略時は現在時刻）
+// --resume: チェックポイントから再開する
+// --checkpoint <path>: チェックポイントの保存先（省略時はdata/<rule>.checkpoint）
+// --checkpoint-interval <secs>: チェックポイントを保存する間隔（省略時は60秒）
+// --shard <i>/<k>: 探索空間をk個に分割したうちのi番目だけを列挙する（--seedが必要）
+// --output <path>: 盤面の出力先（省略時は標準出力）
+// --ordered: 盤面を通し番号の順に出力する（同じシードなら出力が一致する）
+// --profile: カットオフ関数・バリデーション・述語ごとの棄却を集計して表示する
+// --profile-first: --profileに加えて, それぞれが最初に棄却した候補を表示する
+// --count: 盤面を出力せず, 制約を満たす盤面の個数だけを数えて標準出力に表示する
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct DriverOptions {
+    pub seed: u64,
+    pub resume: bool,
+    pub checkpoint_path: String,
+    pub checkpoint_interval: Duration,
+    pub shard: Option<(u64, u64)>,
+    pub output: SinkBackend,
+    pub ordered: bool,
+    pub profile: bool,
+    pub profile_first: bool,
+    pub count: bool,
+}
+
+impl DriverOptions {
+    // 既定の設定
+    pub fn new(rule: &str) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        DriverOptions {
+            seed,
+            resume: false,
+            checkpoint_path: format!("data/{}.checkpoint", rule),
+            checkpoint_interval: Duration::from_secs(60),
+            shard: None,
+            output: SinkBackend::Stdout,
+            ordered: false,
+            profile: false,
+            profile_first: false,
+            count: false,
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // コマンドライン引数から設定を読み込む関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // rule: &str - ルール名
+    // args: &[String] - コマンドライン引数（プログラム名を除く）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<DriverOptions, String> - 不正な引数の場合はエラーメッセージ
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn parse(rule: &str, args: &[String]) -> Result<DriverOptions, String> {
+        let mut options = DriverOptions::new(rule);
+        let mut seed_given = false;
+        let mut checkpoint_given = false;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = |name: &str| -> Result<String, String> {
+                iter.next()
+                    .cloned()
+                    .ok_or(format!("{} requires a value", name))
+            };
+            match arg.as_str() {
+                "--seed" => {
+                    let v = value("--seed")?;
+                    options.seed = v.parse().map_err(|_| format!("invalid seed '{}'", v))?;
+                    seed_given = true;
+                }
+                "--resume" => options.resume = true,
+                "--checkpoint" => {
+                    options.checkpoint_path = value("--checkpoint")?;
+                    checkpoint_given = true;
+                }
+                "--checkpoint-interval" => {
+                    let v = value("--checkpoint-interval")?;
+                    let secs: u64 = v.parse().map_err(|_| format!("invalid interval '{}'", v))?;
+                    options.checkpoint_interval = Duration::from_secs(secs);
+                }
+                "--shard" => options.shard = Some(ShardSummary::parse_spec(&value("--shard")?)?),
+                "--output" => options.output = SinkBackend::File(value("--output")?),
+                "--ordered" => options.ordered = true,
+                "--profile" => options.profile = true,
+                "--profile-first" => {
+                    options.profile = true;
+                    options.profile_first = true;
+                }
+                "--count" => options.count = true,
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+        if options.count && (options.resume || options.shard.is_some()) {
+            return Err("--count cannot be combined with --resume or --shard".to_string());
+        }
+        if let Some((index, count)) = options.shard {
+            // 全てのシャードで同じ配置を作るため, シードは明示する
+            if !seed_given && !options.resume {
+                return Err("--shard requires --seed".to_string());
+            }
+            if !checkpoint_given {
+                options.checkpoint_path =
+                    format!("data/{}.checkpoint", ShardSummary::name(rule, index, count));
+            }
+        }
+        return Ok(options);
+    }
+
+    // プロセスのコマンドライン引数から設定を読み込む
+    pub fn from_args(rule: &str) -> Self {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        match DriverOptions::parse(rule, &args) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}", message);
+                eprintln!(
+                    "usage: {} [--seed N] [--resume] [--checkpoint PATH] [--checkpoint-interval SECS] [--shard I/K] [--output PATH] [--ordered] [--profile] [--profile-first] [--count]",
+                    rule
+                );
+                std::process::exit(2);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 列挙の集計
+// layouts: 作成を試みた配置の個数, skipped_layouts: そのうち作成できなかった個数
+// search_space: 定義域から計算した探索空間の大きさ（シャードの場合は担当する区間の大きさ）
+// resumed: 再開前に完了していた候補の個数, tested: この実行で判定した候補の個数
+// rejections: 候補を棄却した述語ごとの棄却数
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug, Default)]
+pub struct RunStats {
+    pub layouts: u64,
+    pub skipped_layouts: u64,
+    pub search_space: u64,
+    pub resumed: u64,
+    pub tested: u64,
+    pub accepted: u64,
+    pub rejections: BTreeMap<&'static str, u64>,
+    pub elapsed: Duration,
+}
+
+impl RunStats {
+    // 集計を標準エラー出力に表示する
+    pub fn print(&self, rule: &str) {
+        eprintln!("---- {} ----", rule);
+        eprintln!(
+            "layouts       : {} ({} could not be sampled)",
+            self.layouts, self.skipped_layouts
+        );
+        eprintln!("search space  : {}", self.search_space);
+        if self.resumed > 0 {
+            eprintln!("resumed       : {}", self.resumed);
+        }
+        eprintln!("tested        : {}", self.tested);
+        eprintln!("accepted      : {}", self.accepted);
+        for (name, count) in self.rejections.iter() {
+            eprintln!("rejected by {} : {}", name, count);
+        }
+        eprintln!("wall time     : {:.3}s", self.elapsed.as_secs_f64());
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 列挙ドライバ
+// 配置をlayout_count個作成し, 各配置の上で値の組み合わせを全て列挙して, 条件を満たす盤面を出力する
+// 探索空間全体に通し番号を振り, 完了した番号の区間をチェックポイントに保存する
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct Driver {
+    pub rule: String,
+    pub domains: Domains,
+    pub options: DriverOptions,
+}
+
+impl Driver {
+    // コンストラクタ, 設定はコマンドライン引数から読み込む
+    pub fn new(rule: &str, domains: Domains) -> Self {
+        Driver {
+            rule: rule.to_string(),
+            domains,
+            options: DriverOptions::from_args(rule),
+        }
+    }
+
+    // 設定を指定するコンストラクタ
+    pub fn with_options(rule: &str, domains: Domains, options: DriverOptions) -> Self {
+        Driver {
+            rule: rule.to_string(),
+            domains,
+            options,
+        }
+    }
+
+    // 番号indexの配置に使う乱数生成器, シードと番号のみから決まる
+    pub fn layout_rng(seed: u64, index: u64) -> StdRng {
+        return StdRng::seed_from_u64(seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    }
+
+    // 番号0..layout_countの配置を並列に作成する
+    fn sample_layouts<T, L>(seed: u64, layout_count: u64, layout_fn: &L) -> Vec<Option<Layout<T>>>
+    where
+        T: Send + Sync,
+        L: Fn(u64, &mut StdRng) -> Option<Layout<T>> + Sync,
+    {
+        let pb_layout = ProgressBar::new(layout_count);
+        pb_layout.set_style(
+            ProgressStyle::default_bar()
+                .template("layout  {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
+                .unwrap(),
+        );
+        let layouts: Vec<Option<Layout<T>>> = (0..layout_count)
+            .into_par_iter()
+            .map(|i| {
+                let mut rng = Driver::layout_rng(seed, i);
+                let layout = layout_fn(i, &mut rng);
+                pb_layout.inc(1);
+                layout
+            })
+            .collect();
+        pb_layout.finish();
+        return layouts;
+    }
+
+    // --countが指定されているか, 指定されている場合はrunの代わりにcountを呼ぶ
+    pub fn counting(&self) -> bool {
+        return self.options.count;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // runのcheck_fnの中で述語を評価する関数, 評価した回数と棄却した回数を述語ごとに集計する
+    // 例: Driver::require("cycle", cond)?;
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // name: &'static str - 述語の名前
+    // passed: bool - 評価結果
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<(), &'static str> - 棄却する場合は述語の名前
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn require(name: &'static str, passed: bool) -> Result<(), &'static str> {
+        Profile::add_named("check", name, 1, if passed { 0 } else { 1 });
+        if !passed {
+            return Err(name);
+        }
+        return Ok(());
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面を出力せずに, 制約を満たす盤面の個数を数える関数
+    // runと同じ配置を作成し, 各配置の自由な元素への値の割り当てをモデル計数で数えて合計する
+    // 同じシードでrunを実行したときに出力される盤面の個数と一致する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // layout_count: u64 - 作成する配置の個数
+    // layout_fn: L - 番号と乱数生成器から配置を作成する関数（runと同じもの）
+    // constraints_fn: G - 配置のデータと自由な元素列から制約の列を作る関数, 変数の番号は自由な元素列の位置
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // u128 - 盤面の個数, 標準出力にも表示する
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn count<T, L, G>(&self, layout_count: u64, layout_fn: L, constraints_fn: G) -> u128
+    where
+        T: Send + Sync,
+        L: Fn(u64, &mut StdRng) -> Option<Layout<T>> + Sync,
+        G: for<'a> Fn(&'a T, &'a Vec<Structure>) -> Vec<Constraint<'a>> + Sync,
+    {
+        let started = Instant::now();
+        if self.options.profile {
+            Profile::configure(true, self.options.profile_first);
+        }
+        let layouts = Driver::sample_layouts(self.options.seed, layout_count, &layout_fn);
+
+        let pb = ProgressBar::new(layout_count);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("count   {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
+                .unwrap(),
+        );
+        let count: u128 = layouts
+            .par_iter()
+            .map(|layout| {
+                let count = match layout {
+                    Some(layout) => {
+                        let constraints = constraints_fn(&layout.data, &layout.free);
+                        let domains = layout
+                            .free
+                            .iter()
+                            .map(|structure| match structure {
+                                Structure::Element(ref element) => {
+                                    self.domains.of(&element.attr).clone()
+                                }
+                                Structure::Composition(_) => vec![None],
+                            })
+                            .collect();
+                        ModelCounter::count(domains, &constraints)
+                    }
+                    None => 0,
+                };
+                pb.inc(1);
+                count
+            })
+            .sum();
+        pb.finish();
+
+        println!("{}", count);
+        eprintln!("---- {} (count) ----", self.rule);
+        eprintln!(
+            "layouts       : {} ({} could not be sampled)",
+            layout_count,
+            layouts.iter().filter(|layout| layout.is_none()).count()
+        );
+        eprintln!("boards        : {}", count);
+        eprintln!("wall time     : {:.3}s", started.elapsed().as_secs_f64());
+        if Profile::enabled() {
+            Profile::print();
+        }
+        return count;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 列挙を実行する関数
+    // layout_fnで配置を作成し, 各配置の全ての値の組み合わせについてcheck_fnを呼び出す
+    // check_fnがOkを返した盤面をJSON Linesで出力先に送り, Errを返した候補は棄却した述語の名前ごとに数える
+    // シャードを指定した場合は担当する区間だけを列挙し, 完了後にdata/<rule>.shard-<i>-of-<k>.summaryを保存する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // layout_count: u64 - 作成する配置の個数
+    // layout_fn: L - 番号と乱数生成器から配置を作成する関数, 配置が作れなかった場合はNone
+    // check_fn: F - 配置のデータと値を割り当てた元素から盤面を判定する関数, 棄却する場合は述語の名前を返す
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // RunStats - 列挙の集計（acceptedは再開前に見つかった盤面を含む）, 標準エラー出力にも表示する
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn run<T, L, F>(&self, layout_count: u64, layout_fn: L, check_fn: F) -> RunStats
+    where
+        T: Send + Sync,
+        L: Fn(u64, &mut StdRng) -> Option<Layout<T>> + Sync,
+        F: Fn(&T, &Vec<Structure>) -> Result<BoardRecord, &'static str> + Sync,
+    {
+        let started = Instant::now();
+        if self.options.profile {
+            Profile::configure(true, self.options.profile_first);
+        }
+        let path = self.options.checkpoint_path.clone();
+        let mut seed = self.options.seed;
+        let mut resumed: Option<Checkpoint> = None;
+        if self.options.resume {
+            match Checkpoint::load(&path) {
+                Ok(checkpoint) => {
+                    if let Err(message) = checkpoint.check_rule(&self.rule) {
+                        Driver::exit_with(&path, &message);
+                    }
+                    seed = checkpoint.seed;
+                    resumed = Some(checkpoint);
+                }
+                Err(message) => eprintln!("cannot resume ({}), starting from scratch", message),
+            }
+        }
+
+        let layouts = Driver::sample_layouts(seed, layout_count, &layout_fn);
+
+        // offsets[i]: 番号iの配置の先頭の通し番号
+        let mut offsets: Vec<u64> = vec![0];
+        for layout in layouts.iter() {
+            let size = match layout {
+                Some(layout) => layout
+                    .size(&self.domains)
+                    .expect("search space does not fit in u64"),
+                None => 0,
+            };
+            let last = *offsets.last().unwrap();
+            offsets.push(
+                last.checked_add(size)
+                    .expect("search space does not fit in u64"),
+            );
+        }
+        let total = *offsets.last().unwrap();
+        let (range_start, range_end) = match self.options.shard {
+            Some((index, count)) => ShardSummary::range(total, index, count),
+            None => (0, total),
+        };
+
+        let sink = Sink::new(self.options.output.clone(), self.options.ordered)
+            .expect("cannot open the output");
+
+        let checkpoint = match resumed {
+            Some(checkpoint) => {
+                if let Err(message) = checkpoint.check_range(total, (range_start, range_end)) {
+                    Driver::exit_with(&path, &message);
+                }
+                // 再開前に見つかった盤面を出力し直す
+                for (index, line) in checkpoint.records.iter() {
+                    sink.send(*index, line.clone());
+                }
+                sink.watermark(checkpoint.done_until(range_start));
+                checkpoint
+            }
+            None => {
+                let mut checkpoint = Checkpoint::new(&self.rule, seed, total);
+                checkpoint.range = (range_start, range_end);
+                checkpoint
+            }
+        };
+
+        let pb = ProgressBar::new(range_end - range_start);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("assign  {bar:40.cyan/blue} {pos}/{len} {percent}% {eta}")
+                .unwrap(),
+        );
+        let mut stats = RunStats::default();
+        stats.layouts = layout_count;
+        stats.skipped_layouts = layouts.iter().filter(|layout| layout.is_none()).count() as u64;
+        stats.search_space = range_end - range_start;
+        stats.resumed = checkpoint.done_count();
+        pb.inc(stats.resumed);
+        let state = Mutex::new((checkpoint, Instant::now(), stats));
+
+        // チャンクの境界は通し番号のCHUNK_SIZEの倍数に揃え, 担当する区間で切り詰める
+        let first_chunk = range_start / CHUNK_SIZE;
+        let last_chunk = range_end.div_ceil(CHUNK_SIZE);
+        (first_chunk..last_chunk).into_par_iter().for_each(|k| {
+            let start = std::cmp::max(k * CHUNK_SIZE, range_start);
+            let end = std::cmp::min((k + 1) * CHUNK_SIZE, range_end);
+            if state.lock().unwrap().0.is_done(start, end) {
+                return;
+            }
+
+            let mut found: Vec<(u64, String)> = Vec::new();
+            let mut rejections: BTreeMap<&'static str, u64> = BTreeMap::new();
+            let mut index = start;
+            while index < end {
+                // indexを含む配置（サイズ0の配置は飛ばす）
+                let i = offsets.partition_point(|&offset| offset <= index) - 1;
+                let layout = layouts[i].as_ref().unwrap();
+                let stop = std::cmp::min(offsets[i + 1], end);
+                for global in index..stop {
+                    let assigned = layout.assign(&self.domains, global - offsets[i]);
+                    match check_fn(&layout.data, &assigned) {
+                        Ok(record) => found.push((global, record.to_json_line())),
+                        Err(name) => {
+                            *rejections.entry(name).or_insert(0) += 1;
+                            Profile::first_named("check", name, || format!("{:?}", assigned));
+                        }
+                    }
+                }
+                index = stop;
+            }
+
+            // 盤面の送信と完了の記録は同じロックの中で行い, watermarkより前の盤面が必ず送られているようにする
+            let mut guard = state.lock().unwrap();
+            for (global, line) in found.iter() {
+                sink.send(*global, line.clone());
+            }
+            let (checkpoint, last_save, stats) = &mut *guard;
+            stats.tested += end - start;
+            for (name, count) in rejections {
+                *stats.rejections.entry(name).or_insert(0) += count;
+            }
+            checkpoint.mark_done(start, end);
+            checkpoint.records.extend(found);
+            sink.watermark(checkpoint.done_until(range_start));
+            if last_save.elapsed() >= self.options.checkpoint_interval {
+                if let Err(e) = checkpoint.save(&path) {
+                    eprintln!("failed to save checkpoint {}: {}", path, e);
+                }
+                *last_save = Instant::now();
+            }
+            pb.inc(end - start);
+        });
+        pb.finish();
+
+        // 最後まで完了したのでチェックポイントは不要
+        let (checkpoint, _, mut stats) = state.into_inner().unwrap();
+        let accepted = checkpoint.records.len() as u64;
+        sink.finish().expect("failed to write the output");
+        if let Some((index, count)) = self.options.shard {
+            let summary = ShardSummary::new(&self.rule, seed, total, index, count, accepted);
+            let summary_path = format!(
+                "data/{}.summary",
+                ShardSummary::name(&self.rule, index, count)
+            );
+            if let Err(e) = summary.save(&summary_path) {
+                eprintln!("failed to save shard summary {}: {}", summary_path, e);
+            }
+            eprintln!(
+                "shard {}/{}: {} boards in [{}, {}) of {}",
+                index, count, accepted, range_start, range_end, total
+            );
+        }
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(format!("{}.tmp", path));
+        stats.accepted = accepted;
+        stats.elapsed = started.elapsed();
+        stats.print(&self.rule);
+        // 述語ごとの評価回数はDriver::requireで集計している（述語は最初の棄却で打ち切られるため）
+        if Profile::enabled() {
+            Profile::print();
+        }
+        return stats;
+    }
+
+    // 再開できないチェックポイントは上書きせずに残し, 0以外の終了コードで終了する
+    fn exit_with(path: &str, message: &str) -> ! {
+        eprintln!("{}: {}", path, message);
+        eprintln!("remove the checkpoint or pass another --checkpoint to start from scratch");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::dataclass::{BoardSize, Coordinate, Element};
+    use std::sync::Arc;
+
+    // 1行2列の盤面の2つのセル（値は1か2）
+    fn two_cells() -> (Vec<Structure>, Domains) {
+        let cells: Vec<Structure> = (1..=2)
+            .map(|j| Structure::Element(Element::new(Attribute::C, Coordinate(1, j))))
+            .collect();
+        let domains = Domains::new(vec![None], vec![Some(1), Some(2)], vec![None], vec![None]);
+        return (cells, domains);
+    }
+
+    // 左のセルが1の盤面だけを出力する
+    fn left_is_one(_: &u64, assigned: &Vec<Structure>) -> Result<BoardRecord, &'static str> {
+        if let Structure::Element(ref left) = assigned[0] {
+            Driver::require("left_is_one", left.val == Some(1))?;
+        }
+        let mut record = BoardRecord::new("test", &BoardSize(1, 2));
+        record.add_elements(assigned);
+        return Ok(record);
+    }
+
+    // 出力をメモリに集める設定
+    fn memory_options(name: &str, lines: &Arc<Mutex<Vec<String>>>) -> DriverOptions {
+        let mut options = DriverOptions::new("test");
+        options.seed = 1;
+        options.ordered = true;
+        options.output = SinkBackend::Memory(lines.clone());
+        options.checkpoint_path = std::env::temp_dir()
+            .join(format!("puzzle_check_{}.checkpoint", name))
+            .to_string_lossy()
+            .to_string();
+        return options;
+    }
+
+    #[test]
+    fn test_run_ordered() {
+        let (cells, domains) = two_cells();
+        let lines = Arc::new(Mutex::new(vec![]));
+        let options = memory_options("test_run_ordered", &lines);
+        let driver = Driver::with_options("test", domains.clone(), options);
+        driver.run(3, |i, _| Some(Layout::new(i, cells.clone())), left_is_one);
+
+        // 3つの配置 × 4通りの値のうち, 左のセルが1のものが通し番号の順に出力される
+        let mut expected = vec![];
+        for i in 0..3u64 {
+            let layout = Layout::new(i, cells.clone());
+            for index in 0..layout.size(&domains).unwrap() {
+                if let Ok(record) = left_is_one(&i, &layout.assign(&domains, index)) {
+                    expected.push(record.to_json_line());
+                }
+            }
+        }
+        assert_eq!(*lines.lock().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_run_stats() {
+        let (cells, domains) = two_cells();
+        let lines = Arc::new(Mutex::new(vec![]));
+        let options = memory_options("test_run_stats", &lines);
+        let driver = Driver::with_options("test", domains, options);
+        let stats = driver.run(3, |i, _| Some(Layout::new(i, cells.clone())), left_is_one);
+        assert_eq!(stats.search_space, 12);
+        assert_eq!(stats.tested, 12);
+        assert_eq!(stats.accepted, 6);
+        assert_eq!(stats.rejections.get("left_is_one"), Some(&6));
+    }
+
+    #[test]
+    fn test_count() {
+        // 同じ条件を制約として数えると出力される盤面の個数に一致する
+        let (cells, domains) = two_cells();
+        let lines = Arc::new(Mutex::new(vec![]));
+        let options = memory_options("test_count", &lines);
+        let driver = Driver::with_options("test", domains, options);
+        let count = driver.count(
+            3,
+            |i, _| Some(Layout::new(i, cells.clone())),
+            |_, _| vec![Constraint::new("left_is_one", vec![0], |v| v[0] == Some(1))],
+        );
+        assert_eq!(count, 6);
+        assert!(lines.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_run_resumed() {
+        let board_size = BoardSize(1, 3);
+        let cells: Vec<Structure> = (1..=3)
+            .map(|j| Structure::Element(Element::new(Attribute::C, Coordinate(1, j))))
+            .collect();
+        let domains = Domains::new(
+            vec![None],
+            vec![Some(1), Some(2), Some(3)],
+            vec![None],
+            vec![None],
+        );
+        let check = |_: &u64, assigned: &Vec<Structure>| {
+            if let Structure::Element(ref left) = assigned[0] {
+                Driver::require("left_is_one", left.val == Some(1))?;
+            }
+            let mut record = BoardRecord::new("test", &board_size);
+            record.add_elements(assigned);
+            return Ok(record);
+        };
+        // 200個の配置 × 27通りの値で, 2つのチャンクに分かれる
+        let layout_count = 200;
+        let run = |resume: bool, path: &str| {
+            let lines = Arc::new(Mutex::new(vec![]));
+            let mut options = DriverOptions::new("test");
+            options.seed = 1;
+            options.ordered = true;
+            options.resume = resume;
+            options.output = SinkBackend::Memory(lines.clone());
+            options.checkpoint_path = path.to_string();
+            let driver = Driver::with_options("test", domains.clone(), options);
+            let stats = driver.run(
+                layout_count,
+                |i, _| Some(Layout::new(i, cells.clone())),
+                check,
+            );
+            let lines = lines.lock().unwrap().clone();
+            return (stats, lines);
+        };
+        let path = std::env::temp_dir()
+            .join("puzzle_check_test_run_resumed.checkpoint")
+            .to_string_lossy()
+            .to_string();
+        let (stats, expected) = run(false, &path);
+        assert_eq!(stats.search_space, 5400);
+        assert_eq!(stats.accepted, 1800);
+
+        // 最初のチャンクだけを終えたところで中断したチェックポイントを作る
+        let mut checkpoint = Checkpoint::new("test", 1, 5400);
+        checkpoint.mark_done(0, CHUNK_SIZE);
+        let mut global = 0;
+        for i in 0..layout_count {
+            let layout = Layout::new(i, cells.clone());
+            for index in 0..layout.size(&domains).unwrap() {
+                if global < CHUNK_SIZE {
+                    if let Ok(record) = check(&i, &layout.assign(&domains, index)) {
+                        checkpoint.records.push((global, record.to_json_line()));
+                    }
+                }
+                global += 1;
+            }
+        }
+        checkpoint.save(&path).unwrap();
+
+        // 再開した列挙は残りのチャンクだけを判定し, 中断しなかった列挙と同じ盤面を同じ順に出力する
+        let (stats, resumed) = run(true, &path);
+        assert_eq!(stats.resumed, CHUNK_SIZE);
+        assert_eq!(stats.tested, 5400 - CHUNK_SIZE);
+        assert_eq!(stats.accepted, 1800);
+        assert_eq!(resumed, expected);
+        assert!(Checkpoint::load(&path).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use crate::common::dataclass::{Attribute, BoardSize};
+use std::collections::HashMap;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 格子グラフ上の構造体を行ごとに数える（フロンティア法, 転送行列法）
+// 頂点を行優先の順に1つずつ処理し, 処理済みの頂点と未処理の頂点の境界（フロンティア）の状態ごとに個数を持つ
+// 状態の個数は盤面の幅にだけ依存するので, combineでべき集合を作るよりはるかに大きな盤面まで数えられる
+// 頂点はPのとき格子点（(n+1)×(m+1), 辺はEp）, Cのときセル（n×m, 辺はEc）
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct Frontier {}
+
+// 分割の状態: (フロンティアの各列の最新のセルの領域のラベル, 同じ領域になってはいけないラベルの組)
+type PartitionState = (Vec<u8>, Vec<(u8, u8)>);
+
+// 数える構造
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Cycle,
+    Path,
+}
+
+impl Frontier {
+    // 頂点の種類から格子の(行数, 列数)を求める
+    fn grid(board_size: &BoardSize, vertices: &Attribute) -> (usize, usize) {
+        let n = board_size.0 as usize;
+        let m = board_size.1 as usize;
+        match vertices {
+            Attribute::P => (n + 1, m + 1),
+            Attribute::C => (n, m),
+            _ => panic!("vertices must be P or C"),
+        }
+    }
+
+    // ラベルを最初に現れた順に1, 2, ...と振り直す（0はそのまま）
+    fn normalize(labels: &mut [u8]) {
+        let mut map: HashMap<u8, u8> = HashMap::new();
+        for label in labels.iter_mut() {
+            if *label != 0 {
+                let next = map.len() as u8 + 1;
+                *label = *map.entry(*label).or_insert(next);
+            }
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 単一閉曲線（サイクル）の個数を数える関数
+    // combine([H, V, D], Ep, [Cutoff::only_cycle])の個数と一致する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面サイズ
+    // vertices: &Attribute - 頂点の種類（PまたはC）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // u128 - サイクルの個数
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn count_cycles(board_size: &BoardSize, vertices: &Attribute) -> u128 {
+        return Frontier::count_curves(board_size, vertices, Shape::Cycle);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 交差のない曲線（辺を1本以上含むパス）の個数を数える関数
+    // combine([H, V, D], Ec, [Cutoff::only_line])の個数と一致する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面サイズ
+    // vertices: &Attribute - 頂点の種類（PまたはC）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // u128 - パスの個数
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn count_paths(board_size: &BoardSize, vertices: &Attribute) -> u128 {
+        return Frontier::count_curves(board_size, vertices, Shape::Path);
+    }
+
+    // 状態: フロンティアの各列の下向きの辺と, 直前の頂点の右向きの辺のラベル（0は辺なし）と, パスの端点の個数
+    // 同じラベルの辺は処理済みの頂点を通して繋がっている, ラベルが1回しか現れない断片のもう一方の端はパスの端点
+    fn count_curves(board_size: &BoardSize, vertices: &Attribute, shape: Shape) -> u128 {
+        let (rows, cols) = Frontier::grid(board_size, vertices);
+        let mut total: u128 = 0;
+        let mut states: HashMap<(Vec<u8>, u8), u128> = HashMap::new();
+        states.insert((vec![0; cols + 1], 0), 1);
+
+        for r in 0..rows {
+            for c in 0..cols {
+                let mut next: HashMap<(Vec<u8>, u8), u128> = HashMap::new();
+                for ((slots, ends), count) in states {
+                    let up = slots[c];
+                    let left = slots[cols];
+                    let can_right = c + 1 < cols;
+                    let can_down = r + 1 < rows;
+                    // 断片がこの頂点で閉じたとき, 他に辺が残っていなければ完成
+                    let others_empty = |slots: &Vec<u8>| {
+                        slots
+                            .iter()
+                            .enumerate()
+                            .all(|(k, &label)| k == c || k == cols || label == 0)
+                    };
+                    let mut push = |mut slots: Vec<u8>, ends: u8, down: u8, right: u8| {
+                        slots[c] = down;
+                        slots[cols] = right;
+                        Frontier::normalize(&mut slots);
+                        *next.entry((slots, ends)).or_insert(0) += count;
+                    };
+                    let occurrences =
+                        |slots: &Vec<u8>, label: u8| slots.iter().filter(|&&x| x == label).count();
+
+                    match (up != 0, left != 0) {
+                        (true, true) => {
+                            if up == left {
+                                // 断片が閉じてサイクルになる
+                                if shape == Shape::Cycle && others_empty(&slots) {
+                                    total += count;
+                                }
+                            } else if occurrences(&slots, up) == 1 && occurrences(&slots, left) == 1
+                            {
+                                // 端点を持つ断片どうしが繋がってパスになる
+                                if shape == Shape::Path && ends == 2 && others_empty(&slots) {
+                                    total += count;
+                                }
+                            } else {
+                                let mut merged = slots.clone();
+                                for label in merged.iter_mut() {
+                                    if *label == left {
+                                        *label = up;
+                                    }
+                                }
+                                push(merged, ends, 0, 0);
+                            }
+                        }
+                        (true, false) | (false, true) => {
+                            let label = std::cmp::max(up, left);
+                            if can_down {
+                                push(slots.clone(), ends, label, 0);
+                            }
+                            if can_right {
+                                push(slots.clone(), ends, 0, label);
+                            }
+                            // この頂点をパスの端点にする
+                            if shape == Shape::Path && ends < 2 {
+                                if occurrences(&slots, label) == 1 {
+                                    if ends + 1 == 2 && others_empty(&slots) {
+                                        total += count;
+                                    }
+                                } else {
+                                    push(slots.clone(), ends + 1, 0, 0);
+                                }
+                            }
+                        }
+                        (false, false) => {
+                            push(slots.clone(), ends, 0, 0);
+                            let fresh = u8::MAX;
+                            if can_down && can_right {
+                                push(slots.clone(), ends, fresh, fresh);
+                            }
+                            if shape == Shape::Path && ends < 2 {
+                                if can_down {
+                                    push(slots.clone(), ends + 1, fresh, 0);
+                                }
+                                if can_right {
+                                    push(slots.clone(), ends + 1, 0, fresh);
+                                }
+                            }
+                        }
+                    }
+                }
+                states = next;
+            }
+        }
+        return total;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // セルを連結な領域に分割する方法の個数を数える関数
+    // 隣り合うセルの組ごとに同じ領域か異なる領域かを決め, 異なる領域とした組が後で同じ領域に繋がらないものを数える
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面サイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // u128 - 分割の個数
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn count_partitions(board_size: &BoardSize) -> u128 {
+        let (rows, cols) = Frontier::grid(board_size, &Attribute::C);
+        if rows == 0 || cols == 0 {
+            return 1;
+        }
+        let mut states: HashMap<PartitionState, u128> = HashMap::new();
+        states.insert((vec![0; cols], vec![]), 1);
+
+        for r in 0..rows {
+            for c in 0..cols {
+                let mut next: HashMap<PartitionState, u128> = HashMap::new();
+                for ((labels, apart), count) in states {
+                    let up = if r > 0 { labels[c] } else { 0 };
+                    let left = if c > 0 { labels[c - 1] } else { 0 };
+                    let fresh = u8::MAX;
+                    // (このセルのラベル, 上と同じ領域か, 左と同じ領域か)
+                    let mut choices: Vec<(u8, bool, bool)> = vec![];
+                    for join_up in [false, true] {
+                        for join_left in [false, true] {
+                            if (join_up && up == 0) || (join_left && left == 0) {
+                                continue;
+                            }
+                            let label = if join_up {
+                                up
+                            } else if join_left {
+                                left
+                            } else {
+                                fresh
+                            };
+                            choices.push((label, join_up, join_left));
+                        }
+                    }
+                    'choice: for (label, join_up, join_left) in choices {
+                        let mut labels = labels.clone();
+                        let mut apart = apart.clone();
+                        if up != 0 && left != 0 && up != left && join_up != join_left {
+                            // 上と左の一方とだけ同じ領域なので, 上と左は異なる領域
+                            apart.push((std::cmp::min(up, left), std::cmp::max(up, left)));
+                        }
+                        if up != 0 && left != 0 && up == left && join_up != join_left {
+                            // 上と左が既に同じ領域なら, 一方とだけ同じ領域にはできない
+                            continue 'choice;
+                        }
+                        if up != 0 && !join_up {
+                            apart.push((std::cmp::min(up, label), std::cmp::max(up, label)));
+                        }
+                        if left != 0 && !join_left {
+                            apart.push((std::cmp::min(left, label), std::cmp::max(left, label)));
+                        }
+                        if join_up && join_left && up != left {
+                            // 上と左の領域を繋げる
+                            let pair = (std::cmp::min(up, left), std::cmp::max(up, left));
+                            if apart.contains(&pair) {
+                                continue 'choice;
+                            }
+                            for x in labels.iter_mut() {
+                                if *x == left {
+                                    *x = up;
+                                }
+                            }
+                            for (a, b) in apart.iter_mut() {
+                                if *a == left {
+                                    *a = up;
+                                }
+                                if *b == left {
+                                    *b = up;
+                                }
+                                let (x, y) = (std::cmp::min(*a, *b), std::cmp::max(*a, *b));
+                                *a = x;
+                                *b = y;
+                            }
+                        }
+                        labels[c] = label;
+
+                        // ラベルを振り直し, フロンティアから消えた領域の組を捨てる
+                        let mut map: HashMap<u8, u8> = HashMap::new();
+                        for x in labels.iter_mut() {
+                            if *x != 0 {
+                                let n = map.len() as u8 + 1;
+                                *x = *map.entry(*x).or_insert(n);
+                            }
+                        }
+                        let mut renamed: Vec<(u8, u8)> = apart
+                            .iter()
+                            .filter_map(|(a, b)| match (map.get(a), map.get(b)) {
+                                (Some(&a), Some(&b)) => {
+                                    Some((std::cmp::min(a, b), std::cmp::max(a, b)))
+                                }
+                                _ => None,
+                            })
+                            .collect();
+                        renamed.sort();
+                        renamed.dedup();
+                        *next.entry((labels, renamed)).or_insert(0) += count;
+                    }
+                }
+                states = next;
+            }
+        }
+        return states.values().sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::combine;
+    use crate::common::initialize::initialize;
+    use crate::common::profile::Named;
+    use crate::common::relationship::{Relationship, D, H, M, V};
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+
+    #[test]
+    fn test_frontier() {
+        // combineの結果と一致する
+        let board_size = BoardSize(2, 2);
+        let (_, _, _, Ec) = initialize(&BoardSize(2, 3));
+        let R: Vec<Relationship> = vec![H, V, D];
+        let cycles: Vec<Named<CutoffFn>> = named![Cutoff::only_cycle];
+        let lines: Vec<Named<CutoffFn>> = named![Cutoff::only_line];
+        let (_, _, Ep_2x2, _) = initialize(&board_size);
+        assert_eq!(
+            Frontier::count_cycles(&board_size, &Attribute::P),
+            combine(R.clone(), vec![M], &Ep_2x2, &cycles).len() as u128
+        );
+        assert_eq!(
+            Frontier::count_paths(&BoardSize(2, 3), &Attribute::C),
+            combine(R, vec![M], &Ec, &lines).len() as u128
+        );
+
+        // 既知の値: 3×3の格子点上のサイクルは13個, 連結な分割は2×2で12通り, 3×3で1434通り, 1×kで2^(k-1)通り
+        assert_eq!(Frontier::count_cycles(&board_size, &Attribute::P), 13);
+        assert_eq!(Frontier::count_partitions(&board_size), 12);
+        assert_eq!(Frontier::count_partitions(&BoardSize(3, 3)), 1434);
+        assert_eq!(Frontier::count_partitions(&BoardSize(1, 5)), 16);
+    }
+}
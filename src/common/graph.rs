@@ -0,0 +1,365 @@
+use crate::common::dataclass::{Attribute, Coordinate, Element, Structure};
+use crate::common::relationship::Relation;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 平面グラフで与える盤面
+// 面がセル, 頂点が格子点, 辺が格子点辺, 隣り合う面を結ぶ辺（双対辺）がセル辺に当たる
+// 元素の座標は番号で持つ（頂点と面の番号は1から）
+//   Gp : 頂点v                   Coordinate(v, 0)
+//   Gc : 面f                     Coordinate(f, 0)
+//   Gep: 頂点uとvを結ぶ辺（u < v）  Coordinate(u, v)
+//   Gec: 面fとgを結ぶ双対辺（f < g） Coordinate(f, g)
+// 双対辺を面の組で表すので, 2つの面が共有する辺は1本まで
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct PlanarGraph {
+    // 頂点の個数（頂点の番号は1..=vertices）
+    pub vertices: usize,
+    // 面ごとの, 周りの頂点を一周する順に並べた列（面の番号はfaces[f - 1]のf）
+    pub faces: Vec<Vec<usize>>,
+    // 辺(u, v)と, その辺を周りに持つ面の番号（1つまたは2つ）
+    edges: BTreeMap<(usize, usize), Vec<usize>>,
+}
+
+// 平面グラフの隣接関係の種類（relationship.rsの同名の関係に当たる）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphRelation {
+    // 辺を共有する面, 辺で結ばれた頂点, 端を共有する辺・双対辺
+    Adjacent,
+    // 面とその周りの辺
+    Borders,
+    // 面とその周りの頂点
+    Corner,
+    // 辺とその両端の頂点, 双対辺とその両端の面
+    Endpoint,
+    // 互いに交わる辺と双対辺
+    Dual,
+}
+
+impl PlanarGraph {
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 面の列から平面グラフを作る関数, 辺は面の周りの頂点の組から求める
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // vertices: usize - 頂点の個数
+    // faces: Vec<Vec<usize>> - 面ごとの周りの頂点の列
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<PlanarGraph, String> - 平面グラフ, 面の与え方が正しくなければエラー
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn new(vertices: usize, faces: Vec<Vec<usize>>) -> Result<Self, String> {
+        let mut edges: BTreeMap<(usize, usize), Vec<usize>> = BTreeMap::new();
+        for (f, face) in faces.iter().enumerate() {
+            if face.len() < 3 {
+                return Err(format!("face {} has fewer than 3 vertices", f + 1));
+            }
+            for k in 0..face.len() {
+                let (u, v) = (face[k], face[(k + 1) % face.len()]);
+                if u == 0 || v == 0 || u > vertices || v > vertices {
+                    return Err(format!("face {}: vertex out of range", f + 1));
+                }
+                edges.entry((u.min(v), u.max(v))).or_default().push(f + 1);
+            }
+        }
+        let mut duals: BTreeSet<(usize, usize)> = BTreeSet::new();
+        for (&(u, v), around) in edges.iter() {
+            if around.len() > 2 {
+                return Err(format!("edge ({}, {}) belongs to more than 2 faces", u, v));
+            }
+            if around.len() == 2 && !duals.insert((around[0], around[1])) {
+                return Err(format!(
+                    "faces {} and {} share more than one edge",
+                    around[0], around[1]
+                ));
+            }
+        }
+        return Ok(PlanarGraph {
+            vertices,
+            faces,
+            edges,
+        });
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 文字列から平面グラフを作る関数, 1行が1つの面で, 周りの頂点の番号を一周する順に空白区切りで並べる
+    // 空行と '#' で始まる行は読み飛ばす
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // text: &str - 平面グラフ（例: "1 2 4\n1 4 3"）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<PlanarGraph, String> - 平面グラフ, 読めなければエラー
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut faces: Vec<Vec<usize>> = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let face = line
+                .split_whitespace()
+                .map(|v| v.parse::<usize>())
+                .collect::<Result<Vec<usize>, _>>()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            faces.push(face);
+        }
+        let vertices = faces.iter().flatten().copied().max().unwrap_or(0);
+        return PlanarGraph::new(vertices, faces);
+    }
+
+    // ファイルから平面グラフを読む（形式はparse）
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        return PlanarGraph::parse(&text);
+    }
+
+    // n行m列の正方形の格子（頂点は(n+1)×(m+1), 番号は行ごと）
+    pub fn square(n: usize, m: usize) -> Self {
+        let id = |i: usize, j: usize| i * (m + 1) + j + 1;
+        let mut faces = vec![];
+        for i in 0..n {
+            for j in 0..m {
+                faces.push(vec![id(i, j), id(i, j + 1), id(i + 1, j + 1), id(i + 1, j)]);
+            }
+        }
+        return PlanarGraph::new((n + 1) * (m + 1), faces).unwrap();
+    }
+
+    // n行m列の正方形を対角線で2つの三角形に分けた格子
+    pub fn triangular(n: usize, m: usize) -> Self {
+        let id = |i: usize, j: usize| i * (m + 1) + j + 1;
+        let mut faces = vec![];
+        for i in 0..n {
+            for j in 0..m {
+                faces.push(vec![id(i, j), id(i, j + 1), id(i + 1, j + 1)]);
+                faces.push(vec![id(i, j), id(i + 1, j + 1), id(i + 1, j)]);
+            }
+        }
+        return PlanarGraph::new((n + 1) * (m + 1), faces).unwrap();
+    }
+
+    // 面fの周りの辺(u, v)（u < v, 一周する順）
+    pub fn face_edges(&self, f: usize) -> Vec<(usize, usize)> {
+        let face = &self.faces[f - 1];
+        return (0..face.len())
+            .map(|k| {
+                let (u, v) = (face[k], face[(k + 1) % face.len()]);
+                (u.min(v), u.max(v))
+            })
+            .collect();
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 平面グラフの元素列の初期化を行う関数, 各列は番号の順
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // (Vec<Structure>, Vec<Structure>, Vec<Structure>, Vec<Structure>) - (Gp, Gc, Gep, Gec)
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn initialize(
+        &self,
+    ) -> (
+        Vec<Structure>,
+        Vec<Structure>,
+        Vec<Structure>,
+        Vec<Structure>,
+    ) {
+        let element = |attr: Attribute, i: usize, j: usize| {
+            Structure::Element(Element::new(attr, Coordinate(i as i32, j as i32)))
+        };
+        let P = (1..=self.vertices)
+            .map(|v| element(Attribute::Gp, v, 0))
+            .collect();
+        let C = (1..=self.faces.len())
+            .map(|f| element(Attribute::Gc, f, 0))
+            .collect();
+        let Ep = self
+            .edges
+            .keys()
+            .map(|&(u, v)| element(Attribute::Gep, u, v))
+            .collect();
+        let mut duals: Vec<(usize, usize)> = self
+            .edges
+            .values()
+            .filter(|around| around.len() == 2)
+            .map(|around| (around[0], around[1]))
+            .collect();
+        duals.sort();
+        let Ec = duals
+            .iter()
+            .map(|&(f, g)| element(Attribute::Gec, f, g))
+            .collect();
+        return (P, C, Ep, Ec);
+    }
+
+    // 元素の座標の組
+    fn pair(X: &Element) -> (usize, usize) {
+        return (X.coor.0 as usize, X.coor.1 as usize);
+    }
+
+    // 辺(u, v)を周りに持つ面
+    fn faces_of(&self, edge: (usize, usize)) -> &[usize] {
+        return self.edges.get(&edge).map(|v| v.as_slice()).unwrap_or(&[]);
+    }
+
+    // XからYへの向きだけを見た関係（holdsで両方の向きを見る）
+    fn holds_from(&self, kind: GraphRelation, X: &Element, Y: &Element) -> bool {
+        let (x, y) = (PlanarGraph::pair(X), PlanarGraph::pair(Y));
+        match (kind, &X.attr, &Y.attr) {
+            (GraphRelation::Adjacent, Attribute::Gc, Attribute::Gc) => {
+                return self
+                    .face_edges(x.0)
+                    .iter()
+                    .any(|&edge| x != y && self.faces_of(edge).contains(&y.0))
+            }
+            (GraphRelation::Adjacent, Attribute::Gp, Attribute::Gp) => {
+                return self.edges.contains_key(&(x.0.min(y.0), x.0.max(y.0)))
+            }
+            (GraphRelation::Adjacent, Attribute::Gep, Attribute::Gep)
+            | (GraphRelation::Adjacent, Attribute::Gec, Attribute::Gec) => {
+                return x != y && (x.0 == y.0 || x.0 == y.1 || x.1 == y.0 || x.1 == y.1)
+            }
+            (GraphRelation::Borders, Attribute::Gc, Attribute::Gep) => {
+                return self.faces_of(y).contains(&x.0)
+            }
+            (GraphRelation::Corner, Attribute::Gc, Attribute::Gp) => {
+                return self.faces[x.0 - 1].contains(&y.0)
+            }
+            (GraphRelation::Endpoint, Attribute::Gep, Attribute::Gp)
+            | (GraphRelation::Endpoint, Attribute::Gec, Attribute::Gc) => {
+                return x.0 == y.0 || x.1 == y.0
+            }
+            (GraphRelation::Dual, Attribute::Gep, Attribute::Gec) => {
+                return self.faces_of(x) == [y.0, y.1]
+            }
+            _ => return false,
+        }
+    }
+
+    // 2つの元素がkindの関係にあるか（向きは問わない）
+    pub fn holds(&self, kind: GraphRelation, X: &Element, Y: &Element) -> bool {
+        return self.holds_from(kind, X, Y) || self.holds_from(kind, Y, X);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // combine_with_relationsなどに渡す隣接関係を作る関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // kind: GraphRelation - 隣接関係の種類
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // impl Relation - 平面グラフの隣接関係（グラフは複製した関係どうしで共有する）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn relation(&self, kind: GraphRelation) -> impl Relation + Clone {
+        let graph = Arc::new(self.clone());
+        return move |X: &Element, Y: &Element| graph.holds(kind, X, Y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::{combine, combine_with_relations};
+    use crate::common::dataclass::BoardSize;
+    use crate::common::initialize::initialize;
+    use crate::common::profile::Named;
+    use crate::common::relationship::{D, H, M, V};
+    use crate::common::zdd::Zdd;
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+    use crate::specific::structure_functions::StructureFn;
+
+    #[test]
+    fn test_graph() {
+        // 正方形の格子として与えたグラフは, 正方形の盤面と同じ個数の閉曲線とパスを持つ
+        let square = PlanarGraph::square(2, 2);
+        let (P, C, Ep, Ec) = square.initialize();
+        assert_eq!((P.len(), C.len(), Ep.len(), Ec.len()), (9, 4, 12, 4));
+        let adjacent = vec![square.relation(GraphRelation::Adjacent)];
+        let cycles: Vec<Named<CutoffFn>> = named![Cutoff::only_cycle];
+        let lines: Vec<Named<CutoffFn>> = named![Cutoff::only_line];
+        let (_, _, grid_Ep, grid_Ec) = initialize(&BoardSize(2, 2));
+        assert_eq!(
+            combine_with_relations(adjacent.clone(), vec![M], &Ep, &cycles).len(),
+            combine(vec![H, V, D], vec![M], &grid_Ep, &cycles).len()
+        );
+        assert_eq!(
+            combine_with_relations(adjacent.clone(), vec![M], &Ec, &lines).len(),
+            combine(vec![H, V, D], vec![M], &grid_Ec, &lines).len()
+        );
+
+        // 三角形の格子: 閉曲線の個数はZDDのフロンティア法と一致する
+        let triangular = PlanarGraph::triangular(2, 2);
+        let (P, C, Ep, Ec) = triangular.initialize();
+        assert_eq!((P.len(), C.len(), Ep.len(), Ec.len()), (9, 8, 16, 8));
+        let adjacent = vec![triangular.relation(GraphRelation::Adjacent)];
+        let loops = combine_with_relations(adjacent.clone(), vec![M], &Ep, &cycles);
+        let mut zdd = Zdd::new(&Ep);
+        let root = zdd.cycles();
+        assert_eq!(zdd.count(root), loops.len() as u128);
+        // 面の領域は辺を共有する面どうしで連結
+        let no_cutoff: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+        let areas = combine_with_relations(adjacent, vec![M], &C, &no_cutoff);
+        assert!(areas.iter().all(|a| a.len() <= C.len()));
+        assert!(areas.iter().any(|a| a.len() == C.len()));
+
+        // 面の周りの辺の値の和（StructureFn::cycle）
+        let mut independent_Ep = Ep.clone();
+        for edge in independent_Ep.iter_mut() {
+            let on_first = triangular.holds(
+                GraphRelation::Borders,
+                C[0].as_element().unwrap(),
+                edge.as_element().unwrap(),
+            );
+            edge.set_val(Some(on_first as i32));
+        }
+        assert_eq!(
+            StructureFn::cycle_on_graph(&C[0], &independent_Ep, &triangular),
+            3
+        );
+        assert_eq!(
+            StructureFn::cycle_on_graph(&C[7], &independent_Ep, &triangular),
+            0
+        );
+
+        // 接続関係
+        let face = C[0].as_element().unwrap();
+        let count = |items: &Vec<Structure>, kind| {
+            items
+                .iter()
+                .filter(|s| triangular.holds(kind, face, s.as_element().unwrap()))
+                .count()
+        };
+        assert_eq!(count(&P, GraphRelation::Corner), 3);
+        assert_eq!(count(&Ec, GraphRelation::Endpoint), 2);
+        assert_eq!(count(&C, GraphRelation::Adjacent), 2);
+        for dual in Ec.iter() {
+            let crossing = Ep
+                .iter()
+                .filter(|e| {
+                    triangular.holds(
+                        GraphRelation::Dual,
+                        e.as_element().unwrap(),
+                        dual.as_element().unwrap(),
+                    )
+                })
+                .count();
+            assert_eq!(crossing, 1);
+        }
+
+        // 文字列から読む
+        let parsed = PlanarGraph::parse("# 2つの三角形\n1 2 4\n\n1 4 3\n").unwrap();
+        assert_eq!(
+            parsed.initialize(),
+            PlanarGraph::triangular(1, 1).initialize()
+        );
+        assert!(PlanarGraph::parse("1 2").is_err());
+        assert!(PlanarGraph::parse("1 2 3 4\n1 2 3 4").is_err());
+    }
+}
@@ -0,0 +1,312 @@
+use crate::common::dataclass::{Attribute, Coordinate, Element, Structure};
+use std::collections::BTreeSet;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 六角形の盤面
+// セルは斜交座標(q, r)で表し, 隣接する6つのセルは(q±1, r), (q, r±1), (q+1, r-1), (q-1, r+1)
+// 元素の座標は6倍の格子の上の位置(行, 列)で持つ（セル(q, r)は(6r, 6q)）
+//   Xc : セルの中心                 (行, 列)を6で割った余りが(0, 0)
+//   Xp : 頂点（3つのセルの重心）     余りが(2, 2)または(4, 4)
+//   Xep: 頂点どうしを結ぶ辺の中点     余りが(0, 3), (3, 0), (3, 3)（3つの向き）
+//   Xec: セルどうしを結ぶ辺の中点     Xepと同じ位置（互いに交わる辺は同じ座標を持つ）
+// 正方形の盤面のP, C, Ep, Ecと同じ役割を持つので, combineやcutoffは隣接関係を替えるだけでそのまま使える
+// ---------------------------------------------------------------------------------------------------------------------
+
+// 隣接する6つのセルへの(行, 列)の差, 周りを一周する順
+const NEIGHBOURS: [(i32, i32); 6] = [(0, 6), (6, 0), (6, -6), (0, -6), (-6, 0), (-6, 6)];
+
+// セル(q, r)の元素
+pub fn hex_cell(q: i32, r: i32) -> Element {
+    return Element::new(Attribute::Xc, Coordinate(6 * r, 6 * q));
+}
+
+// 6倍の格子の上の位置を6で割った余り
+fn class(coor: &Coordinate) -> (i32, i32) {
+    return (coor.0.rem_euclid(6), coor.1.rem_euclid(6));
+}
+
+// 辺の向き（0: qの向き, 1: rの向き, 2: q+1, r-1の向き）, 辺の元素でなければNone
+pub fn orientation(element: &Element) -> Option<usize> {
+    if element.attr != Attribute::Xep && element.attr != Attribute::Xec {
+        return None;
+    }
+    match class(&element.coor) {
+        (0, 3) => return Some(0),
+        (3, 0) => return Some(1),
+        (3, 3) => return Some(2),
+        _ => return None,
+    }
+}
+
+// セルの6つの頂点の座標（周りを一周する順）
+pub fn corners(cell: &Coordinate) -> Vec<Coordinate> {
+    return (0..6)
+        .map(|k| {
+            let (a, b) = (NEIGHBOURS[k], NEIGHBOURS[(k + 1) % 6]);
+            Coordinate(cell.0 + (a.0 + b.0) / 3, cell.1 + (a.1 + b.1) / 3)
+        })
+        .collect();
+}
+
+// セルの6つの辺の中点の座標（周りを一周する順）
+pub fn sides(cell: &Coordinate) -> Vec<Coordinate> {
+    return NEIGHBOURS
+        .iter()
+        .map(|&(di, dj)| Coordinate(cell.0 + di / 2, cell.1 + dj / 2))
+        .collect();
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 辺の中点から辺の両端の座標を求める関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// coor: &Coordinate - 辺の中点の座標
+// vertices: bool - trueなら頂点どうしを結ぶ辺（Xep）, falseならセルどうしを結ぶ辺（Xec）
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// (Coordinate, Coordinate) - 両端の座標, 盤面を超える場合があることに注意
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn ends(coor: &Coordinate, vertices: bool) -> (Coordinate, Coordinate) {
+    let (di, dj) = match (class(coor), vertices) {
+        ((0, 3), true) => (2, -1),
+        ((3, 0), true) => (1, -2),
+        ((3, 3), true) => (1, 1),
+        ((0, 3), false) => (0, 3),
+        ((3, 0), false) => (3, 0),
+        ((3, 3), false) => (3, -3),
+        _ => panic!("辺の中点ではない座標です！"),
+    };
+    return (
+        Coordinate(coor.0 - di, coor.1 - dj),
+        Coordinate(coor.0 + di, coor.1 + dj),
+    );
+}
+
+// 2つの元素の座標の差
+fn difference(X: &Element, Y: &Element) -> (i32, i32) {
+    return (Y.coor.0 - X.coor.0, Y.coor.1 - X.coor.1);
+}
+
+// 辺の元素の両端の座標
+fn edge_ends(X: &Element) -> Vec<Coordinate> {
+    let (a, b) = ends(&X.coor, X.attr == Attribute::Xep);
+    return vec![a, b];
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 比較した2つの元素が六角形の盤面で隣接しているかを確認する関数
+// セルは6つの隣のセル, 頂点は辺で結ばれた頂点, 辺は端を共有する同じ種類の辺と隣接する
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// X: &Element - 比較したい元素 1
+// Y: &Element - 比較したい元素 2
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// bool - 隣接であるときtrue
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn hex_adjacent(X: &Element, Y: &Element) -> bool {
+    if X.attr != Y.attr || X.coor == Y.coor {
+        return false;
+    }
+    let d = difference(X, Y);
+    match X.attr {
+        Attribute::Xc => return NEIGHBOURS.contains(&d),
+        Attribute::Xp => {
+            return [(4, -2), (2, -4), (2, 2)]
+                .iter()
+                .any(|&(di, dj)| d == (di, dj) || d == (-di, -dj))
+        }
+        Attribute::Xep | Attribute::Xec => {
+            let x_ends = edge_ends(X);
+            return edge_ends(Y).iter().any(|end| x_ends.contains(end));
+        }
+        _ => return false,
+    }
+}
+
+// 比較した2つの元素がセルとその周りの辺（Xep）であるか
+pub fn hex_borders(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::Xc, Attribute::Xep) => return sides(&X.coor).contains(&Y.coor),
+        (Attribute::Xep, Attribute::Xc) => return sides(&Y.coor).contains(&X.coor),
+        _ => return false,
+    }
+}
+
+// 比較した2つの元素がセルとその頂点であるか
+pub fn hex_corner(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::Xc, Attribute::Xp) => return corners(&X.coor).contains(&Y.coor),
+        (Attribute::Xp, Attribute::Xc) => return corners(&Y.coor).contains(&X.coor),
+        _ => return false,
+    }
+}
+
+// 比較した2つの元素が辺とその端（XepとXp, XecとXc）であるか
+pub fn hex_endpoint(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::Xep, Attribute::Xp) | (Attribute::Xec, Attribute::Xc) => {
+            return edge_ends(X).contains(&Y.coor)
+        }
+        (Attribute::Xp, Attribute::Xep) | (Attribute::Xc, Attribute::Xec) => {
+            return edge_ends(Y).contains(&X.coor)
+        }
+        _ => return false,
+    }
+}
+
+// 比較した2つの元素が互いに交わる辺（XepとXec）であるか
+pub fn hex_dual(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::Xep, Attribute::Xec) | (Attribute::Xec, Attribute::Xep) => {
+            return X.coor == Y.coor
+        }
+        _ => return false,
+    }
+}
+
+// 一辺にsize個のセルが並ぶ六角形の盤面のセル(q, r)
+pub fn hexagon(size: i32) -> Vec<(i32, i32)> {
+    let mut cells = vec![];
+    for r in -(size - 1)..size {
+        for q in -(size - 1)..size {
+            if (q + r).abs() < size {
+                cells.push((q, r));
+            }
+        }
+    }
+    return cells;
+}
+
+// n行m列の平行四辺形の盤面のセル(q, r)
+pub fn parallelogram(n: i32, m: i32) -> Vec<(i32, i32)> {
+    let mut cells = vec![];
+    for r in 0..n {
+        for q in 0..m {
+            cells.push((q, r));
+        }
+    }
+    return cells;
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 六角形の盤面の元素列の初期化を行う関数, 各列は座標の順
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// cells: &Vec<(i32, i32)> - 盤面のセル(q, r)（hexagon, parallelogramなど）
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// (Vec<Structure>, Vec<Structure>, Vec<Structure>, Vec<Structure>) - (Xp, Xc, Xep, Xec)
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn initialize_hex(
+    cells: &Vec<(i32, i32)>,
+) -> (
+    Vec<Structure>,
+    Vec<Structure>,
+    Vec<Structure>,
+    Vec<Structure>,
+) {
+    let centres: BTreeSet<(i32, i32)> = cells.iter().map(|&(q, r)| (6 * r, 6 * q)).collect();
+    let mut points: BTreeSet<(i32, i32)> = BTreeSet::new();
+    let mut point_edges: BTreeSet<(i32, i32)> = BTreeSet::new();
+    let mut cell_edges: BTreeSet<(i32, i32)> = BTreeSet::new();
+    for &(i, j) in centres.iter() {
+        for corner in corners(&Coordinate(i, j)) {
+            points.insert((corner.0, corner.1));
+        }
+        for side in sides(&Coordinate(i, j)) {
+            point_edges.insert((side.0, side.1));
+            if centres.contains(&(2 * side.0 - i, 2 * side.1 - j)) {
+                cell_edges.insert((side.0, side.1));
+            }
+        }
+    }
+    let elements = |attr: Attribute, coors: &BTreeSet<(i32, i32)>| {
+        return coors
+            .iter()
+            .map(|&(i, j)| Structure::Element(Element::new(attr.clone(), Coordinate(i, j))))
+            .collect::<Vec<Structure>>();
+    };
+    let P = elements(Attribute::Xp, &points);
+    let C = elements(Attribute::Xc, &centres);
+    let Ep = elements(Attribute::Xep, &point_edges);
+    let Ec = elements(Attribute::Xec, &cell_edges);
+    return (P, C, Ep, Ec);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::combine;
+    use crate::common::profile::Named;
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+
+    #[test]
+    fn test_hex() {
+        // 一辺に2つのセルが並ぶ六角形: 7セル, 24頂点, 30辺, セルどうしの辺12本
+        let (P, C, Ep, Ec) = initialize_hex(&hexagon(2));
+        assert_eq!((P.len(), C.len(), Ep.len(), Ec.len()), (24, 7, 30, 12));
+        assert_eq!(initialize_hex(&parallelogram(2, 3)).1.len(), 6);
+
+        // 中央のセルは6つのセルと隣接し, 6本の辺と6つの頂点に囲まれる
+        let centre = hex_cell(0, 0);
+        let count = |items: &Vec<Structure>, r: fn(&Element, &Element) -> bool| {
+            items
+                .iter()
+                .filter(|s| r(&centre, s.as_element().unwrap()))
+                .count()
+        };
+        assert_eq!(count(&C, hex_adjacent), 6);
+        assert_eq!(count(&Ep, hex_borders), 6);
+        assert_eq!(count(&P, hex_corner), 6);
+        assert_eq!(count(&Ec, hex_endpoint), 6);
+
+        // 辺は3つの向きに2本ずつ
+        let around: Vec<Structure> = Ep
+            .iter()
+            .filter(|s| hex_borders(&centre, s.as_element().unwrap()))
+            .cloned()
+            .collect();
+        for k in 0..3 {
+            assert_eq!(
+                around
+                    .iter()
+                    .filter(|s| orientation(s.as_element().unwrap()) == Some(k))
+                    .count(),
+                2
+            );
+        }
+        // 各頂点は3本の辺の端, 辺の両端の頂点は辺で結ばれている
+        for edge in Ep.iter().map(|s| s.as_element().unwrap()) {
+            let ends: Vec<&Element> = P
+                .iter()
+                .map(|s| s.as_element().unwrap())
+                .filter(|p| hex_endpoint(edge, p))
+                .collect();
+            assert_eq!(ends.len(), 2);
+            assert!(hex_adjacent(ends[0], ends[1]));
+            assert!(Ec.iter().all(|s| !hex_dual(edge, s.as_element().unwrap())
+                || s.as_element().unwrap().coor == edge.coor));
+        }
+
+        // combineとcutoffはそのまま使える: 中央のセルを囲む1周（6本）が閉曲線の1つ
+        let no_cutoff: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+        let areas = combine(vec![hex_adjacent], vec![], &C, &no_cutoff);
+        assert!(areas.iter().all(|a| a.len() <= 7));
+        let (_, _, Ep1, _) = initialize_hex(&hexagon(1));
+        let cycles = combine(
+            vec![hex_adjacent],
+            vec![],
+            &Ep1,
+            &named![Cutoff::only_cycle],
+        );
+        assert_eq!(cycles.len(), 1);
+        let lines = combine(vec![hex_adjacent], vec![], &Ep1, &named![Cutoff::only_line]);
+        // 6本の輪の上のパス: 長さ1..5が6本ずつ
+        assert_eq!(lines.len(), 30);
+    }
+}
@@ -1,4 +1,166 @@
 use crate::common::dataclass::{Attribute, BoardSize, Coordinate, Element, Structure};
+use crate::common::relationship::{Relation, Relationship};
+use crate::common::topology::Topology;
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 盤面の形（どのセルが存在するか）
+// 長方形の盤面から穴を空けたり, 外形を切り取ったりするのに使う
+// 存在するセルの角の格子点, 周りの格子点辺, 存在するセルどうしを結ぶセル辺だけが存在する
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mask {
+    // cells[i][j]: セルC(i+1, j+1)が存在するときtrue
+    pub cells: Vec<Vec<bool>>,
+}
+
+impl Mask {
+    // 全てのセルが存在する（長方形の）盤面
+    pub fn full(board_size: &BoardSize) -> Self {
+        Mask {
+            cells: vec![vec![true; board_size.1 as usize]; board_size.0 as usize],
+        }
+    }
+
+    // 与えたセルだけが存在する盤面
+    pub fn from_cells(board_size: &BoardSize, cells: &Vec<Coordinate>) -> Self {
+        let mut mask = Mask {
+            cells: vec![vec![false; board_size.1 as usize]; board_size.0 as usize],
+        };
+        for coor in cells.iter() {
+            mask.cells[(coor.0 - 1) as usize][(coor.1 - 1) as usize] = true;
+        }
+        return mask;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 文字列から盤面の形を作る関数, 1行が盤面の1行で '.' が存在しないセル, それ以外の文字が存在するセル
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // text: &str - 盤面の形（例: "#.#\n###"）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // (BoardSize, Mask) - 盤面サイズと盤面の形
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn parse(text: &str) -> (BoardSize, Mask) {
+        let cells: Vec<Vec<bool>> = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| line.chars().map(|c| c != '.').collect())
+            .collect();
+        let n = cells.len() as i32;
+        let m = cells.iter().map(|row| row.len()).max().unwrap_or(0) as i32;
+        let mut mask = Mask::full(&BoardSize(n, m));
+        for (i, row) in mask.cells.iter_mut().enumerate() {
+            for (j, present) in row.iter_mut().enumerate() {
+                *present = cells[i].get(j).copied().unwrap_or(false);
+            }
+        }
+        return (BoardSize(n, m), mask);
+    }
+
+    // セルC(i, j)が存在するか（盤面の外ならfalse）
+    pub fn contains(&self, i: i32, j: i32) -> bool {
+        if i < 1 || j < 1 {
+            return false;
+        }
+        return self
+            .cells
+            .get((i - 1) as usize)
+            .and_then(|row| row.get((j - 1) as usize))
+            .copied()
+            .unwrap_or(false);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 元素が盤面に存在するかを確認する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // element: &Element - 元素
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // bool - 存在するときtrue
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn has(&self, element: &Element) -> bool {
+        let (i, j) = (element.coor.0, element.coor.1);
+        match element.attr {
+            Attribute::C => return self.contains(i, j),
+            // セルの斜線と三角形はそのセルが存在するとき存在する
+            Attribute::Ds | Attribute::Db => return self.contains(i, j),
+            Attribute::Tnw | Attribute::Tne | Attribute::Tsw | Attribute::Tse => {
+                return self.contains(i, j)
+            }
+            // 格子点はいずれかの角のセルが存在するとき存在する
+            Attribute::P => {
+                return self.contains(i - 1, j - 1)
+                    || self.contains(i - 1, j)
+                    || self.contains(i, j - 1)
+                    || self.contains(i, j)
+            }
+            // 格子点辺は両側のいずれかのセルが存在するとき存在する
+            Attribute::Hp => return self.contains(i - 1, j) || self.contains(i, j),
+            Attribute::Vp => return self.contains(i, j - 1) || self.contains(i, j),
+            // セル辺は両端のセルが存在するとき存在する
+            Attribute::Hc => return self.contains(i, j) && self.contains(i, j + 1),
+            Attribute::Vc => return self.contains(i, j) && self.contains(i + 1, j),
+            // 六角形の盤面・平面グラフ・立体の盤面はマスクを使わない（hex.rs, graph.rs, cube.rs）
+            Attribute::Xp | Attribute::Xc | Attribute::Xep | Attribute::Xec => return false,
+            Attribute::Gp | Attribute::Gc | Attribute::Gep | Attribute::Gec => return false,
+            Attribute::P3 | Attribute::C3 | Attribute::Fl | Attribute::Fr | Attribute::Fc => {
+                return false
+            }
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 2つの元素が盤面の上で隣接しうるかを確認する関数
+    // 格子点どうしは, 間の格子点辺（横・縦）またはセル（斜め）が存在するときだけ隣接する
+    // それ以外の元素どうしは, 両方が存在すれば間の元素も存在するので常にtrue
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // X: &Element - 元素 1
+    // Y: &Element - 元素 2
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // bool - 間が繋がっているときtrue
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn connects(&self, X: &Element, Y: &Element) -> bool {
+        if X.attr != Attribute::P || Y.attr != Attribute::P {
+            return true;
+        }
+        let (i, j) = (
+            std::cmp::min(X.coor.0, Y.coor.0),
+            std::cmp::min(X.coor.1, Y.coor.1),
+        );
+        let (di, dj) = ((X.coor.0 - Y.coor.0).abs(), (X.coor.1 - Y.coor.1).abs());
+        match (di, dj) {
+            (0, 1) => return self.has(&Element::new(Attribute::Hp, Coordinate(i, j))),
+            (1, 0) => return self.has(&Element::new(Attribute::Vp, Coordinate(i, j))),
+            (1, 1) => return self.contains(i, j),
+            _ => return true,
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面の形に沿う隣接関係を作る関数（H, V, Dなどを包む）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // R: Relationship - 隣接関係
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // impl Relation - 両方の元素が存在し, 間が繋がっているときだけ成り立つ隣接関係
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn relation(&self, R: Relationship) -> impl Relation + Clone {
+        let mask = self.clone();
+        return move |X: &Element, Y: &Element| {
+            R(X, Y) && mask.has(X) && mask.has(Y) && mask.connects(X, Y)
+        };
+    }
+}
 
 // ---------------------------------------------------------------------------------------------------------------------
 // [overview]
@@ -17,6 +179,29 @@ pub fn initialize(
     Vec<Structure>,
     Vec<Structure>,
     Vec<Structure>,
+) {
+    return initialize_with_mask(board_size, &Mask::full(board_size));
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 長方形でない盤面の元素列の初期化を行う関数, 並びはinitializeから存在しない元素を除いたもの
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// board_size: &BoardSize - 盤面を囲む長方形のサイズ
+// mask: &Mask - 盤面の形
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// (Vec<Structure>, Vec<Structure>, Vec<Structure>, Vec<Structure>) - (P, C, Ep, Ec)
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn initialize_with_mask(
+    board_size: &BoardSize,
+    mask: &Mask,
+) -> (
+    Vec<Structure>,
+    Vec<Structure>,
+    Vec<Structure>,
+    Vec<Structure>,
 ) {
     let n = board_size.0;
     let m = board_size.1;
@@ -28,49 +213,165 @@ pub fn initialize(
     for i in 0..n + 1 {
         for j in 0..m + 1 {
             let coordinate = Coordinate(i + 1, j + 1);
-            let point = Structure::Element(Element::new(Attribute::P, coordinate));
-            P.push(point);
+            let element = Element::new(Attribute::P, coordinate);
+            if mask.has(&element) {
+                P.push(Structure::Element(element));
+            }
         }
     }
 
     for i in 0..n {
         for j in 0..m {
             let coordinate = Coordinate(i + 1, j + 1);
-            let cell = Structure::Element(Element::new(Attribute::C, coordinate));
-            C.push(cell);
+            let element = Element::new(Attribute::C, coordinate);
+            if mask.has(&element) {
+                C.push(Structure::Element(element));
+            }
         }
     }
 
     for i in 0..n + 1 {
         for j in 0..m {
             let coordinate = Coordinate(i + 1, j + 1);
-            let point_horizon_edge = Structure::Element(Element::new(Attribute::Hp, coordinate));
-            Ep.push(point_horizon_edge);
+            let element = Element::new(Attribute::Hp, coordinate);
+            if mask.has(&element) {
+                Ep.push(Structure::Element(element));
+            }
         }
     }
 
     for i in 0..n {
         for j in 0..m + 1 {
             let coordinate = Coordinate(i + 1, j + 1);
-            let point_vertical_edge = Structure::Element(Element::new(Attribute::Vp, coordinate));
-            Ep.push(point_vertical_edge);
+            let element = Element::new(Attribute::Vp, coordinate);
+            if mask.has(&element) {
+                Ep.push(Structure::Element(element));
+            }
         }
     }
 
     for i in 0..n {
         for j in 0..m - 1 {
             let coordinate = Coordinate(i + 1, j + 1);
-            let cell_horizon_edge = Structure::Element(Element::new(Attribute::Hc, coordinate));
-            Ec.push(cell_horizon_edge);
+            let element = Element::new(Attribute::Hc, coordinate);
+            if mask.has(&element) {
+                Ec.push(Structure::Element(element));
+            }
         }
     }
 
     for i in 0..n - 1 {
         for j in 0..m {
             let coordinate = Coordinate(i + 1, j + 1);
-            let cell_vertical_edge = Structure::Element(Element::new(Attribute::Vc, coordinate));
-            Ec.push(cell_vertical_edge);
+            let element = Element::new(Attribute::Vc, coordinate);
+            if mask.has(&element) {
+                Ec.push(Structure::Element(element));
+            }
         }
     }
     return (P, C, Ep, Ec);
 }
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 端が繋がった盤面の元素列の初期化を行う関数, 並びはinitializeと同じ
+// 端を越える格子点辺・セル辺（Hc(i, m), Vc(n, j)など）が加わり, 端で重なる格子点・格子点辺は盤面の中の代表だけになる
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// board_size: &BoardSize - 盤面サイズ
+// topology: &Topology - 盤面の位相
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// (Vec<Structure>, Vec<Structure>, Vec<Structure>, Vec<Structure>) - (P, C, Ep, Ec)
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn initialize_with_topology(
+    board_size: &BoardSize,
+    topology: &Topology,
+) -> (
+    Vec<Structure>,
+    Vec<Structure>,
+    Vec<Structure>,
+    Vec<Structure>,
+) {
+    let grid = |attr: Attribute| {
+        let (rows, columns) = topology.extent(&attr, board_size);
+        let mut elements: Vec<Structure> = Vec::new();
+        for i in 0..rows {
+            for j in 0..columns {
+                let coordinate = Coordinate(i + 1, j + 1);
+                elements.push(Structure::Element(Element::new(attr.clone(), coordinate)));
+            }
+        }
+        return elements;
+    };
+
+    // 行数と列数はTopology::extent（端が繋がる向きでは格子点は最後の行・列が最初と重なり, セル辺は端を越える辺が加わる）
+    let P = grid(Attribute::P);
+    let C = grid(Attribute::C);
+    let mut Ep = grid(Attribute::Hp);
+    Ep.extend(grid(Attribute::Vp));
+    let mut Ec = grid(Attribute::Hc);
+    Ec.extend(grid(Attribute::Vc));
+    return (P, C, Ep, Ec);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::{combine, combine_with_mask};
+    use crate::common::profile::Named;
+    use crate::common::relationship::H;
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+    use crate::specific::structure_functions::StructureFn;
+
+    #[test]
+    fn test_mask() {
+        // 中央に穴の空いた3×3の盤面
+        let (board_size, mask) = Mask::parse("###\n#.#\n###");
+        let (P, C, Ep, Ec) = initialize_with_mask(&board_size, &mask);
+        assert_eq!((P.len(), C.len(), Ep.len(), Ec.len()), (16, 8, 24, 8));
+        assert!(!C.contains(&Structure::Element(Element::new(
+            Attribute::C,
+            Coordinate(2, 2)
+        ))));
+
+        // 穴の中の格子点どうしは存在するが繋がっていない
+        let p22 = Element::new(Attribute::P, Coordinate(2, 2));
+        let p23 = Element::new(Attribute::P, Coordinate(2, 3));
+        let p12 = Element::new(Attribute::P, Coordinate(1, 2));
+        let p13 = Element::new(Attribute::P, Coordinate(1, 3));
+        assert!(mask.has(&p22) && mask.has(&p23));
+        assert_eq!(mask.relation(H).holds(&p22, &p23), true);
+        let (_, hole) = Mask::parse("#.#");
+        let q11 = Element::new(Attribute::P, Coordinate(1, 2));
+        let q12 = Element::new(Attribute::P, Coordinate(1, 3));
+        assert_eq!(hole.relation(H).holds(&q11, &q12), false);
+        assert_eq!(mask.relation(H).holds(&p12, &p13), true);
+
+        // 組成演算も穴を跨がない（各行の格子点が2つずつに分かれる）
+        let (P, _, _, _) = initialize_with_mask(&BoardSize(1, 3), &hole);
+        let no_cutoff: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+        assert_eq!(combine(vec![H], vec![], &P, &no_cutoff).len(), 20);
+        assert_eq!(
+            combine_with_mask(vec![H], vec![], &P, &no_cutoff, &hole).len(),
+            12
+        );
+
+        // 穴の左と上のセルも端になる
+        let cell = |i, j| Structure::Element(Element::new(Attribute::C, Coordinate(i, j)));
+        assert!(StructureFn::is_side_masked(&cell(2, 1), &mask));
+        assert!(StructureFn::is_side_masked(&cell(1, 2), &mask));
+        assert!(!StructureFn::is_side_masked(&cell(1, 1), &mask));
+
+        let full = Mask::full(&BoardSize(2, 3));
+        assert_eq!(
+            initialize_with_mask(&BoardSize(2, 3), &full),
+            initialize(&BoardSize(2, 3))
+        );
+        assert_eq!(
+            Mask::from_cells(&BoardSize(1, 3), &vec![Coordinate(1, 1), Coordinate(1, 3)]),
+            hole
+        );
+    }
+}
@@ -1,5 +1,23 @@
+pub mod assignment;
+pub mod board;
+pub mod checkpoint;
 pub mod combine;
+pub mod count;
+pub mod cube;
 pub mod dataclass;
+pub mod diagonal;
+pub mod driver;
+pub mod frontier;
+pub mod graph;
+pub mod hex;
 pub mod initialize;
 pub mod operate_structures;
+pub mod profile;
+pub mod pzpr;
 pub mod relationship;
+pub mod render;
+pub mod serialize;
+pub mod shard;
+pub mod sink;
+pub mod topology;
+pub mod zdd;
@@ -1,4 +1,5 @@
 use crate::common::dataclass::{Attribute, BoardSize, Composition, Coordinate, Element, Structure};
+use crate::common::profile::{Named, Profile};
 use crate::specific::board_validation::BoardValidationFn;
 use indicatif::{ProgressBar, ProgressStyle};
 use rand::Rng;
@@ -32,9 +33,9 @@ impl OperateStructure {
                 c1.entity.iter().all(|e1| {
                     match e1 {
                         Structure::Element(el1) => {
-                            // c2のentity内で, el1と同じCoordinateとAttributeを持つElementが存在するか
+                            // c2のentity内で, el1と同じCoordinateとAttribute（と層）を持つElementが存在するか
                             c2.entity.iter().any(|e2| match e2 {
-                                Structure::Element(el2) => el1.coor == el2.coor && el1.attr == el2.attr,
+                                Structure::Element(el2) => el1.same(el2),
                                 _ => false,
                             })
                         }
@@ -45,9 +46,9 @@ impl OperateStructure {
             c2.entity.iter().all(|e2| {
                 match e2 {
                     Structure::Element(el2) => {
-                        // c1のentity内で, el2と同じCoordinateとAttributeを持つElementが存在するか
+                        // c1のentity内で, el2と同じCoordinateとAttribute（と層）を持つElementが存在するか
                         c1.entity.iter().any(|e1| match e1 {
-                            Structure::Element(el1) => el1.coor == el2.coor && el1.attr == el2.attr,
+                            Structure::Element(el1) => el1.same(el2),
                             _ => false,
                         })
                     }
@@ -56,7 +57,7 @@ impl OperateStructure {
             })
             }
             (Structure::Element(e1), Structure::Element(e2)) => {
-                return e1.attr == e2.attr && e1.coor == e2.coor;
+                return e1.same(e2);
             }
             _ => false,
         }
@@ -70,16 +71,17 @@ impl OperateStructure {
     // ---------------------------------------------------------------------------------------------------------------------
     // [params]
     // set: &Vec<Structure> - 構造体全てを含む集合, 基本的にcombineの返り値を入れればよい.
-    // board_validation_fn: &Vec<BoardValidationFn> - 盤面に存在する構造体同士が満たすべきバリデーションを入れる. 重なっていない(Mでない)を入力することが多い.
+    // board_validation_fn: &Vec<Named<BoardValidationFn>> - 盤面に存在する構造体同士が満たすべきバリデーションを入れる. 重なっていない(Mでない)を入力することが多い.
+    // rng: &mut R - 乱数生成器, 同じシードからは同じ部分集合が作成される
     // ---------------------------------------------------------------------------------------------------------------------
     // [return]
     // Vec<Structure> - 作成された部分集合
     // ---------------------------------------------------------------------------------------------------------------------
-    pub fn random_subset_with_validation(
+    pub fn random_subset_with_validation<R: Rng>(
         set: &Vec<Structure>,
-        board_validation_fn: &Vec<BoardValidationFn>,
+        board_validation_fn: &Vec<Named<BoardValidationFn>>,
+        rng: &mut R,
     ) -> Vec<Structure> {
-        let mut rng = rand::thread_rng(); // 乱数生成器のインスタンス
         'outer: loop {
             let mut subset = Vec::new();
             for item in set {
@@ -91,8 +93,10 @@ impl OperateStructure {
             if subset.len() == 0 {
                 continue 'outer;
             }
-            for function in board_validation_fn {
-                if !function(&subset) {
+            for (name, function) in board_validation_fn {
+                if !Profile::check_named("board_validation", name, function(&subset), || {
+                    format!("{:?}", subset)
+                }) {
                     continue 'outer;
                 }
             }
@@ -107,12 +111,12 @@ impl OperateStructure {
     // ---------------------------------------------------------------------------------------------------------------------
     // [params]
     // structures: &Vec<Structure> - 構造体全てを含む集合, 基本的にcombineの返り値を入れればよい.
+    // rng: &mut R - 乱数生成器
     // ---------------------------------------------------------------------------------------------------------------------
     // [return]
     // Structure - ランダムに取り出された構造体
     // ---------------------------------------------------------------------------------------------------------------------
-    pub fn extract_random_structure(structures: &Vec<Structure>) -> Structure {
-        let mut rng = rand::thread_rng(); // 乱数生成器のインスタンス
+    pub fn extract_random_structure<R: Rng>(structures: &Vec<Structure>, rng: &mut R) -> Structure {
         let index = rng.gen_range(0..structures.len()); // ランダムなインデックスを生成
         return structures.get(index).unwrap().clone(); // ランダムに選ばれた要素を返す
     }
@@ -0,0 +1,304 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+// 名前と組にした関数, カットオフ関数などは集計表にこの名前で出る
+pub type Named<F> = (&'static str, F);
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 関数の列を, 書いたとおりの名前と組にするマクロ
+// 例: let cutoff_functions: Vec<Named<CutoffFn>> = named![size_limitation, Cutoff::is_square];
+// ---------------------------------------------------------------------------------------------------------------------
+#[macro_export]
+macro_rules! named {
+    ($($f:path),* $(,)?) => {
+        vec![$( (stringify!($f), $f as _) ),*]
+    };
+}
+
+#[derive(Default)]
+struct Counter {
+    evaluated: AtomicU64,
+    rejected: AtomicU64,
+    first_rejected: OnceLock<String>,
+}
+
+// 0: 未設定, 1: 集計しない, 2: --profile, 3: --profile-first
+static STATE: AtomicU8 = AtomicU8::new(0);
+static COUNTERS: Mutex<BTreeMap<(&'static str, &'static str), Arc<Counter>>> =
+    Mutex::new(BTreeMap::new());
+
+thread_local! {
+    static CACHE: RefCell<HashMap<(&'static str, &'static str), Arc<Counter>>> =
+        RefCell::new(HashMap::new());
+    // このスレッドだけの集計の設定（0: STATEに従う）, テストがSTATEを書き換えて他のテストに影響しないようにする
+    static LOCAL_STATE: Cell<u8> = const { Cell::new(0) };
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 集計表の1行
+// kind: 種類（cutoff, board_validation, predicate, check）
+// name: 関数の名前
+// evaluated: 評価した回数, rejected: 棄却した回数
+// first_rejected: 最初に棄却した候補（--profile-firstのときのみ）
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfileRow {
+    pub kind: String,
+    pub name: String,
+    pub evaluated: u64,
+    pub rejected: u64,
+    pub first_rejected: Option<String>,
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// カットオフ関数・盤面のバリデーション・述語ごとの棄却の集計
+// コマンドライン引数に--profileがあるときだけ集計する（--profile-firstで最初に棄却した候補も記録する）
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct Profile {}
+
+impl Profile {
+    // 集計するか
+    pub fn enabled() -> bool {
+        return Profile::flags().0;
+    }
+
+    // (集計するか, 最初に棄却した候補を記録するか), 未設定ならコマンドライン引数から決める
+    fn flags() -> (bool, bool) {
+        let local = LOCAL_STATE.with(|local| local.get());
+        if local != 0 {
+            return (local >= 2, local == 3);
+        }
+        let mut state = STATE.load(Ordering::Relaxed);
+        if state == 0 {
+            let args: Vec<String> = std::env::args().collect();
+            state = if args.iter().any(|arg| arg == "--profile-first") {
+                3
+            } else if args.iter().any(|arg| arg == "--profile") {
+                2
+            } else {
+                1
+            };
+            STATE.store(state, Ordering::Relaxed);
+        }
+        return (state >= 2, state == 3);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // コマンドライン引数によらず集計の有無を決める関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // enabled: bool - 集計するか
+    // first: bool - 最初に棄却した候補を記録するか
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn configure(enabled: bool, first: bool) {
+        STATE.store(Profile::state_of(enabled, first), Ordering::Relaxed);
+    }
+
+    // 呼び出したスレッドでだけ集計の有無を決める（テスト用）
+    #[cfg(test)]
+    fn configure_local(enabled: bool, first: bool) {
+        LOCAL_STATE.with(|local| local.set(Profile::state_of(enabled, first)));
+    }
+
+    fn state_of(enabled: bool, first: bool) -> u8 {
+        return match (enabled, first) {
+            (false, _) => 1,
+            (true, false) => 2,
+            (true, true) => 3,
+        };
+    }
+
+    fn counter(kind: &'static str, name: &'static str) -> Arc<Counter> {
+        return CACHE.with(|cache| {
+            cache
+                .borrow_mut()
+                .entry((kind, name))
+                .or_insert_with(|| {
+                    COUNTERS
+                        .lock()
+                        .unwrap()
+                        .entry((kind, name))
+                        .or_default()
+                        .clone()
+                })
+                .clone()
+        });
+    }
+
+    fn record<C: FnOnce() -> String>(
+        kind: &'static str,
+        name: &'static str,
+        passed: bool,
+        candidate: C,
+    ) {
+        let counter = Profile::counter(kind, name);
+        counter.evaluated.fetch_add(1, Ordering::Relaxed);
+        if !passed {
+            counter.rejected.fetch_add(1, Ordering::Relaxed);
+            if Profile::flags().1 && counter.first_rejected.get().is_none() {
+                let _ = counter.first_rejected.set(candidate());
+            }
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 名前で指定した関数（カットオフ関数, 述語など）の評価結果を集計する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // kind: &'static str - 種類
+    // name: &'static str - 関数の名前（Named<F>の名前など）
+    // passed: bool - 評価結果
+    // candidate: C - 棄却された候補の文字列表現を作る関数（必要なときだけ呼ばれる）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // bool - passedをそのまま返す
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn check_named<C: FnOnce() -> String>(
+        kind: &'static str,
+        name: &'static str,
+        passed: bool,
+        candidate: C,
+    ) -> bool {
+        if Profile::enabled() {
+            Profile::record(kind, name, passed, candidate);
+        }
+        return passed;
+    }
+
+    // 評価回数と棄却回数をまとめて加える（Driver::requireが述語の評価ごとに集計するのに使う）
+    pub fn add_named(kind: &'static str, name: &'static str, evaluated: u64, rejected: u64) {
+        if Profile::enabled() {
+            let counter = Profile::counter(kind, name);
+            counter.evaluated.fetch_add(evaluated, Ordering::Relaxed);
+            counter.rejected.fetch_add(rejected, Ordering::Relaxed);
+        }
+    }
+
+    // 名前で指定した述語が最初に棄却した候補を記録する
+    pub fn first_named<C: FnOnce() -> String>(
+        kind: &'static str,
+        name: &'static str,
+        candidate: C,
+    ) {
+        if Profile::enabled() && Profile::flags().1 {
+            let counter = Profile::counter(kind, name);
+            if counter.first_rejected.get().is_none() {
+                let _ = counter.first_rejected.set(candidate());
+            }
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // これまでの集計を返す関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<ProfileRow> - 種類, 名前の順に並べた集計
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn report() -> Vec<ProfileRow> {
+        let mut rows: Vec<ProfileRow> = COUNTERS
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((kind, name), counter)| ProfileRow {
+                kind: kind.to_string(),
+                name: name.to_string(),
+                evaluated: counter.evaluated.load(Ordering::Relaxed),
+                rejected: counter.rejected.load(Ordering::Relaxed),
+                first_rejected: counter.first_rejected.get().cloned(),
+            })
+            .collect();
+        rows.sort_by(|a, b| (&a.kind, &a.name).cmp(&(&b.kind, &b.name)));
+        return rows;
+    }
+
+    // 集計表を標準エラー出力に表示する
+    pub fn print() {
+        let rows = Profile::report();
+        let width = rows
+            .iter()
+            .map(|row| row.name.len())
+            .max()
+            .unwrap_or(4)
+            .max(4);
+        eprintln!(
+            "{:<16} {:<width$} {:>14} {:>14} {:>8}",
+            "kind",
+            "name",
+            "evaluated",
+            "rejected",
+            "rate",
+            width = width
+        );
+        for row in rows.iter() {
+            let rate = if row.evaluated == 0 {
+                0.0
+            } else {
+                100.0 * row.rejected as f64 / row.evaluated as f64
+            };
+            eprintln!(
+                "{:<16} {:<width$} {:>14} {:>14} {:>7.2}%",
+                row.kind,
+                row.name,
+                row.evaluated,
+                row.rejected,
+                rate,
+                width = width
+            );
+        }
+        for row in rows.iter() {
+            if let Some(ref candidate) = row.first_rejected {
+                // 構造体のDebug表示は改行を含むので1行にまとめる
+                let candidate = candidate.replace('\n', "");
+                eprintln!("first rejected by {} {}: {}", row.kind, row.name, candidate);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::dataclass::{Attribute, Composition, Coordinate, Element, Structure};
+    use crate::common::driver::Driver;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+
+    #[test]
+    fn test_profile() {
+        // STATEを書き換えると並行して走る他のテストも集計してしまうので, このスレッドでだけ集計する
+        Profile::configure_local(true, true);
+        let cutoffs: Vec<Named<CutoffFn>> = named![Cutoff::is_square];
+        let (name, cutoff) = cutoffs[0];
+        let line = Structure::Composition(Composition::new(vec![
+            Structure::Element(Element::new(Attribute::C, Coordinate(1, 1))),
+            Structure::Element(Element::new(Attribute::C, Coordinate(1, 2))),
+        ]));
+        let passed = Profile::check_named("cutoff", name, cutoff(&line), || "line".to_string());
+        assert!(!passed);
+        Profile::check_named("predicate", "test_predicate", true, || unreachable!());
+        // 判定関数の述語は打ち切られた分を除いて, 実際に評価した回数だけ数える
+        assert!(Driver::require("test_require", true).is_ok());
+        assert_eq!(Driver::require("test_require", false), Err("test_require"));
+
+        let rows = Profile::report();
+        let square = rows
+            .iter()
+            .find(|row| row.name == "Cutoff::is_square")
+            .unwrap();
+        assert_eq!((square.evaluated, square.rejected), (1, 1));
+        assert_eq!(square.first_rejected, Some("line".to_string()));
+        let predicate = rows
+            .iter()
+            .find(|row| row.name == "test_predicate")
+            .unwrap();
+        assert_eq!((predicate.evaluated, predicate.rejected), (1, 0));
+        let require = rows.iter().find(|row| row.name == "test_require").unwrap();
+        assert_eq!((require.evaluated, require.rejected), (2, 1));
+
+        LOCAL_STATE.with(|local| local.set(0));
+    }
+}
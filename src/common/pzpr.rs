@@ -0,0 +1,1531 @@
+use crate::common::dataclass::{Attribute, BoardSize, Composition, Coordinate, Element, Structure};
+use crate::common::serialize::{AreaRecord, BoardRecord};
+use crate::specific::structure_functions::StructureFn;
+use std::collections::{HashMap, HashSet};
+
+// ---------------------------------------------------------------------------------------------------------------------
+// pzprjs（ぱずぷれ）のpzprv3ファイル形式, puzz.linkのURL形式との相互変換
+// 盤面はserialize.rsのBoardRecordを介してやり取りする
+// 問題（URL）には手がかりだけが, ファイルには手がかりと解答が含まれる
+// ---------------------------------------------------------------------------------------------------------------------
+
+const URL_PREFIX: &str = "https://puzz.link/p?";
+
+// URLの本体を構成する符号化の種類（pzprjsのEncode.jsの関数に対応）
+#[derive(Clone, Copy, PartialEq)]
+enum UrlPart {
+    Number10,
+    Number16,
+    FourCell,
+    Hitori,
+    Border,
+    RoomNumber16,
+}
+
+// pzprv3ファイルの盤面データを構成する区画（pzprjsのFileData.jsの関数に対応）
+#[derive(Clone, Copy, PartialEq)]
+enum FilePart {
+    CellQnum,
+    CellAns,
+    CellAnumsub,
+    BorderLine,
+    BorderAns,
+    AreaRoom,
+}
+
+// 問題の数字（qnum）を盤面のどこから取るか
+// 解答のない問題（URL）には領域も線もないので, AreaValueとLineEndsの手がかりは細胞の値にも持つ
+#[derive(Clone, Copy, PartialEq)]
+enum Clue {
+    // 細胞の値そのもの
+    CellValue,
+    // 領域の値を領域の左上の細胞に置く（細胞の値は手がかりの数字）
+    AreaValue,
+    // 線の値+1を線の両端の細胞に置く（細胞の値は端にある線の値）
+    LineEnds,
+}
+
+// 線がどの辺の上にあるか
+#[derive(Clone, Copy, PartialEq)]
+enum Lines {
+    None,
+    // 格子点辺（Ep）, 盤面の外周を含む
+    Point,
+    // 細胞辺（Ec）, 盤面の内側のみ
+    Cell,
+}
+
+// パズルごとの変換規則
+struct PzprRule {
+    rule: &'static str,
+    pid: &'static str,
+    url: &'static [UrlPart],
+    file: &'static [FilePart],
+    clue: Clue,
+    lines: Lines,
+    // 細胞の値を解答の数字（anum）として扱う
+    answer_numbers: bool,
+    // 部屋として扱う構造体のラベル
+    room_label: Option<&'static str>,
+    // 黒マスとして扱う構造体のラベル
+    shade_label: Option<&'static str>,
+    // 黒マスでない細胞のつながりを白い領域として扱う構造体のラベル
+    white_label: Option<&'static str>,
+}
+
+const RULES: [PzprRule; 12] = [
+    PzprRule {
+        rule: "slitherlink",
+        pid: "slither",
+        url: &[UrlPart::FourCell],
+        file: &[FilePart::CellQnum, FilePart::BorderLine],
+        clue: Clue::CellValue,
+        lines: Lines::Point,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: None,
+        white_label: None,
+    },
+    PzprRule {
+        rule: "hitori",
+        pid: "hitori",
+        url: &[UrlPart::Hitori],
+        file: &[FilePart::CellQnum, FilePart::CellAns],
+        clue: Clue::CellValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: Some("black"),
+        white_label: None,
+    },
+    PzprRule {
+        rule: "norinori",
+        pid: "norinori",
+        url: &[UrlPart::Border],
+        file: &[FilePart::AreaRoom, FilePart::CellAns],
+        clue: Clue::CellValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: Some("room"),
+        shade_label: Some("black"),
+        white_label: None,
+    },
+    PzprRule {
+        rule: "numberlink",
+        pid: "numlin",
+        url: &[UrlPart::Number16],
+        file: &[FilePart::CellQnum, FilePart::BorderLine],
+        clue: Clue::LineEnds,
+        lines: Lines::Cell,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: None,
+        white_label: None,
+    },
+    PzprRule {
+        rule: "shikaku",
+        pid: "shikaku",
+        url: &[UrlPart::Number16],
+        file: &[FilePart::CellQnum, FilePart::BorderAns],
+        clue: Clue::AreaValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: Some("room"),
+        shade_label: None,
+        white_label: None,
+    },
+    PzprRule {
+        rule: "fillomino",
+        pid: "fillomino",
+        url: &[UrlPart::Number16],
+        file: &[FilePart::CellQnum, FilePart::CellAnumsub],
+        clue: Clue::CellValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: None,
+        white_label: None,
+    },
+    PzprRule {
+        rule: "sukoro",
+        pid: "sukoro",
+        url: &[UrlPart::Number10],
+        file: &[FilePart::CellQnum, FilePart::CellAnumsub],
+        clue: Clue::CellValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: None,
+        white_label: None,
+    },
+    PzprRule {
+        rule: "usowan",
+        pid: "usoone",
+        url: &[UrlPart::Border, UrlPart::Number10],
+        file: &[FilePart::AreaRoom, FilePart::CellQnum, FilePart::CellAns],
+        clue: Clue::CellValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: Some("room"),
+        shade_label: Some("black"),
+        white_label: None,
+    },
+    PzprRule {
+        rule: "chocobanana",
+        pid: "cbanana",
+        url: &[UrlPart::Number16],
+        file: &[FilePart::CellQnum, FilePart::CellAns],
+        clue: Clue::AreaValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: Some("black"),
+        white_label: Some("white"),
+    },
+    PzprRule {
+        rule: "kurounit",
+        pid: "kurotto",
+        url: &[UrlPart::Number16],
+        file: &[FilePart::CellQnum, FilePart::CellAns],
+        clue: Clue::CellValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: Some("black"),
+        white_label: None,
+    },
+    PzprRule {
+        rule: "inshi_no_heya",
+        pid: "factors",
+        url: &[UrlPart::Border, UrlPart::RoomNumber16],
+        file: &[
+            FilePart::AreaRoom,
+            FilePart::CellQnum,
+            FilePart::CellAnumsub,
+        ],
+        clue: Clue::AreaValue,
+        lines: Lines::None,
+        answer_numbers: true,
+        room_label: Some("room"),
+        shade_label: None,
+        white_label: None,
+    },
+    PzprRule {
+        rule: "sudoku",
+        pid: "sudoku",
+        url: &[UrlPart::Number16],
+        file: &[FilePart::CellQnum, FilePart::CellAnumsub],
+        clue: Clue::CellValue,
+        lines: Lines::None,
+        answer_numbers: false,
+        room_label: None,
+        shade_label: None,
+        white_label: None,
+    },
+];
+
+// pzprjs側の盤面表現, 細胞は行優先で並べる
+// vline: rows * (cols + 1)個の縦の境界（細胞(r, c)の左がc）, hline: (rows + 1) * cols個の横の境界（細胞(r, c)の上がr）
+struct PzprBoard {
+    rows: usize,
+    cols: usize,
+    qnum: Vec<Option<i32>>,
+    anum: Vec<Option<i32>>,
+    shaded: Vec<bool>,
+    vline: Vec<i32>,
+    hline: Vec<i32>,
+    room: Option<Vec<usize>>,
+}
+
+pub struct Pzpr {}
+
+impl Pzpr {
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 記録をpuzz.linkのURLに変換する関数（手がかりのみが含まれる）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // record: &BoardRecord - 盤面の記録, record.ruleでパズルを判別する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<String, String> - URL, 対応していないパズルの場合はエラー
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn to_url(record: &BoardRecord) -> Result<String, String> {
+        let rule = find_rule_by_name(&record.rule)?;
+        let board = to_pzpr_board(rule, record)?;
+        let mut body = String::new();
+        for part in rule.url.iter() {
+            match part {
+                UrlPart::Number10 => body.push_str(&encode_number10(&board.qnum)),
+                UrlPart::Number16 => body.push_str(&encode_number16(&board.qnum)),
+                UrlPart::FourCell => body.push_str(&encode_4cell(&board.qnum)),
+                UrlPart::Hitori => body.push_str(&encode_hitori(&board.qnum)),
+                UrlPart::Border => body.push_str(&encode_border(&board)),
+                UrlPart::RoomNumber16 => {
+                    let tops = room_tops(&board);
+                    let nums: Vec<Option<i32>> = tops.iter().map(|&c| board.qnum[c]).collect();
+                    body.push_str(&encode_number16(&nums));
+                }
+            }
+        }
+        return Ok(format!(
+            "{}{}/{}/{}/{}",
+            URL_PREFIX, rule.pid, board.cols, board.rows, body
+        ));
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // puzz.linkのURL（p?以降, pzv.jpなどの別ドメインも可）を記録に変換する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // url: &str - URL
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<BoardRecord, String> - 盤面の記録（手がかりのみ）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn from_url(url: &str) -> Result<BoardRecord, String> {
+        let query = match url.find('?') {
+            Some(i) => &url[i + 1..],
+            None => url,
+        };
+        let mut items = query.splitn(4, '/');
+        let pid = items.next().unwrap_or("");
+        let rule = find_rule_by_pid(pid)?;
+        let cols = parse_size(items.next())?;
+        let rows = parse_size(items.next())?;
+        let mut body: &str = items.next().unwrap_or("");
+        // 各decode関数は読んだ文字数を返すので, 文字数とバイト数が一致するASCIIだけを受け付ける
+        if !body.is_ascii() {
+            return Err("URL body must be ASCII".to_string());
+        }
+        let mut board = PzprBoard::new(rows, cols);
+        for part in rule.url.iter() {
+            let used = match part {
+                UrlPart::Number10 => decode_number10(body, &mut board.qnum),
+                UrlPart::Number16 => decode_number16(body, &mut board.qnum),
+                UrlPart::FourCell => decode_4cell(body, &mut board.qnum),
+                UrlPart::Hitori => decode_hitori(body, &mut board.qnum),
+                UrlPart::Border => decode_border(body, &mut board),
+                UrlPart::RoomNumber16 => {
+                    let tops = room_tops(&board);
+                    let mut nums: Vec<Option<i32>> = vec![None; tops.len()];
+                    let used = decode_number16(body, &mut nums);
+                    for (i, &c) in tops.iter().enumerate() {
+                        board.qnum[c] = nums[i];
+                    }
+                    used
+                }
+            };
+            // 途中で切れたURLでは読んだ文字数が残りより大きくなることがある
+            body = &body[used.min(body.len())..];
+        }
+        return Ok(from_pzpr_board(rule, &board));
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 記録をpzprv3ファイルの文字列に変換する関数（手がかりと解答が含まれる）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // record: &BoardRecord - 盤面の記録
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<String, String> - ファイルの内容
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn to_file(record: &BoardRecord) -> Result<String, String> {
+        let rule = find_rule_by_name(&record.rule)?;
+        let board = to_pzpr_board(rule, record)?;
+        let mut file = format!("pzprv3\n{}\n{}\n{}\n", rule.pid, board.rows, board.cols);
+        let (rows, cols) = (board.rows, board.cols);
+        for part in rule.file.iter() {
+            match part {
+                FilePart::CellQnum => {
+                    file.push_str(&write_grid(rows, cols, |c| match board.qnum[c] {
+                        Some(-2) => "-".to_string(),
+                        Some(v) => v.to_string(),
+                        None => ".".to_string(),
+                    }));
+                }
+                FilePart::CellAns => {
+                    file.push_str(&write_grid(rows, cols, |c| {
+                        if board.shaded[c] {
+                            "#".to_string()
+                        } else {
+                            ".".to_string()
+                        }
+                    }));
+                }
+                FilePart::CellAnumsub => {
+                    file.push_str(&write_grid(rows, cols, |c| match board.anum[c] {
+                        Some(v) => v.to_string(),
+                        None => ".".to_string(),
+                    }));
+                }
+                FilePart::BorderLine => {
+                    let outer = rule.lines == Lines::Point;
+                    file.push_str(&write_borders(&board, outer, |v| v.to_string()));
+                }
+                FilePart::BorderAns => {
+                    let borders = room_borders(&board);
+                    file.push_str(&write_borders(&borders, false, |v| v.to_string()));
+                }
+                FilePart::AreaRoom => {
+                    let room = board.room.clone().unwrap_or(vec![0; rows * cols]);
+                    let count = room.iter().collect::<HashSet<_>>().len();
+                    file.push_str(&format!("{}\n", count));
+                    file.push_str(&write_grid(rows, cols, |c| room[c].to_string()));
+                }
+            }
+        }
+        return Ok(file);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // pzprv3ファイルの文字列を記録に変換する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // text: &str - ファイルの内容
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<BoardRecord, String> - 盤面の記録（手がかりと解答）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn from_file(text: &str) -> Result<BoardRecord, String> {
+        let mut lines = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+        let header = lines.next().unwrap_or("");
+        if !header.starts_with("pzprv3") {
+            return Err(format!("not a pzprv3 file: {}", header));
+        }
+        let rule = find_rule_by_pid(lines.next().unwrap_or(""))?;
+        let rows = parse_size(lines.next())?;
+        let cols = parse_size(lines.next())?;
+        let mut board = PzprBoard::new(rows, cols);
+
+        // 盤面データを行ごとに読み進める
+        let mut read_rows = |count: usize| -> Result<Vec<Vec<String>>, String> {
+            let mut result: Vec<Vec<String>> = Vec::new();
+            for _ in 0..count {
+                let line = lines.next().ok_or("unexpected end of file".to_string())?;
+                result.push(line.split_whitespace().map(|s| s.to_string()).collect());
+            }
+            return Ok(result);
+        };
+
+        for part in rule.file.iter() {
+            match part {
+                FilePart::CellQnum | FilePart::CellAns | FilePart::CellAnumsub => {
+                    let grid = read_rows(rows)?;
+                    for r in 0..rows {
+                        for c in 0..cols {
+                            let item = grid[r].get(c).map(|s| s.as_str()).unwrap_or(".");
+                            let i = r * cols + c;
+                            match part {
+                                FilePart::CellQnum => {
+                                    board.qnum[i] = match item {
+                                        "-" => Some(-2),
+                                        "." => None,
+                                        _ => Some(parse_number(item)?),
+                                    };
+                                }
+                                FilePart::CellAns => board.shaded[i] = item == "#",
+                                _ => {
+                                    board.anum[i] = match item.parse::<i32>() {
+                                        Ok(v) if v >= 0 => Some(v),
+                                        _ => None,
+                                    };
+                                }
+                            }
+                        }
+                    }
+                }
+                FilePart::BorderLine | FilePart::BorderAns => {
+                    let outer = *part == FilePart::BorderLine && rule.lines == Lines::Point;
+                    let (v_from, v_to) = if outer { (0, cols) } else { (1, cols - 1) };
+                    let (h_from, h_to) = if outer { (0, rows) } else { (1, rows - 1) };
+                    let mut borders = PzprBoard::new(rows, cols);
+                    let grid = read_rows(rows)?;
+                    for r in 0..rows {
+                        for c in v_from..=v_to {
+                            let item = grid[r].get(c - v_from).map(|s| s.as_str()).unwrap_or("0");
+                            borders.vline[r * (cols + 1) + c] = (parse_number(item)? == 1) as i32;
+                        }
+                    }
+                    let grid = read_rows(h_to + 1 - h_from)?;
+                    for r in h_from..=h_to {
+                        for c in 0..cols {
+                            let item = grid[r - h_from].get(c).map(|s| s.as_str()).unwrap_or("0");
+                            borders.hline[r * cols + c] = (parse_number(item)? == 1) as i32;
+                        }
+                    }
+                    if *part == FilePart::BorderLine {
+                        board.vline = borders.vline;
+                        board.hline = borders.hline;
+                    } else {
+                        board.room = Some(rooms_from_borders(&borders));
+                    }
+                }
+                FilePart::AreaRoom => {
+                    read_rows(1)?;
+                    let grid = read_rows(rows)?;
+                    let mut room: Vec<usize> = vec![0; rows * cols];
+                    for r in 0..rows {
+                        for c in 0..cols {
+                            let item = grid[r].get(c).map(|s| s.as_str()).unwrap_or("0");
+                            room[r * cols + c] = parse_number(item)? as usize;
+                        }
+                    }
+                    board.room = Some(room);
+                }
+            }
+        }
+        return Ok(from_pzpr_board(rule, &board));
+    }
+}
+
+impl PzprBoard {
+    fn new(rows: usize, cols: usize) -> Self {
+        PzprBoard {
+            rows,
+            cols,
+            qnum: vec![None; rows * cols],
+            anum: vec![None; rows * cols],
+            shaded: vec![false; rows * cols],
+            vline: vec![0; rows * (cols + 1)],
+            hline: vec![0; (rows + 1) * cols],
+            room: None,
+        }
+    }
+}
+
+fn find_rule_by_name(name: &str) -> Result<&'static PzprRule, String> {
+    RULES
+        .iter()
+        .find(|r| r.rule == name)
+        .ok_or(format!("unsupported rule: {}", name))
+}
+
+fn find_rule_by_pid(pid: &str) -> Result<&'static PzprRule, String> {
+    RULES
+        .iter()
+        .find(|r| r.pid == pid)
+        .ok_or(format!("unsupported puzzle: {}", pid))
+}
+
+fn parse_size(item: Option<&str>) -> Result<usize, String> {
+    match item.and_then(|s| s.trim().parse::<usize>().ok()) {
+        Some(size) if size > 0 => Ok(size),
+        _ => Err(format!("invalid board size: {:?}", item)),
+    }
+}
+
+fn parse_number(item: &str) -> Result<i32, String> {
+    item.parse::<i32>()
+        .map_err(|_| format!("invalid number: {}", item))
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// ↓ 記録とpzprjsの盤面表現の変換
+
+// 構造体の細胞の座標（行優先の番号）を集める
+fn cells_of(elements: &Vec<Element>, cols: usize) -> Vec<usize> {
+    elements
+        .iter()
+        .filter(|e| e.attr == Attribute::C)
+        .map(|e| (e.coor.0 - 1) as usize * cols + (e.coor.1 - 1) as usize)
+        .collect()
+}
+
+// 元素の座標が盤面の中にあるかを確認する（正方形の盤面の元素以外はpzprに書き出さないので確認しない）
+// 書き出すときは属性と座標で元素を区別するので, 層のある元素（立体の盤面）は盤面の外として扱う
+fn check_bounds(e: &Element, rows: i32, cols: i32) -> Result<(), String> {
+    let (attr, coor, layer) = e.key();
+    if layer != 0 {
+        return Err(format!("element out of board: {:?}", e));
+    }
+    let (max_y, max_x) = match attr {
+        Attribute::C => (rows, cols),
+        Attribute::P => (rows + 1, cols + 1),
+        Attribute::Hp => (rows + 1, cols),
+        Attribute::Vp => (rows, cols + 1),
+        Attribute::Hc => (rows, cols - 1),
+        Attribute::Vc => (rows - 1, cols),
+        _ => return Ok(()),
+    };
+    if coor.0 < 1 || coor.0 > max_y || coor.1 < 1 || coor.1 > max_x {
+        return Err(format!("element out of board: {:?}", e));
+    }
+    return Ok(());
+}
+
+fn to_pzpr_board(rule: &PzprRule, record: &BoardRecord) -> Result<PzprBoard, String> {
+    if record.board_size.0 <= 0 || record.board_size.1 <= 0 {
+        return Err("invalid board size".to_string());
+    }
+    for e in record.elements.iter() {
+        check_bounds(e, record.board_size.0, record.board_size.1)?;
+    }
+    for area in record.areas.iter() {
+        for e in area.elements.iter() {
+            check_bounds(e, record.board_size.0, record.board_size.1)?;
+        }
+    }
+    let rows = record.board_size.0 as usize;
+    let cols = record.board_size.1 as usize;
+    let mut board = PzprBoard::new(rows, cols);
+
+    for e in record.elements.iter() {
+        let (y, x) = ((e.coor.0 - 1) as usize, (e.coor.1 - 1) as usize);
+        match e.attr {
+            Attribute::C => {
+                if rule.answer_numbers {
+                    board.anum[y * cols + x] = e.val;
+                } else if rule.clue == Clue::LineEnds {
+                    board.qnum[y * cols + x] = e.val.map(|v| v + 1);
+                } else {
+                    board.qnum[y * cols + x] = e.val;
+                }
+            }
+            // 格子点辺はそのまま境界の上の線になる
+            Attribute::Hp if rule.lines == Lines::Point => {
+                board.hline[y * cols + x] = line_value(e.val)
+            }
+            Attribute::Vp if rule.lines == Lines::Point => {
+                board.vline[y * (cols + 1) + x] = line_value(e.val)
+            }
+            // 細胞辺は2つの細胞の間の境界を横切る
+            Attribute::Hc if rule.lines == Lines::Cell => {
+                board.vline[y * (cols + 1) + x + 1] = line_value(e.val)
+            }
+            Attribute::Vc if rule.lines == Lines::Cell => {
+                board.hline[(y + 1) * cols + x] = line_value(e.val)
+            }
+            _ => {}
+        }
+    }
+
+    let mut room: Vec<usize> = (0..rows * cols).collect();
+    let mut has_room = false;
+    for area in record.areas.iter() {
+        let cells = cells_of(&area.elements, cols);
+        if Some(area.label.as_str()) == rule.shade_label {
+            for &c in cells.iter() {
+                board.shaded[c] = true;
+            }
+        }
+        if Some(area.label.as_str()) == rule.room_label {
+            has_room = true;
+            if let Some(&first) = cells.iter().min() {
+                for &c in cells.iter() {
+                    room[c] = first;
+                }
+            }
+        }
+        // 細胞の値で手がかりを置いた領域はそのままにする
+        if rule.clue == Clue::AreaValue && cells.iter().all(|&c| board.qnum[c].is_none()) {
+            if let Some(&top) = cells.iter().min() {
+                board.qnum[top] = area.val;
+            }
+        }
+        if rule.clue == Clue::LineEnds {
+            let mut line = Vec::new();
+            for e in area.elements.iter() {
+                line.push(Structure::Element(e.clone()));
+            }
+            let composition = Structure::Composition(Composition::new(line));
+            for end in StructureFn::line_edgepoints(&composition) {
+                if let Structure::Element(ref end_content) = end {
+                    let c = (end_content.coor.0 - 1) as usize * cols
+                        + (end_content.coor.1 - 1) as usize;
+                    board.qnum[c] = area.val.map(|v| v + 1);
+                }
+            }
+        }
+    }
+    if has_room {
+        board.room = Some(renumber_rooms(&room));
+    }
+    return Ok(board);
+}
+
+fn line_value(val: Option<i32>) -> i32 {
+    match val {
+        Some(v) if v != 0 => 1,
+        _ => 0,
+    }
+}
+
+fn from_pzpr_board(rule: &PzprRule, board: &PzprBoard) -> BoardRecord {
+    let (rows, cols) = (board.rows, board.cols);
+    let board_size = BoardSize(rows as i32, cols as i32);
+    let mut record = BoardRecord::new(rule.rule, &board_size);
+    let cell_coor = |c: usize| Coordinate((c / cols) as i32 + 1, (c % cols) as i32 + 1);
+
+    for c in 0..rows * cols {
+        let mut element = Element::new(Attribute::C, cell_coor(c));
+        element.val = if rule.answer_numbers {
+            board.anum[c]
+        } else {
+            match rule.clue {
+                Clue::CellValue => board.anum[c].or(board.qnum[c]).filter(|&v| v != -2),
+                Clue::AreaValue => board.qnum[c].filter(|&v| v >= 0),
+                Clue::LineEnds => board.qnum[c].filter(|&v| v > 0).map(|v| v - 1),
+            }
+        };
+        record.elements.push(element);
+    }
+
+    match rule.lines {
+        Lines::Point => {
+            for r in 0..=rows {
+                for c in 0..cols {
+                    let mut e = Element::new(Attribute::Hp, Coordinate(r as i32 + 1, c as i32 + 1));
+                    e.val = Some(board.hline[r * cols + c]);
+                    record.elements.push(e);
+                }
+            }
+            for r in 0..rows {
+                for c in 0..=cols {
+                    let mut e = Element::new(Attribute::Vp, Coordinate(r as i32 + 1, c as i32 + 1));
+                    e.val = Some(board.vline[r * (cols + 1) + c]);
+                    record.elements.push(e);
+                }
+            }
+        }
+        Lines::Cell => {
+            for r in 0..rows {
+                for c in 0..cols - 1 {
+                    let mut e = Element::new(Attribute::Hc, Coordinate(r as i32 + 1, c as i32 + 1));
+                    e.val = Some(board.vline[r * (cols + 1) + c + 1]);
+                    record.elements.push(e);
+                }
+            }
+            for r in 0..rows - 1 {
+                for c in 0..cols {
+                    let mut e = Element::new(Attribute::Vc, Coordinate(r as i32 + 1, c as i32 + 1));
+                    e.val = Some(board.hline[(r + 1) * cols + c]);
+                    record.elements.push(e);
+                }
+            }
+            push_lines(&mut record, board);
+        }
+        Lines::None => {}
+    }
+
+    // 部屋, 黒マス, 白い領域を構造体として復元する
+    let area_value = |cells: &Vec<usize>| -> Option<i32> {
+        if rule.clue != Clue::AreaValue {
+            return None;
+        }
+        cells
+            .iter()
+            .filter_map(|&c| board.qnum[c])
+            .find(|&v| v >= 0)
+    };
+    if let (Some(label), Some(room)) = (rule.room_label, board.room.as_ref()) {
+        for cells in group_cells(room) {
+            push_area(&mut record, label, area_value(&cells), &cells, cols);
+        }
+    }
+    if let Some(label) = rule.shade_label {
+        for cells in connected_cells(board, true) {
+            push_area(&mut record, label, area_value(&cells), &cells, cols);
+        }
+    }
+    if let Some(label) = rule.white_label {
+        for cells in connected_cells(board, false) {
+            push_area(&mut record, label, area_value(&cells), &cells, cols);
+        }
+    }
+    return record;
+}
+
+fn push_area(
+    record: &mut BoardRecord,
+    label: &str,
+    val: Option<i32>,
+    cells: &Vec<usize>,
+    cols: usize,
+) {
+    record.areas.push(AreaRecord {
+        label: label.to_string(),
+        val,
+        elements: cells
+            .iter()
+            .map(|&c| {
+                Element::new(
+                    Attribute::C,
+                    Coordinate((c / cols) as i32 + 1, (c % cols) as i32 + 1),
+                )
+            })
+            .collect(),
+    });
+}
+
+// 細胞辺の線をつながりごとに"line"の構造体として復元する, 値は端の数字-1
+fn push_lines(record: &mut BoardRecord, board: &PzprBoard) {
+    let (rows, cols) = (board.rows, board.cols);
+    let mut edges: Vec<(usize, usize, Element)> = Vec::new();
+    for r in 0..rows {
+        for c in 0..cols - 1 {
+            if board.vline[r * (cols + 1) + c + 1] == 1 {
+                let e = Element::new(Attribute::Hc, Coordinate(r as i32 + 1, c as i32 + 1));
+                edges.push((r * cols + c, r * cols + c + 1, e));
+            }
+        }
+    }
+    for r in 0..rows - 1 {
+        for c in 0..cols {
+            if board.hline[(r + 1) * cols + c] == 1 {
+                let e = Element::new(Attribute::Vc, Coordinate(r as i32 + 1, c as i32 + 1));
+                edges.push((r * cols + c, (r + 1) * cols + c, e));
+            }
+        }
+    }
+    let mut parent: Vec<usize> = (0..rows * cols).collect();
+    fn find(parent: &mut Vec<usize>, x: usize) -> usize {
+        if parent[x] != x {
+            let root = find(parent, parent[x]);
+            parent[x] = root;
+        }
+        parent[x]
+    }
+    for (a, b, _) in edges.iter() {
+        let (ra, rb) = (find(&mut parent, *a), find(&mut parent, *b));
+        parent[ra] = rb;
+    }
+    let mut groups: Vec<(usize, Vec<Element>, Option<i32>)> = Vec::new();
+    for (a, b, e) in edges.into_iter() {
+        let root = find(&mut parent, a);
+        let val = board.qnum[a]
+            .or(board.qnum[b])
+            .filter(|&v| v > 0)
+            .map(|v| v - 1);
+        match groups.iter_mut().find(|g| g.0 == root) {
+            Some(g) => {
+                g.1.push(e);
+                g.2 = g.2.or(val);
+            }
+            None => groups.push((root, vec![e], val)),
+        }
+    }
+    for (_, elements, val) in groups.into_iter() {
+        record.areas.push(AreaRecord {
+            label: "line".to_string(),
+            val,
+            elements,
+        });
+    }
+}
+
+// 部屋番号ごとに細胞をまとめる（最初に現れた順）
+fn group_cells(room: &Vec<usize>) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = Vec::new();
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (c, &r) in room.iter().enumerate() {
+        if !groups.contains_key(&r) {
+            order.push(r);
+        }
+        groups.entry(r).or_default().push(c);
+    }
+    order
+        .into_iter()
+        .map(|r| groups.remove(&r).unwrap())
+        .collect()
+}
+
+// 部屋番号を左上から順に0, 1, 2, ...と振り直す
+fn renumber_rooms(room: &Vec<usize>) -> Vec<usize> {
+    let mut result: Vec<usize> = vec![0; room.len()];
+    for (i, cells) in group_cells(room).iter().enumerate() {
+        for &c in cells.iter() {
+            result[c] = i;
+        }
+    }
+    return result;
+}
+
+// 黒マス（shaded = true）または白マスの縦横のつながりを求める
+fn connected_cells(board: &PzprBoard, shaded: bool) -> Vec<Vec<usize>> {
+    let (rows, cols) = (board.rows, board.cols);
+    let mut room: Vec<usize> = vec![usize::MAX; rows * cols];
+    let mut result: Vec<Vec<usize>> = Vec::new();
+    for start in 0..rows * cols {
+        if board.shaded[start] != shaded || room[start] != usize::MAX {
+            continue;
+        }
+        let mut cells: Vec<usize> = vec![start];
+        room[start] = result.len();
+        let mut i = 0;
+        while i < cells.len() {
+            let c = cells[i];
+            let (r, x) = (c / cols, c % cols);
+            let mut neighbors: Vec<usize> = Vec::new();
+            if r > 0 {
+                neighbors.push(c - cols);
+            }
+            if r + 1 < rows {
+                neighbors.push(c + cols);
+            }
+            if x > 0 {
+                neighbors.push(c - 1);
+            }
+            if x + 1 < cols {
+                neighbors.push(c + 1);
+            }
+            for d in neighbors {
+                if board.shaded[d] == shaded && room[d] == usize::MAX {
+                    room[d] = result.len();
+                    cells.push(d);
+                }
+            }
+            i += 1;
+        }
+        cells.sort();
+        result.push(cells);
+    }
+    return result;
+}
+
+// 部屋の境界（内側のみ）を線として表現した盤面を返す
+fn room_borders(board: &PzprBoard) -> PzprBoard {
+    let (rows, cols) = (board.rows, board.cols);
+    let mut borders = PzprBoard::new(rows, cols);
+    if let Some(ref room) = board.room {
+        for r in 0..rows {
+            for c in 1..cols {
+                borders.vline[r * (cols + 1) + c] =
+                    (room[r * cols + c - 1] != room[r * cols + c]) as i32;
+            }
+        }
+        for r in 1..rows {
+            for c in 0..cols {
+                borders.hline[r * cols + c] =
+                    (room[(r - 1) * cols + c] != room[r * cols + c]) as i32;
+            }
+        }
+    }
+    return borders;
+}
+
+// 境界線から部屋番号を求める
+fn rooms_from_borders(borders: &PzprBoard) -> Vec<usize> {
+    let (rows, cols) = (borders.rows, borders.cols);
+    let mut room: Vec<usize> = vec![usize::MAX; rows * cols];
+    let mut count = 0;
+    for start in 0..rows * cols {
+        if room[start] != usize::MAX {
+            continue;
+        }
+        let mut stack: Vec<usize> = vec![start];
+        room[start] = count;
+        while let Some(c) = stack.pop() {
+            let (r, x) = (c / cols, c % cols);
+            let mut neighbors: Vec<usize> = Vec::new();
+            if r > 0 && borders.hline[r * cols + x] == 0 {
+                neighbors.push(c - cols);
+            }
+            if r + 1 < rows && borders.hline[(r + 1) * cols + x] == 0 {
+                neighbors.push(c + cols);
+            }
+            if x > 0 && borders.vline[r * (cols + 1) + x] == 0 {
+                neighbors.push(c - 1);
+            }
+            if x + 1 < cols && borders.vline[r * (cols + 1) + x + 1] == 0 {
+                neighbors.push(c + 1);
+            }
+            for d in neighbors {
+                if room[d] == usize::MAX {
+                    room[d] = count;
+                    stack.push(d);
+                }
+            }
+        }
+        count += 1;
+    }
+    return room;
+}
+
+// 部屋ごとの左上の細胞（部屋番号順）
+fn room_tops(board: &PzprBoard) -> Vec<usize> {
+    match board.room {
+        Some(ref room) => group_cells(room).iter().map(|cells| cells[0]).collect(),
+        None => Vec::new(),
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// ↓ pzprv3ファイルの盤面データ
+
+fn write_grid<F: Fn(usize) -> String>(rows: usize, cols: usize, item: F) -> String {
+    let mut result = String::new();
+    for r in 0..rows {
+        for c in 0..cols {
+            result.push_str(&item(r * cols + c));
+            result.push(' ');
+        }
+        result.push('\n');
+    }
+    return result;
+}
+
+// 縦の境界（rows行）, 横の境界の順に書き出す. outerがfalseのときは盤面の内側の境界のみ
+fn write_borders<F: Fn(i32) -> String>(board: &PzprBoard, outer: bool, item: F) -> String {
+    let (rows, cols) = (board.rows, board.cols);
+    let (v_from, v_to) = if outer { (0, cols) } else { (1, cols - 1) };
+    let (h_from, h_to) = if outer { (0, rows) } else { (1, rows - 1) };
+    let mut result = String::new();
+    for r in 0..rows {
+        for c in v_from..=v_to {
+            result.push_str(&item(board.vline[r * (cols + 1) + c]));
+            result.push(' ');
+        }
+        result.push('\n');
+    }
+    for r in h_from..=h_to {
+        for c in 0..cols {
+            result.push_str(&item(board.hline[r * cols + c]));
+            result.push(' ');
+        }
+        result.push('\n');
+    }
+    return result;
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// ↓ URLの符号化, decode_*は読んだ文字数を返す
+
+// 空白がcount個続くことを表す文字（count + offsetの36進数1文字）
+fn skip_char(count: usize, offset: usize) -> char {
+    std::char::from_digit((count + offset) as u32, 36).unwrap()
+}
+
+fn encode_number10(nums: &Vec<Option<i32>>) -> String {
+    let mut result = String::new();
+    let mut count = 0;
+    for num in nums.iter() {
+        let item = match num {
+            Some(-2) => ".".to_string(),
+            Some(v) if (0..10).contains(v) => v.to_string(),
+            _ => String::new(),
+        };
+        if item.is_empty() {
+            count += 1;
+        }
+        if !item.is_empty() || count == 26 {
+            if count > 0 {
+                result.push(skip_char(count, 9));
+                count = 0;
+            }
+            result.push_str(&item);
+        }
+    }
+    if count > 0 {
+        result.push(skip_char(count, 9));
+    }
+    return result;
+}
+
+fn decode_number10(body: &str, nums: &mut Vec<Option<i32>>) -> usize {
+    let chars: Vec<char> = body.chars().collect();
+    let (mut c, mut i) = (0, 0);
+    while i < chars.len() && c < nums.len() {
+        let ca = chars[i];
+        match ca {
+            '0'..='9' => nums[c] = ca.to_digit(10).map(|v| v as i32),
+            '.' => nums[c] = Some(-2),
+            'a'..='z' => c += ca.to_digit(36).unwrap() as usize - 10,
+            _ => {}
+        }
+        c += 1;
+        i += 1;
+    }
+    return i;
+}
+
+fn encode_number16(nums: &Vec<Option<i32>>) -> String {
+    let mut result = String::new();
+    let mut count = 0;
+    for num in nums.iter() {
+        let item = match num {
+            Some(-2) => ".".to_string(),
+            Some(v) if (0..16).contains(v) => format!("{:x}", v),
+            Some(v) if (16..256).contains(v) => format!("-{:02x}", v),
+            Some(v) if (256..4096).contains(v) => format!("+{:03x}", v),
+            Some(v) if (4096..8192).contains(v) => format!("={:03x}", v - 4096),
+            Some(v) if (8192..12288).contains(v) => format!("%{:03x}", v - 8192),
+            _ => String::new(),
+        };
+        if item.is_empty() {
+            count += 1;
+        }
+        if !item.is_empty() || count == 20 {
+            if count > 0 {
+                result.push(skip_char(count, 15));
+                count = 0;
+            }
+            result.push_str(&item);
+        }
+    }
+    if count > 0 {
+        result.push(skip_char(count, 15));
+    }
+    return result;
+}
+
+fn decode_number16(body: &str, nums: &mut Vec<Option<i32>>) -> usize {
+    let chars: Vec<char> = body.chars().collect();
+    let hex = |from: usize, len: usize| -> Option<i32> {
+        let s: String = chars.iter().skip(from).take(len).collect();
+        i32::from_str_radix(&s, 16).ok()
+    };
+    let (mut c, mut i) = (0, 0);
+    while i < chars.len() && c < nums.len() {
+        let ca = chars[i];
+        match ca {
+            '0'..='9' | 'a'..='f' => nums[c] = ca.to_digit(16).map(|v| v as i32),
+            '-' => {
+                nums[c] = hex(i + 1, 2);
+                i += 2;
+            }
+            '+' => {
+                nums[c] = hex(i + 1, 3);
+                i += 3;
+            }
+            '=' => {
+                nums[c] = hex(i + 1, 3).map(|v| v + 4096);
+                i += 3;
+            }
+            '%' => {
+                nums[c] = hex(i + 1, 3).map(|v| v + 8192);
+                i += 3;
+            }
+            '.' => nums[c] = Some(-2),
+            'g'..='z' => c += ca.to_digit(36).unwrap() as usize - 16,
+            _ => {}
+        }
+        c += 1;
+        i += 1;
+    }
+    return i;
+}
+
+// 0から4の数字と, その後に続く空白（2個まで）を1文字にまとめる
+fn encode_4cell(nums: &Vec<Option<i32>>) -> String {
+    let mut result = String::new();
+    let mut c = 0;
+    while c < nums.len() {
+        match nums[c] {
+            Some(v) if (0..5).contains(&v) => {
+                let mut count = 0;
+                while count < 2 && c + count + 1 < nums.len() && is_blank(nums[c + count + 1]) {
+                    count += 1;
+                }
+                result.push(std::char::from_digit((v + 5 * count as i32) as u32, 16).unwrap());
+                c += count + 1;
+            }
+            Some(-2) => {
+                result.push('.');
+                c += 1;
+            }
+            _ => {
+                let mut count = 0;
+                while count < 20 && c + count < nums.len() && is_blank(nums[c + count]) {
+                    count += 1;
+                }
+                result.push(skip_char(count, 15));
+                c += count;
+            }
+        }
+    }
+    return result;
+}
+
+fn is_blank(num: Option<i32>) -> bool {
+    match num {
+        Some(v) => v != -2 && !(0..5).contains(&v),
+        None => true,
+    }
+}
+
+fn decode_4cell(body: &str, nums: &mut Vec<Option<i32>>) -> usize {
+    let chars: Vec<char> = body.chars().collect();
+    let (mut c, mut i) = (0, 0);
+    while i < chars.len() && c < nums.len() {
+        let ca = chars[i];
+        match ca {
+            '0'..='9' | 'a'..='e' => {
+                let v = ca.to_digit(16).unwrap() as i32;
+                nums[c] = Some(v % 5);
+                c += (v / 5) as usize + 1;
+            }
+            '.' => {
+                nums[c] = Some(-2);
+                c += 1;
+            }
+            'g'..='z' => c += ca.to_digit(36).unwrap() as usize - 15,
+            _ => c += 1,
+        }
+        i += 1;
+    }
+    return i;
+}
+
+// ひとりにまかせての数字（36進数）, 空白は1個ずつ.で表す
+fn encode_hitori(nums: &Vec<Option<i32>>) -> String {
+    let mut result = String::new();
+    for num in nums.iter() {
+        match num {
+            Some(-2) => result.push('%'),
+            Some(v) if (0..36).contains(v) => {
+                result.push(std::char::from_digit(*v as u32, 36).unwrap())
+            }
+            Some(v) if (36..1296).contains(v) => {
+                result.push('-');
+                result.push(std::char::from_digit((*v / 36) as u32, 36).unwrap());
+                result.push(std::char::from_digit((*v % 36) as u32, 36).unwrap());
+            }
+            _ => result.push('.'),
+        }
+    }
+    return result;
+}
+
+fn decode_hitori(body: &str, nums: &mut Vec<Option<i32>>) -> usize {
+    let chars: Vec<char> = body.chars().collect();
+    let (mut c, mut i) = (0, 0);
+    while i < chars.len() && c < nums.len() {
+        let ca = chars[i];
+        match ca {
+            '0'..='9' | 'a'..='z' => nums[c] = ca.to_digit(36).map(|v| v as i32),
+            '-' => {
+                let s: String = chars.iter().skip(i + 1).take(2).collect();
+                nums[c] = i32::from_str_radix(&s, 36).ok();
+                i += 2;
+            }
+            '%' => nums[c] = Some(-2),
+            _ => {}
+        }
+        c += 1;
+        i += 1;
+    }
+    return i;
+}
+
+// 部屋の境界を縦の境界, 横の境界の順に5ビットずつ32進数の1文字にする
+fn encode_border(board: &PzprBoard) -> String {
+    let (rows, cols) = (board.rows, board.cols);
+    let borders = room_borders(board);
+    let mut bits: Vec<Vec<i32>> = vec![Vec::new(), Vec::new()];
+    for r in 0..rows {
+        for c in 1..cols {
+            bits[0].push(borders.vline[r * (cols + 1) + c]);
+        }
+    }
+    for r in 1..rows {
+        for c in 0..cols {
+            bits[1].push(borders.hline[r * cols + c]);
+        }
+    }
+    let mut result = String::new();
+    for group in bits.iter() {
+        for chunk in group.chunks(5) {
+            let mut num = 0;
+            for (w, bit) in chunk.iter().enumerate() {
+                if *bit == 1 {
+                    num |= 16 >> w;
+                }
+            }
+            result.push(std::char::from_digit(num, 32).unwrap());
+        }
+    }
+    return result;
+}
+
+fn decode_border(body: &str, board: &mut PzprBoard) -> usize {
+    let (rows, cols) = (board.rows, board.cols);
+    let chars: Vec<char> = body.chars().collect();
+    let len_v = (cols - 1) * rows;
+    let len_h = cols * (rows - 1);
+    let pos1 = std::cmp::min(len_v.div_ceil(5), chars.len());
+    let pos2 = std::cmp::min(len_h.div_ceil(5) + pos1, chars.len());
+    let bits = |from: usize, to: usize, len: usize| -> Vec<i32> {
+        let mut result: Vec<i32> = Vec::new();
+        for ca in chars[from..to].iter() {
+            let num = ca.to_digit(32).unwrap_or(0);
+            for w in 0..5 {
+                if result.len() < len {
+                    result.push(((num & (16 >> w)) != 0) as i32);
+                }
+            }
+        }
+        result.resize(len, 0);
+        result
+    };
+    let v_bits = bits(0, pos1, len_v);
+    let h_bits = bits(pos1, pos2, len_h);
+    let mut borders = PzprBoard::new(rows, cols);
+    for r in 0..rows {
+        for c in 1..cols {
+            borders.vline[r * (cols + 1) + c] = v_bits[r * (cols - 1) + c - 1];
+        }
+    }
+    for r in 1..rows {
+        for c in 0..cols {
+            borders.hline[r * cols + c] = h_bits[(r - 1) * cols + c];
+        }
+    }
+    board.room = Some(rooms_from_borders(&borders));
+    return pos2;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_round_trip() {
+        let url = "https://puzz.link/p?slither/3/2/db";
+        let record = Pzpr::from_url(url).unwrap();
+        assert_eq!(record.rule, "slitherlink");
+        assert_eq!(record.board_size, (2, 3));
+        let values: Vec<Option<i32>> = record
+            .elements_of(&Attribute::C)
+            .iter()
+            .map(|c| match c {
+                Structure::Element(ref e) => e.val,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(values, vec![Some(3), None, None, Some(1), None, None]);
+        assert_eq!(Pzpr::to_url(&record).unwrap(), url);
+
+        let url = "https://puzz.link/p?norinori/3/3/50s0";
+        let record = Pzpr::from_url(url).unwrap();
+        assert_eq!(record.areas_of("room").len(), 3);
+        assert_eq!(Pzpr::to_url(&record).unwrap(), url);
+
+        // ASCIIでない本文や途中で切れた本文はpanicせずに扱う
+        assert!(Pzpr::from_url("https://puzz.link/p?slither/3/2/dé").is_err());
+        assert!(Pzpr::from_url("https://puzz.link/p?shikaku/2/2/+1").is_ok());
+
+        // 盤面の外の元素はpanicせずにErrになる
+        let mut broken = Pzpr::from_url("https://puzz.link/p?slither/3/2/db").unwrap();
+        broken.elements[0].coor = Coordinate(0, 4);
+        assert!(Pzpr::to_url(&broken).is_err());
+        // 層のある元素は同じ属性と座標の平面の元素と区別できないので書き出さない
+        let mut layered = Pzpr::from_url("https://puzz.link/p?slither/3/2/db").unwrap();
+        layered.elements[0].layer = 1;
+        assert!(Pzpr::to_url(&layered).is_err());
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let mut record = BoardRecord::new("shikaku", &BoardSize(2, 2));
+        for y in 1..=2 {
+            record.areas.push(AreaRecord {
+                label: "room".to_string(),
+                val: Some(2),
+                elements: vec![
+                    Element::new(Attribute::C, Coordinate(y, 1)),
+                    Element::new(Attribute::C, Coordinate(y, 2)),
+                ],
+            });
+        }
+        let file = Pzpr::to_file(&record).unwrap();
+        assert_eq!(file, "pzprv3\nshikaku\n2\n2\n2 . \n2 . \n0 \n0 \n1 1 \n");
+        let parsed = Pzpr::from_file(&file).unwrap();
+        assert_eq!(parsed.areas_of("room"), record.areas_of("room"));
+        assert_eq!(
+            Pzpr::to_url(&parsed).unwrap(),
+            "https://puzz.link/p?shikaku/2/2/2g2g"
+        );
+    }
+
+    // URLを読んで書き戻し, 同じURLになることを確かめてから読んだ記録を返す
+    fn round_trip(body: &str) -> BoardRecord {
+        let url = format!("https://puzz.link/p?{}", body);
+        let record = Pzpr::from_url(&url).unwrap();
+        assert_eq!(Pzpr::to_url(&record).unwrap(), url);
+        return record;
+    }
+
+    // 細胞の値を行優先の順で並べる
+    fn cell_values(record: &BoardRecord) -> Vec<Option<i32>> {
+        return record
+            .elements_of(&Attribute::C)
+            .iter()
+            .map(|c| match c {
+                Structure::Element(ref e) => e.val,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    #[test]
+    fn test_url_slitherlink() {
+        let record = round_trip("slither/3/2/db");
+        assert_eq!(record.rule, "slitherlink");
+        assert_eq!(cell_values(&record)[0], Some(3));
+    }
+
+    #[test]
+    fn test_url_hitori() {
+        // 36以上の数字は'-'と2桁で書く
+        let record = round_trip("hitori/3/3/12-10.23211");
+        assert_eq!(record.rule, "hitori");
+        assert_eq!(
+            cell_values(&record),
+            vec![
+                Some(1),
+                Some(2),
+                Some(36),
+                None,
+                Some(2),
+                Some(3),
+                Some(2),
+                Some(1),
+                Some(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_url_norinori() {
+        let record = round_trip("norinori/3/3/50s0");
+        assert_eq!(record.rule, "norinori");
+        assert_eq!(record.areas_of("room").len(), 3);
+    }
+
+    #[test]
+    fn test_url_numberlink() {
+        // 線の端の数字は線の値+1なので, 線のない問題では細胞に線の値が残る
+        let record = round_trip("numlin/3/3/1g2i2g1");
+        assert_eq!(record.rule, "numberlink");
+        assert_eq!(
+            cell_values(&record),
+            vec![
+                Some(0),
+                None,
+                Some(1),
+                None,
+                None,
+                None,
+                Some(1),
+                None,
+                Some(0)
+            ]
+        );
+        assert!(record.areas_of("line").is_empty());
+    }
+
+    #[test]
+    fn test_file_numberlink() {
+        let file = "pzprv3\nnumlin\n1\n3\n1 . 1 \n1 1 \n";
+        let record = Pzpr::from_file(file).unwrap();
+        let lines = record.areas_of("line");
+        assert_eq!(lines.len(), 1);
+        assert_eq!(record.areas[0].val, Some(0));
+        assert_eq!(Pzpr::to_file(&record).unwrap(), file);
+    }
+
+    #[test]
+    fn test_url_shikaku() {
+        let record = round_trip("shikaku/2/2/2g2g");
+        assert_eq!(record.rule, "shikaku");
+        assert_eq!(cell_values(&record), vec![Some(2), None, Some(2), None]);
+    }
+
+    #[test]
+    fn test_url_fillomino() {
+        let record = round_trip("fillomino/3/3/1h2h3h");
+        assert_eq!(record.rule, "fillomino");
+        assert_eq!(
+            cell_values(&record),
+            vec![
+                Some(1),
+                None,
+                None,
+                Some(2),
+                None,
+                None,
+                Some(3),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_url_sukoro() {
+        let record = round_trip("sukoro/3/3/2b3a1c");
+        assert_eq!(record.rule, "sukoro");
+        assert_eq!(
+            cell_values(&record),
+            vec![
+                Some(2),
+                None,
+                None,
+                Some(3),
+                None,
+                Some(1),
+                None,
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_url_usowan() {
+        // 本文は境界線の部分と数字の部分の2つからなる
+        let record = round_trip("usoone/3/3/50s01c0d");
+        assert_eq!(record.rule, "usowan");
+        assert_eq!(record.areas_of("room").len(), 3);
+        assert_eq!(
+            cell_values(&record),
+            vec![Some(1), None, None, None, Some(0), None, None, None, None]
+        );
+    }
+
+    #[test]
+    fn test_url_chocobanana() {
+        let record = round_trip("cbanana/3/3/3m2");
+        assert_eq!(record.rule, "chocobanana");
+        assert_eq!(
+            cell_values(&record),
+            vec![Some(3), None, None, None, None, None, None, None, Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_url_kurounit() {
+        let record = round_trip("kurotto/3/3/g0h3j");
+        assert_eq!(record.rule, "kurounit");
+        assert_eq!(
+            cell_values(&record),
+            vec![None, Some(0), None, None, Some(3), None, None, None, None]
+        );
+    }
+
+    #[test]
+    fn test_url_inshi_no_heya() {
+        // 部屋の数字は部屋の左上の細胞の順に並び, 16以上の数字は'-'と16進2桁で書く
+        let record = round_trip("factors/3/3/50s062-18");
+        assert_eq!(record.rule, "inshi_no_heya");
+        let mut values: Vec<Option<i32>> = record.areas.iter().map(|a| a.val).collect();
+        values.sort();
+        assert_eq!(values, vec![Some(2), Some(6), Some(24)]);
+    }
+
+    #[test]
+    fn test_url_sudoku() {
+        let record = round_trip("sudoku/4/4/1j2k3i4");
+        assert_eq!(record.rule, "sudoku");
+        let values = cell_values(&record);
+        assert_eq!(
+            (values[0], values[5], values[11], values[15]),
+            (Some(1), Some(2), Some(3), Some(4))
+        );
+    }
+}
@@ -1,8 +1,176 @@
 use crate::common::dataclass::{Attribute, Coordinate, Element, Structure};
+use std::collections::HashSet;
 
 // H, V, D, Mの型
 pub type Relationship = fn(&Element, &Element) -> bool;
 
+// ---------------------------------------------------------------------------------------------------------------------
+// 元素の間の隣接関係
+// H, V, D, Mのような関数（とクロージャ）はそのまま隣接関係として使え, or, and, not などで組み合わせられる
+// 例: 「黒マスを通らずに縦横で繋がる」は H.or(V).closure(&white) で表せる
+// ---------------------------------------------------------------------------------------------------------------------
+pub trait Relation: Send + Sync {
+    // XとYがこの隣接関係にあるときtrue
+    fn holds(&self, X: &Element, Y: &Element) -> bool;
+
+    // どちらかの隣接関係にある
+    fn or<S: Relation>(self, other: S) -> Or<Self, S>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    // 両方の隣接関係にある
+    fn and<S: Relation>(self, other: S) -> And<Self, S>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    // 隣接関係にない
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+
+    // XとYを入れ替えた隣接関係（「左にある」から「右にある」を作るなど）
+    fn inverse(self) -> Inverse<Self>
+    where
+        Self: Sized,
+    {
+        Inverse(self)
+    }
+
+    // withinの元素だけを経由して, 1回以上辿って着く
+    fn closure(self, within: &Vec<Structure>) -> Closure<Self>
+    where
+        Self: Sized,
+    {
+        Closure {
+            relation: self,
+            within: leaf_elements(within),
+        }
+    }
+
+    // withinの元素だけを経由して, ちょうどk回辿って着く
+    fn power(self, k: usize, within: &Vec<Structure>) -> Power<Self>
+    where
+        Self: Sized,
+    {
+        Power {
+            relation: self,
+            k,
+            within: leaf_elements(within),
+        }
+    }
+}
+
+impl<F> Relation for F
+where
+    F: Fn(&Element, &Element) -> bool + Send + Sync,
+{
+    fn holds(&self, X: &Element, Y: &Element) -> bool {
+        return self(X, Y);
+    }
+}
+
+pub struct Or<A, B>(A, B);
+pub struct And<A, B>(A, B);
+pub struct Not<A>(A);
+pub struct Inverse<A>(A);
+
+pub struct Closure<A> {
+    relation: A,
+    within: Vec<Element>,
+}
+
+pub struct Power<A> {
+    relation: A,
+    k: usize,
+    within: Vec<Element>,
+}
+
+impl<A: Relation, B: Relation> Relation for Or<A, B> {
+    fn holds(&self, X: &Element, Y: &Element) -> bool {
+        return self.0.holds(X, Y) || self.1.holds(X, Y);
+    }
+}
+
+impl<A: Relation, B: Relation> Relation for And<A, B> {
+    fn holds(&self, X: &Element, Y: &Element) -> bool {
+        return self.0.holds(X, Y) && self.1.holds(X, Y);
+    }
+}
+
+impl<A: Relation> Relation for Not<A> {
+    fn holds(&self, X: &Element, Y: &Element) -> bool {
+        return !self.0.holds(X, Y);
+    }
+}
+
+impl<A: Relation> Relation for Inverse<A> {
+    fn holds(&self, X: &Element, Y: &Element) -> bool {
+        return self.0.holds(Y, X);
+    }
+}
+
+impl<A: Relation> Relation for Closure<A> {
+    // Xから幅優先でwithinの元素を辿り, どこかからYに隣接すればtrue
+    fn holds(&self, X: &Element, Y: &Element) -> bool {
+        let mut visited: HashSet<&Element> = HashSet::new();
+        let mut queue: Vec<&Element> = vec![X];
+        while let Some(current) = queue.pop() {
+            if self.relation.holds(current, Y) {
+                return true;
+            }
+            for e in self.within.iter() {
+                if !visited.contains(e) && self.relation.holds(current, e) {
+                    visited.insert(e);
+                    queue.push(e);
+                }
+            }
+        }
+        return false;
+    }
+}
+
+impl<A: Relation> Relation for Power<A> {
+    fn holds(&self, X: &Element, Y: &Element) -> bool {
+        if self.k == 0 {
+            return X == Y;
+        }
+        // reached: k-1回辿って着くwithinの元素
+        let mut reached: Vec<&Element> = vec![X];
+        for _ in 1..self.k {
+            let next: HashSet<&Element> = self
+                .within
+                .iter()
+                .filter(|e| reached.iter().any(|r| self.relation.holds(r, e)))
+                .collect();
+            reached = next.into_iter().collect();
+        }
+        return reached.iter().any(|r| self.relation.holds(r, Y));
+    }
+}
+
+// 構造体の列に含まれる元素を全て取り出す
+fn leaf_elements(structures: &Vec<Structure>) -> Vec<Element> {
+    let mut elements = vec![];
+    for structure in structures.iter() {
+        match structure {
+            Structure::Element(ref element) => elements.push(element.clone()),
+            Structure::Composition(ref composition) => {
+                elements.extend(leaf_elements(&composition.entity))
+            }
+        }
+    }
+    return elements;
+}
+
 // ---------------------------------------------------------------------------------------------------------------------
 // [overview]
 // 比較した2つの構造体が隣接関係にあるかを確認する（再帰）関数
@@ -10,22 +178,27 @@ pub type Relationship = fn(&Element, &Element) -> bool;
 // [params]
 // X: &Element - 構造体 1
 // Y: &Element - 構造体 2
-// R: Relationship - 確認したい隣接関係
+// R: impl Relation - 確認したい隣接関係（H, V, D, Mやその組み合わせ）
 // ---------------------------------------------------------------------------------------------------------------------
 // [return]
 // bool - 隣接関係にあるならtrue
 // ---------------------------------------------------------------------------------------------------------------------
-pub fn relationship(X: &Structure, Y: &Structure, R: Relationship) -> bool {
+pub fn relationship<R: Relation>(X: &Structure, Y: &Structure, R: R) -> bool {
+    return related(X, Y, &R);
+}
+
+// relationshipの参照版（dyn Relationにも使える）
+pub fn related<R: Relation + ?Sized>(X: &Structure, Y: &Structure, R: &R) -> bool {
     if let (Structure::Composition(ref x), Structure::Composition(ref y)) = (X, Y) {
         for x_entity in &x.entity {
             for y_entity in &y.entity {
-                if relationship(&x_entity, &y_entity, R) {
+                if related(&x_entity, &y_entity, R) {
                     return true;
                 }
             }
         }
     } else if let (Structure::Element(ref x), Structure::Element(ref y)) = (X, Y) {
-        return R(x, y);
+        return R.holds(x, y);
     }
     return false;
 }
@@ -162,20 +335,220 @@ pub fn M(X: &Element, Y: &Element) -> bool {
     let dx = X.coor.0 - Y.coor.0;
     let dy = X.coor.1 - Y.coor.1;
 
-    if dx == 0 && dy == 0 {
-        match (&X.attr, &Y.attr) {
-            (Attribute::P, Attribute::P)
-            | (Attribute::C, Attribute::C)
-            | (Attribute::Hp, Attribute::Hp)
-            | (Attribute::Vp, Attribute::Vp)
-            | (Attribute::Hc, Attribute::Hc)
-            | (Attribute::Vc, Attribute::Vc) => return true,
-            _ => false,
-        };
+    // 属性と座標（立体の盤面では層も）が同じとき一致
+    if dx == 0 && dy == 0 && X.layer == Y.layer && X.attr == Y.attr {
+        return true;
+    }
+    return false;
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// ↓ 距離・方向の隣接関係
+// 属性の異なる元素どうしも比べられるよう, 元素を2倍の座標に置いて比べる
+// P(i, j) -> (2i-1, 2j-1), C(i, j) -> (2i, 2j), Hp(i, j) -> (2i-1, 2j), Vp(i, j) -> (2i, 2j-1),
+// Hc(i, j) -> (2i, 2j+1), Vc(i, j) -> (2i+1, 2j)（Hc, VcはそれぞれVp, Hpと同じ位置で交差する）
+// 距離の引数kはセル単位で, 2倍の座標では2kにあたる
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// 元素の2倍の座標を求める関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// X: &Element - 元素
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// (i32, i32) - 2倍の座標(行, 列)
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn position(X: &Element) -> (i32, i32) {
+    let (i, j) = (X.coor.0, X.coor.1);
+    match X.attr {
+        Attribute::P => return (2 * i - 1, 2 * j - 1),
+        Attribute::C => return (2 * i, 2 * j),
+        // セルの斜線と三角形はセルと同じ位置
+        Attribute::Ds | Attribute::Db => return (2 * i, 2 * j),
+        Attribute::Tnw | Attribute::Tne | Attribute::Tsw | Attribute::Tse => return (2 * i, 2 * j),
+        Attribute::Hp => return (2 * i - 1, 2 * j),
+        Attribute::Vp => return (2 * i, 2 * j - 1),
+        Attribute::Hc => return (2 * i, 2 * j + 1),
+        Attribute::Vc => return (2 * i + 1, 2 * j),
+        // 六角形の盤面の元素は座標がそのまま格子の上の位置, 平面グラフの元素は位置を持たないので番号の組
+        Attribute::Xp | Attribute::Xc | Attribute::Xep | Attribute::Xec => return (i, j),
+        Attribute::Gp | Attribute::Gc | Attribute::Gep | Attribute::Gec => return (i, j),
+        // 立体の盤面の元素は層を除いた2倍の座標（cube.rsのposition3を参照）
+        Attribute::P3 | Attribute::C3 | Attribute::Fl | Attribute::Fr | Attribute::Fc => {
+            let (_, y, x) = crate::common::cube::position3(X);
+            return (y, x);
+        }
+    }
+}
+
+// 2倍の座標での差(行, 列), 同じ元素ならNone
+fn difference(X: &Element, Y: &Element) -> Option<(i32, i32)> {
+    if X == Y {
+        return None;
+    }
+    let (x, y) = (position(X), position(Y));
+    return Some((y.0 - x.0, y.1 - x.1));
+}
+
+// 同じ行にある（異なる元素）
+pub fn same_row(X: &Element, Y: &Element) -> bool {
+    return matches!(difference(X, Y), Some((0, _)));
+}
+
+// 同じ列にある（異なる元素）
+pub fn same_column(X: &Element, Y: &Element) -> bool {
+    return matches!(difference(X, Y), Some((_, 0)));
+}
+
+// XがYより真に左にある（行は問わない）
+pub fn left_of(X: &Element, Y: &Element) -> bool {
+    return position(X).1 < position(Y).1;
+}
+
+// XがYより真に右にある（行は問わない）
+pub fn right_of(X: &Element, Y: &Element) -> bool {
+    return left_of(Y, X);
+}
+
+// XがYより真に上にある（列は問わない）
+pub fn above(X: &Element, Y: &Element) -> bool {
+    return position(X).0 < position(Y).0;
+}
+
+// XがYより真に下にある（列は問わない）
+pub fn below(X: &Element, Y: &Element) -> bool {
+    return above(Y, X);
+}
+
+// チェビシェフ距離が1セル以内にある（将棋の玉・チェスのキングの動き, 属性が同じならH, V, Dのいずれか）
+pub fn king(X: &Element, Y: &Element) -> bool {
+    if let Some((dy, dx)) = difference(X, Y) {
+        return dy.abs() <= 2 && dx.abs() <= 2;
     }
     return false;
 }
 
+// 縦横に(1, 2)または(2, 1)セルずれている（チェスのナイトの動き）
+pub fn knight(X: &Element, Y: &Element) -> bool {
+    if let Some((dy, dx)) = difference(X, Y) {
+        let (dy, dx) = (dy.abs(), dx.abs());
+        return (dy == 2 && dx == 4) || (dy == 4 && dx == 2);
+    }
+    return false;
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// チェビシェフ距離（縦横斜めの移動回数）がkセル以内にある, という隣接関係を作る関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// k: i32 - 距離の上限（セル単位）
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// impl Relation - 隣接関係（同じ元素どうしは含まない）
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn within(k: i32) -> impl Relation + Copy {
+    return move |X: &Element, Y: &Element| match difference(X, Y) {
+        Some((dy, dx)) => dy.abs() <= 2 * k && dx.abs() <= 2 * k,
+        None => false,
+    };
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// マンハッタン距離（縦横の移動回数）がkセル以内にある, という隣接関係を作る関数
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// k: i32 - 距離の上限（セル単位）
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// impl Relation - 隣接関係（同じ元素どうしは含まない）
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn manhattan_within(k: i32) -> impl Relation + Copy {
+    return move |X: &Element, Y: &Element| match difference(X, Y) {
+        Some((dy, dx)) => dy.abs() + dx.abs() <= 2 * k,
+        None => false,
+    };
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// YがXから(dy, dx)セルだけずれた位置にある, という隣接関係を作る関数
+// 例えば offset(0, 1) は「すぐ右隣」, offset(-1, 0) は「すぐ上隣」
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// dy: i32 - 行のずれ（下が正）
+// dx: i32 - 列のずれ（右が正）
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// impl Relation - 隣接関係
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn offset(dy: i32, dx: i32) -> impl Relation + Copy {
+    return move |X: &Element, Y: &Element| {
+        let (x, y) = (position(X), position(Y));
+        y.0 - x.0 == 2 * dy && y.1 - x.1 == 2 * dx
+    };
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// ↓ 種類の異なる元素の接続関係
+// いずれも対称で, 2倍の座標（position）の差で判定する
+
+// セルCとその周りの格子点辺（Hp, Vp）: 格子点辺から見ればその両側のセル
+pub fn borders(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::C, Attribute::Hp)
+        | (Attribute::C, Attribute::Vp)
+        | (Attribute::Hp, Attribute::C)
+        | (Attribute::Vp, Attribute::C) => {
+            let (x, y) = (position(X), position(Y));
+            return (y.0 - x.0).abs() + (y.1 - x.1).abs() == 1;
+        }
+        _ => return false,
+    }
+}
+
+// セルCとその四隅の格子点P
+pub fn corner(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::C, Attribute::P) | (Attribute::P, Attribute::C) => {
+            let (x, y) = (position(X), position(Y));
+            return (y.0 - x.0).abs() == 1 && (y.1 - x.1).abs() == 1;
+        }
+        _ => return false,
+    }
+}
+
+// 辺とその両端: 格子点辺（Hp, Vp）と格子点P, セル辺（Hc, Vc）とセルC
+pub fn endpoint(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::P, Attribute::Hp)
+        | (Attribute::Hp, Attribute::P)
+        | (Attribute::P, Attribute::Vp)
+        | (Attribute::Vp, Attribute::P)
+        | (Attribute::C, Attribute::Hc)
+        | (Attribute::Hc, Attribute::C)
+        | (Attribute::C, Attribute::Vc)
+        | (Attribute::Vc, Attribute::C) => {
+            let (x, y) = (position(X), position(Y));
+            return (y.0 - x.0).abs() + (y.1 - x.1).abs() == 1;
+        }
+        _ => return false,
+    }
+}
+
+// 交差するセル辺と格子点辺: Hc(i, j)とVp(i, j+1), Vc(i, j)とHp(i+1, j)
+pub fn dual(X: &Element, Y: &Element) -> bool {
+    match (&X.attr, &Y.attr) {
+        (Attribute::Hc, Attribute::Vp)
+        | (Attribute::Vp, Attribute::Hc)
+        | (Attribute::Vc, Attribute::Hp)
+        | (Attribute::Hp, Attribute::Vc) => return position(X) == position(Y),
+        _ => return false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -255,4 +628,124 @@ mod tests {
         assert_eq!(D(&vp42, &vp33), false);
         assert_eq!(D(&vp33, &vp42), false);
     }
+
+    #[test]
+    fn test_relation() {
+        let c11 = Element::new(Attribute::C, Coordinate(1, 1));
+        let c12 = Element::new(Attribute::C, Coordinate(1, 2));
+        let c13 = Element::new(Attribute::C, Coordinate(1, 3));
+        let c22 = Element::new(Attribute::C, Coordinate(2, 2));
+        let all: Vec<Structure> = vec![&c11, &c12, &c13, &c22]
+            .into_iter()
+            .map(|c| Structure::Element(c.clone()))
+            .collect();
+        // (1, 2)が黒マス
+        let white: Vec<Structure> = all
+            .iter()
+            .filter(|s| **s != Structure::Element(c12.clone()))
+            .cloned()
+            .collect();
+
+        assert_eq!(H.or(V).holds(&c12, &c22), true);
+        assert_eq!(H.and(V).holds(&c12, &c22), false);
+        assert_eq!(H.not().holds(&c11, &c13), true);
+        let left = |X: &Element, Y: &Element| X.coor.1 < Y.coor.1;
+        assert_eq!(left.holds(&c11, &c13), true);
+        assert_eq!(left.inverse().holds(&c11, &c13), false);
+
+        assert_eq!(H.or(V).closure(&all).holds(&c11, &c13), true);
+        assert_eq!(H.or(V).closure(&white).holds(&c11, &c13), false);
+        assert_eq!(H.or(V).closure(&white).holds(&c11, &c12), true);
+        assert_eq!(H.power(2, &all).holds(&c11, &c13), true);
+        assert_eq!(H.power(2, &all).holds(&c11, &c12), false);
+        assert_eq!(H.power(2, &white).holds(&c11, &c13), false);
+
+        let s11 = Structure::Element(c11.clone());
+        let s22 = Structure::Element(c22.clone());
+        assert_eq!(relationship(&s11, &s22, D), true);
+        assert_eq!(relationship(&s11, &s22, H.or(V)), false);
+        let boxed: Box<dyn Relation> = Box::new(H.or(V).or(D));
+        assert_eq!(related(&s11, &s22, &*boxed), true);
+    }
+
+    #[test]
+    fn test_distance() {
+        let p11 = Element::new(Attribute::P, Coordinate(1, 1));
+        let c11 = Element::new(Attribute::C, Coordinate(1, 1));
+        let c13 = Element::new(Attribute::C, Coordinate(1, 3));
+        let c23 = Element::new(Attribute::C, Coordinate(2, 3));
+        let c32 = Element::new(Attribute::C, Coordinate(3, 2));
+        let c33 = Element::new(Attribute::C, Coordinate(3, 3));
+        let hp11 = Element::new(Attribute::Hp, Coordinate(1, 1));
+        let vp13 = Element::new(Attribute::Vp, Coordinate(1, 3));
+        let hc12 = Element::new(Attribute::Hc, Coordinate(1, 2));
+        let vc12 = Element::new(Attribute::Vc, Coordinate(1, 2));
+        assert_eq!(same_row(&c11, &c13), true);
+        assert_eq!(same_row(&c11, &hc12), true);
+        assert_eq!(same_row(&c11, &vp13), true);
+        assert_eq!(same_row(&p11, &hp11), true);
+        assert_eq!(same_row(&c11, &c11), false);
+        assert_eq!(same_row(&p11, &c11), false);
+        assert_eq!(same_column(&c13, &c33), true);
+        assert_eq!(same_column(&vc12, &c32), true);
+        assert_eq!(same_column(&c13, &c32), false);
+        assert_eq!(left_of(&c11, &c13), true);
+        assert_eq!(left_of(&c13, &c11), false);
+        assert_eq!(left_of(&p11, &c11), true);
+        assert_eq!(right_of(&c13, &c11), true);
+        assert_eq!(above(&c13, &c33), true);
+        assert_eq!(below(&c13, &c33), false);
+        assert_eq!(below(&vc12, &c11), true);
+        assert_eq!(king(&c11, &p11), true);
+        assert_eq!(king(&c23, &c32), true);
+        assert_eq!(king(&c11, &c13), false);
+        assert_eq!(knight(&c11, &c23), true);
+        assert_eq!(knight(&c11, &c32), true);
+        assert_eq!(knight(&c11, &c33), false);
+        assert_eq!(knight(&p11, &c23), false);
+        assert_eq!(within(2).holds(&c11, &c33), true);
+        assert_eq!(within(1).holds(&c11, &c33), false);
+        assert_eq!(within(1).holds(&c11, &c11), false);
+        assert_eq!(manhattan_within(3).holds(&c11, &c23), true);
+        assert_eq!(manhattan_within(3).holds(&c11, &c33), false);
+        assert_eq!(offset(0, 2).holds(&c11, &c13), true);
+        assert_eq!(offset(0, 2).holds(&c13, &c11), false);
+        assert_eq!(offset(0, 1).holds(&vc12, &hp11), false);
+        assert_eq!(same_row.and(left_of).holds(&c11, &c13), true);
+    }
+
+    #[test]
+    fn test_incidence() {
+        let p22 = Element::new(Attribute::P, Coordinate(2, 2));
+        let p33 = Element::new(Attribute::P, Coordinate(3, 3));
+        let c22 = Element::new(Attribute::C, Coordinate(2, 2));
+        let c23 = Element::new(Attribute::C, Coordinate(2, 3));
+        let hp22 = Element::new(Attribute::Hp, Coordinate(2, 2));
+        let hp32 = Element::new(Attribute::Hp, Coordinate(3, 2));
+        let hp42 = Element::new(Attribute::Hp, Coordinate(4, 2));
+        let vp22 = Element::new(Attribute::Vp, Coordinate(2, 2));
+        let vp23 = Element::new(Attribute::Vp, Coordinate(2, 3));
+        let hc22 = Element::new(Attribute::Hc, Coordinate(2, 2));
+        let vc22 = Element::new(Attribute::Vc, Coordinate(2, 2));
+        assert_eq!(borders(&c22, &hp22), true);
+        assert_eq!(borders(&c22, &hp32), true);
+        assert_eq!(borders(&c22, &vp22), true);
+        assert_eq!(borders(&vp23, &c22), true);
+        assert_eq!(borders(&vp23, &c23), true);
+        assert_eq!(borders(&c22, &hp42), false);
+        assert_eq!(borders(&c23, &vp22), false);
+        assert_eq!(corner(&c22, &p22), true);
+        assert_eq!(corner(&p33, &c22), true);
+        assert_eq!(corner(&p33, &c23), true);
+        assert_eq!(corner(&p22, &c23), false);
+        assert_eq!(endpoint(&hp22, &p22), true);
+        assert_eq!(endpoint(&vp22, &p33), false);
+        assert_eq!(endpoint(&hc22, &c23), true);
+        assert_eq!(endpoint(&c22, &vc22), true);
+        assert_eq!(endpoint(&c22, &hp22), false);
+        assert_eq!(dual(&hc22, &vp23), true);
+        assert_eq!(dual(&vc22, &hp32), true);
+        assert_eq!(dual(&hp32, &vc22), true);
+        assert_eq!(dual(&hc22, &vp22), false);
+    }
 }
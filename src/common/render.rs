@@ -0,0 +1,600 @@
+use crate::common::dataclass::{Attribute, BoardSize, Coordinate, Element, Structure};
+use crate::common::serialize::BoardRecord;
+use std::collections::{HashMap, HashSet};
+
+pub struct Render {}
+
+impl Render {
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面を文字のグリッドとして描画する関数
+    // 格子点は+, 値が0でない格子点辺（Ep）は-と|, 細胞辺（Ec）は細胞同士をつなぐ-と|で描画する
+    // 細胞（C）から成る構造体は領域とみなし, 異なる領域の境界を-と|で描画する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面のサイズ
+    // elements: &Vec<Structure> - 値の入った元素列（P, C, Ep, Ecを混ぜてよい）
+    // structures: &Vec<Structure> - 盤面に存在する構造体の列（領域, 線）
+    // shaded: &Vec<Structure> - 黒マスとして#で描画する構造体の列
+    // shade_val: Option<i32> - この値を持つ細胞を#で描画する（black = -1など, 使わないときはNone）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // String - 描画結果（行ごとに改行される）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn ascii(
+        board_size: &BoardSize,
+        elements: &Vec<Structure>,
+        structures: &Vec<Structure>,
+        shaded: &Vec<Structure>,
+        shade_val: Option<i32>,
+    ) -> String {
+        let n = board_size.0;
+        let m = board_size.1;
+        let scene = Scene::new(elements, structures, shaded, shade_val);
+
+        // 細胞の列と格子点の列の表示幅は, それぞれ最も長い値に合わせる
+        let mut width = 1;
+        let mut point_width = 1;
+        for ((attr, _, _), val) in scene.values.iter() {
+            if *attr == Attribute::C {
+                width = std::cmp::max(width, val.to_string().len());
+            } else if *attr == Attribute::P {
+                point_width = std::cmp::max(point_width, val.to_string().len());
+            }
+        }
+
+        let mut lines: Vec<String> = Vec::new();
+        for i in 1..=n + 1 {
+            // 格子点と横の辺の行
+            let mut line = String::new();
+            for j in 1..=m + 1 {
+                match scene.values.get(&key(Attribute::P, Coordinate(i, j))) {
+                    Some(val) => line.push_str(&format!("{:>width$}", val, width = point_width)),
+                    None => line.push_str(&format!("{:>width$}", "+", width = point_width)),
+                }
+                if j == m + 1 {
+                    break;
+                }
+                let coor = Coordinate(i, j);
+                let symbol = if scene.edges.contains_key(&key(Attribute::Hp, coor.clone())) {
+                    "-"
+                } else if i >= 2
+                    && scene
+                        .edges
+                        .contains_key(&key(Attribute::Vc, Coordinate(i - 1, j)))
+                {
+                    "|"
+                } else if scene.is_border(Coordinate(i - 1, j), Coordinate(i, j)) {
+                    "-"
+                } else {
+                    " "
+                };
+                line.push_str(&symbol.repeat(width));
+            }
+            lines.push(line.trim_end().to_string());
+            if i == n + 1 {
+                break;
+            }
+
+            // 縦の辺と細胞の行
+            let mut line = String::new();
+            for j in 1..=m + 1 {
+                let coor = Coordinate(i, j);
+                let symbol = if scene.edges.contains_key(&key(Attribute::Vp, coor.clone())) {
+                    "|"
+                } else if j >= 2
+                    && scene
+                        .edges
+                        .contains_key(&key(Attribute::Hc, Coordinate(i, j - 1)))
+                {
+                    "-"
+                } else if scene.is_border(Coordinate(i, j - 1), Coordinate(i, j)) {
+                    "|"
+                } else {
+                    " "
+                };
+                line.push_str(&format!("{:>width$}", symbol, width = point_width));
+                if j == m + 1 {
+                    break;
+                }
+                if scene.shaded_cells.contains(&coor) {
+                    line.push_str(&"#".repeat(width));
+                } else {
+                    match scene.values.get(&key(Attribute::C, coor)) {
+                        Some(val) => line.push_str(&format!("{:>width$}", val, width = width)),
+                        None => line.push_str(&".".repeat(width)),
+                    }
+                }
+            }
+            lines.push(line.trim_end().to_string());
+        }
+
+        let mut result = lines.join("\n");
+        result.push('\n');
+        return result;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // JSON Linesの記録（serialize.rs）から盤面を描画する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // record: &BoardRecord - 完成盤面の記録
+    // shaded_labels: &[&str] - 黒マスとして描画する構造体のラベル（"black"など）
+    // shade_val: Option<i32> - この値を持つ細胞を#で描画する（使わないときはNone）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // String - 描画結果
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn ascii_record(
+        record: &BoardRecord,
+        shaded_labels: &[&str],
+        shade_val: Option<i32>,
+    ) -> String {
+        let (board_size, elements, structures, shaded) = split_record(record, shaded_labels);
+        return Render::ascii(&board_size, &elements, &structures, &shaded, shade_val);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面を単体のSVGファイルとして描画する関数
+    // 細胞から成る領域は領域ごとに色分けし, 境界を太線で描画する. 線（Ep, Ec）は構造体ごとに色分けする
+    // 細胞の値は細胞の中央に, 領域の値は領域の左上の細胞の隅に描画する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // board_size: &BoardSize - 盤面のサイズ
+    // elements: &Vec<Structure> - 値の入った元素列（P, C, Ep, Ecを混ぜてよい）
+    // structures: &Vec<Structure> - 盤面に存在する構造体の列（領域, 線）
+    // shaded: &Vec<Structure> - 黒マスとして塗りつぶす構造体の列
+    // shade_val: Option<i32> - この値を持つ細胞を塗りつぶす（使わないときはNone）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // String - SVG文書（そのままファイルに書き出せる）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn svg(
+        board_size: &BoardSize,
+        elements: &Vec<Structure>,
+        structures: &Vec<Structure>,
+        shaded: &Vec<Structure>,
+        shade_val: Option<i32>,
+    ) -> String {
+        let n = board_size.0;
+        let m = board_size.1;
+        let scene = Scene::new(elements, structures, shaded, shade_val);
+
+        // 格子点(y, x)のピクセル座標
+        let px = |x: i32| SVG_MARGIN + (x - 1) * SVG_CELL;
+        let py = |y: i32| SVG_MARGIN + (y - 1) * SVG_CELL;
+        let width = 2 * SVG_MARGIN + m * SVG_CELL;
+        let height = 2 * SVG_MARGIN + n * SVG_CELL;
+
+        let mut svg = String::new();
+        svg.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        ));
+        svg.push_str(&format!(
+            "<rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+            width, height
+        ));
+
+        // 細胞の塗りつぶし（領域の色, 黒マス）
+        for i in 1..=n {
+            for j in 1..=m {
+                let coor = Coordinate(i, j);
+                let fill = if scene.shaded_cells.contains(&coor) {
+                    Some("black".to_string())
+                } else {
+                    scene.area_index.get(&coor).map(|&a| area_color(a))
+                };
+                if let Some(fill) = fill {
+                    svg.push_str(&format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                        px(j),
+                        py(i),
+                        SVG_CELL,
+                        SVG_CELL,
+                        fill
+                    ));
+                }
+            }
+        }
+
+        // 補助線と領域の境界
+        for i in 1..=n + 1 {
+            for j in 1..=m {
+                let border = scene.is_border(Coordinate(i - 1, j), Coordinate(i, j));
+                svg.push_str(&svg_line(px(j), py(i), px(j + 1), py(i), border));
+            }
+        }
+        for i in 1..=n {
+            for j in 1..=m + 1 {
+                let border = scene.is_border(Coordinate(i, j - 1), Coordinate(i, j));
+                svg.push_str(&svg_line(px(j), py(i), px(j), py(i + 1), border));
+            }
+        }
+
+        // 線（Epは格子点同士, Ecは細胞の中心同士をつなぐ）
+        let half = SVG_CELL / 2;
+        let mut edges: Vec<(&Key, &Option<usize>)> = scene.edges.iter().collect();
+        edges.sort_by_key(|((attr, coor, layer), _)| {
+            (format!("{:?}", attr), *layer, coor.0, coor.1)
+        });
+        for ((attr, coor, _), index) in edges {
+            let (y, x) = (coor.0, coor.1);
+            let (x1, y1, x2, y2) = match attr {
+                Attribute::Hp => (px(x), py(y), px(x + 1), py(y)),
+                Attribute::Vp => (px(x), py(y), px(x), py(y + 1)),
+                Attribute::Hc => (px(x) + half, py(y) + half, px(x + 1) + half, py(y) + half),
+                Attribute::Vc => (px(x) + half, py(y) + half, px(x) + half, py(y + 1) + half),
+                _ => continue,
+            };
+            let color = match index {
+                Some(i) => line_color(*i),
+                None => "black".to_string(),
+            };
+            svg.push_str(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"5\" stroke-linecap=\"round\"/>\n",
+                x1, y1, x2, y2, color
+            ));
+        }
+
+        // 格子点
+        for i in 1..=n + 1 {
+            for j in 1..=m + 1 {
+                match scene.values.get(&key(Attribute::P, Coordinate(i, j))) {
+                    Some(val) => svg.push_str(&svg_text(px(j), py(i), SVG_CELL / 3, "black", *val)),
+                    None => svg.push_str(&format!(
+                        "<circle cx=\"{}\" cy=\"{}\" r=\"2\" fill=\"black\"/>\n",
+                        px(j),
+                        py(i)
+                    )),
+                }
+            }
+        }
+
+        // 細胞の値
+        for i in 1..=n {
+            for j in 1..=m {
+                let coor = Coordinate(i, j);
+                if let Some(val) = scene.values.get(&key(Attribute::C, coor.clone())) {
+                    let color = if scene.shaded_cells.contains(&coor) {
+                        "white"
+                    } else {
+                        "black"
+                    };
+                    svg.push_str(&svg_text(
+                        px(j) + half,
+                        py(i) + half,
+                        SVG_CELL / 2,
+                        color,
+                        *val,
+                    ));
+                }
+            }
+        }
+
+        // 領域の値は左上の細胞の隅に小さく描画する
+        for structure in structures.iter() {
+            if let Structure::Composition(ref composition) = structure {
+                let corner = leaf_elements(structure)
+                    .into_iter()
+                    .filter(|(attr, _, _)| *attr == Attribute::C)
+                    .map(|(_, coor, _)| (coor.0, coor.1))
+                    .min();
+                if let (Some(val), Some((y, x))) = (composition.val, corner) {
+                    svg.push_str(&svg_text(
+                        px(x) + SVG_CELL / 5,
+                        py(y) + SVG_CELL / 5,
+                        SVG_CELL / 4,
+                        "black",
+                        val,
+                    ));
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        return svg;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // JSON Linesの記録（serialize.rs）からSVGを描画する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // record: &BoardRecord - 完成盤面の記録
+    // shaded_labels: &[&str] - 黒マスとして塗りつぶす構造体のラベル（"black"など）
+    // shade_val: Option<i32> - この値を持つ細胞を塗りつぶす（使わないときはNone）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // String - SVG文書
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn svg_record(
+        record: &BoardRecord,
+        shaded_labels: &[&str],
+        shade_val: Option<i32>,
+    ) -> String {
+        let (board_size, elements, structures, shaded) = split_record(record, shaded_labels);
+        return Render::svg(&board_size, &elements, &structures, &shaded, shade_val);
+    }
+}
+
+// SVGの細胞の一辺と余白（px）
+const SVG_CELL: i32 = 40;
+const SVG_MARGIN: i32 = 20;
+
+// 元素の識別子（Element::key, 属性, 座標, 層）
+type Key = (Attribute, Coordinate, i32);
+
+// 描画に必要な情報を構造体と元素列から集めたもの
+struct Scene {
+    // 値の入った元素
+    values: HashMap<Key, i32>,
+    // 線として描画する辺と, その辺を含む構造体の番号（値から描画する場合はNone）
+    edges: HashMap<Key, Option<usize>>,
+    // 細胞が属する領域の番号
+    area_index: HashMap<Coordinate, usize>,
+    // 黒マス
+    shaded_cells: HashSet<Coordinate>,
+}
+
+impl Scene {
+    fn new(
+        elements: &Vec<Structure>,
+        structures: &Vec<Structure>,
+        shaded: &Vec<Structure>,
+        shade_val: Option<i32>,
+    ) -> Self {
+        let mut values: HashMap<Key, i32> = HashMap::new();
+        for structure in elements.iter() {
+            if let Structure::Element(ref e) = structure {
+                if let Some(val) = e.val {
+                    values.insert(e.key(), val);
+                }
+            }
+        }
+
+        let mut edges: HashMap<Key, Option<usize>> = HashMap::new();
+        for (k, val) in values.iter() {
+            if k.0 != Attribute::P && k.0 != Attribute::C && *val != 0 {
+                edges.insert(k.clone(), None);
+            }
+        }
+        let mut area_index: HashMap<Coordinate, usize> = HashMap::new();
+        for (i, structure) in structures.iter().enumerate() {
+            for e in leaf_elements(structure) {
+                match e.0 {
+                    Attribute::C => {
+                        area_index.insert(e.1, i);
+                    }
+                    Attribute::P => {}
+                    _ => {
+                        edges.insert(e, Some(i));
+                    }
+                }
+            }
+        }
+
+        let mut shaded_cells: HashSet<Coordinate> = HashSet::new();
+        for structure in shaded.iter() {
+            for e in leaf_elements(structure) {
+                if e.0 == Attribute::C {
+                    shaded_cells.insert(e.1);
+                }
+            }
+        }
+        if shade_val.is_some() {
+            for ((attr, coor, _), val) in values.iter() {
+                if *attr == Attribute::C && Some(*val) == shade_val {
+                    shaded_cells.insert(coor.clone());
+                }
+            }
+        }
+
+        Scene {
+            values,
+            edges,
+            area_index,
+            shaded_cells,
+        }
+    }
+
+    // 2つの細胞の間が領域の境界であるか（盤面の外は領域に属さないとみなす）
+    fn is_border(&self, a: Coordinate, b: Coordinate) -> bool {
+        let area_a = self.area_index.get(&a);
+        let area_b = self.area_index.get(&b);
+        (area_a.is_some() || area_b.is_some()) && area_a != area_b
+    }
+}
+
+// 記録を描画関数の引数（盤面サイズ, 元素列, 構造体の列, 黒マスの列）に分ける
+fn split_record(
+    record: &BoardRecord,
+    shaded_labels: &[&str],
+) -> (BoardSize, Vec<Structure>, Vec<Structure>, Vec<Structure>) {
+    let mut elements: Vec<Structure> = Vec::new();
+    for element in record.elements.iter() {
+        elements.push(Structure::Element(element.clone()));
+    }
+    let mut structures: Vec<Structure> = Vec::new();
+    let mut shaded: Vec<Structure> = Vec::new();
+    let mut labels: Vec<&str> = Vec::new();
+    for area in record.areas.iter() {
+        if !labels.contains(&area.label.as_str()) {
+            labels.push(area.label.as_str());
+        }
+    }
+    for label in labels.iter() {
+        if shaded_labels.contains(label) {
+            shaded.extend(record.areas_of(label));
+        } else {
+            structures.extend(record.areas_of(label));
+        }
+    }
+    let board_size = BoardSize(record.board_size.0, record.board_size.1);
+    return (board_size, elements, structures, shaded);
+}
+
+// 領域の色, 隣り合う番号の色相が離れるように黄金角ずつずらす
+fn area_color(index: usize) -> String {
+    format!("hsl({}, 70%, 85%)", (index * 137) % 360)
+}
+
+// 線の色, 領域より濃い色にする
+fn line_color(index: usize) -> String {
+    format!("hsl({}, 80%, 40%)", (index * 137) % 360)
+}
+
+fn svg_line(x1: i32, y1: i32, x2: i32, y2: i32, border: bool) -> String {
+    if border {
+        format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-width=\"3\"/>\n",
+            x1, y1, x2, y2
+        )
+    } else {
+        format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"gray\" stroke-width=\"1\" stroke-dasharray=\"2,2\"/>\n",
+            x1, y1, x2, y2
+        )
+    }
+}
+
+fn svg_text(x: i32, y: i32, size: i32, color: &str, val: i32) -> String {
+    format!(
+        "<text x=\"{}\" y=\"{}\" font-size=\"{}\" font-family=\"sans-serif\" fill=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>\n",
+        x, y, size, color, val
+    )
+}
+
+// 平面の盤面の元素の識別子
+fn key(attr: Attribute, coor: Coordinate) -> Key {
+    return Element::new(attr, coor).key();
+}
+
+// 構造体に含まれる元素（入れ子も含む）の識別子を列挙する
+fn leaf_elements(structure: &Structure) -> Vec<Key> {
+    match structure {
+        Structure::Element(ref e) => vec![e.key()],
+        Structure::Composition(ref c) => c.entity.iter().flat_map(leaf_elements).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::dataclass::{Composition, Element};
+    use crate::common::initialize::initialize;
+
+    #[test]
+    fn test_ascii() {
+        let board_size = BoardSize(2, 2);
+        let (_, mut C, _, _) = initialize(&board_size);
+        for (i, cell) in C.iter_mut().enumerate() {
+            if let Structure::Element(ref mut cell_content) = cell {
+                cell_content.val = Some(i as i32 + 1);
+            }
+        }
+        let top = Structure::Composition(Composition::new(C[0..2].to_vec()));
+        let bottom = Structure::Composition(Composition::new(C[2..4].to_vec()));
+        let black = vec![Structure::Element(Element::new(
+            Attribute::C,
+            Coordinate(2, 2),
+        ))];
+        let result = Render::ascii(&board_size, &C, &vec![top, bottom], &black, None);
+        assert_eq!(result, "+-+-+\n|1 2|\n+-+-+\n|3 #|\n+-+-+\n");
+
+        // 層のある元素は平面の同じ座標の元素とは別の元素なので描画しない
+        let mut layered = Element::new(Attribute::C, Coordinate(1, 1));
+        layered.val = Some(5);
+        layered.layer = 1;
+        let result = Render::ascii(
+            &board_size,
+            &vec![Structure::Element(layered)],
+            &vec![],
+            &vec![],
+            None,
+        );
+        assert!(!result.contains('5'));
+    }
+
+    #[test]
+    fn test_ascii_points() {
+        // 格子点の値は格子点の列の幅に, 細胞の値は細胞の列の幅に揃える
+        let board_size = BoardSize(1, 2);
+        let (mut P, mut C, _, _) = initialize(&board_size);
+        if let Structure::Element(ref mut p) = P[0] {
+            p.val = Some(12);
+        }
+        if let Structure::Element(ref mut c) = C[1] {
+            c.val = Some(3);
+        }
+        let mut elements = P[0..1].to_vec();
+        elements.extend(C);
+        let result = Render::ascii(&board_size, &elements, &vec![], &vec![], None);
+        assert_eq!(result, "12  +  +\n  .  3\n +  +  +\n");
+    }
+
+    #[test]
+    fn test_ascii_lines() {
+        // 格子点辺の線は格子点をつなぐ
+        let board_size = BoardSize(1, 2);
+        let (_, _, mut Ep, _) = initialize(&board_size);
+        for ep in Ep.iter_mut() {
+            if let Structure::Element(ref mut ep_content) = ep {
+                ep_content.val = Some(1);
+            }
+        }
+        let result = Render::ascii(&board_size, &Ep, &vec![], &vec![], None);
+        assert_eq!(result, "+-+-+\n|.|.|\n+-+-+\n");
+
+        // 細胞辺の線は細胞をつなぐ
+        let board_size = BoardSize(2, 2);
+        let (_, _, _, mut Ec) = initialize(&board_size);
+        for ec in Ec.iter_mut() {
+            if let Structure::Element(ref mut ec_content) = ec {
+                let horizontal = ec_content.attr == Attribute::Hc;
+                ec_content.val = Some(if horizontal { 1 } else { 0 });
+            }
+        }
+        let result = Render::ascii(&board_size, &Ec, &vec![], &vec![], None);
+        assert_eq!(result, "+ + +\n .-.\n+ + +\n .-.\n+ + +\n");
+    }
+
+    #[test]
+    fn test_ascii_shade_val() {
+        // hitoriやkurounitのように黒マスを値-1で持つ細胞は#で描画する
+        let board_size = BoardSize(1, 3);
+        let (_, mut C, _, _) = initialize(&board_size);
+        for (i, cell) in C.iter_mut().enumerate() {
+            if let Structure::Element(ref mut cell_content) = cell {
+                cell_content.val = Some(if i == 1 { -1 } else { 2 });
+            }
+        }
+        let result = Render::ascii(&board_size, &C, &vec![], &vec![], Some(-1));
+        assert_eq!(result, "+  +  +  +\n  2 ##  2\n+  +  +  +\n");
+        let result = Render::ascii(&board_size, &C, &vec![], &vec![], None);
+        assert_eq!(result, "+  +  +  +\n  2 -1  2\n+  +  +  +\n");
+    }
+
+    #[test]
+    fn test_svg() {
+        let board_size = BoardSize(2, 2);
+        let (_, _, mut Ep, _) = initialize(&board_size);
+        for ep in Ep.iter_mut() {
+            if let Structure::Element(ref mut ep_content) = ep {
+                ep_content.val = Some(1);
+            }
+        }
+        let black = vec![Structure::Element(Element::new(
+            Attribute::C,
+            Coordinate(1, 1),
+        ))];
+        let result = Render::svg(&board_size, &Ep, &vec![], &black, None);
+        assert!(result.starts_with("<?xml"));
+        assert!(result.trim_end().ends_with("</svg>"));
+        assert_eq!(result.matches("stroke-width=\"5\"").count(), Ep.len());
+        assert_eq!(result.matches("fill=\"black\"/>").count(), 1 + 9);
+    }
+}
@@ -0,0 +1,665 @@
+use crate::common::dataclass::{
+    Attribute, BoardSize, Composition, Coordinate, Coordinate3, Element, Structure,
+};
+use std::fmt::Write;
+
+// 完成盤面1つ分の記録, JSON Linesの1行に対応する
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardRecord {
+    pub rule: String,
+    pub board_size: (i32, i32),
+    pub elements: Vec<Element>,
+    pub areas: Vec<AreaRecord>,
+}
+
+// 盤面に存在する構造体（領域, 線など）1つ分の記録
+#[derive(Clone, Debug, PartialEq)]
+pub struct AreaRecord {
+    pub label: String,
+    pub val: Option<i32>,
+    pub elements: Vec<Element>,
+}
+
+// 出力される属性の順番, 元素の値はこの順に属性ごとにまとめて出力される
+const ATTRIBUTES: [Attribute; 25] = [
+    Attribute::P,
+    Attribute::C,
+    Attribute::Hp,
+    Attribute::Vp,
+    Attribute::Hc,
+    Attribute::Vc,
+    Attribute::Xp,
+    Attribute::Xc,
+    Attribute::Xep,
+    Attribute::Xec,
+    Attribute::Gp,
+    Attribute::Gc,
+    Attribute::Gep,
+    Attribute::Gec,
+    Attribute::P3,
+    Attribute::C3,
+    Attribute::Fl,
+    Attribute::Fr,
+    Attribute::Fc,
+    Attribute::Ds,
+    Attribute::Db,
+    Attribute::Tnw,
+    Attribute::Tne,
+    Attribute::Tsw,
+    Attribute::Tse,
+];
+
+impl BoardRecord {
+    // コンストラクタ, 元素と構造体は空
+    pub fn new(rule: &str, board_size: &BoardSize) -> Self {
+        BoardRecord {
+            rule: rule.to_string(),
+            board_size: (board_size.0, board_size.1),
+            elements: Vec::new(),
+            areas: Vec::new(),
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 元素列（independent_Cなど）の値を記録に追加する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // elements: &Vec<Structure> - 元素列
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn add_elements(&mut self, elements: &Vec<Structure>) {
+        for structure in elements.iter() {
+            if let Structure::Element(ref element) = structure {
+                self.elements.push(element.clone());
+            }
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面に存在する構造体の列（power_Aなど）を, ラベルを付けて記録に追加する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // label: &str - 構造体の種類を表すラベル（"black", "room", "line"など）
+    // areas: &Vec<Structure> - 構造体の列
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn add_areas(&mut self, label: &str, areas: &Vec<Structure>) {
+        for area in areas.iter() {
+            if let Structure::Composition(ref area_content) = area {
+                // 入れ子の構造体に含まれる元素も記録する
+                // 構造体に含まれる元素の値は記録しない（値はelementsに記録する）
+                let mut elements: Vec<Element> = Vec::new();
+                for element in area.elements() {
+                    let mut bare = element.clone();
+                    bare.val = None;
+                    elements.push(bare);
+                }
+                self.areas.push(AreaRecord {
+                    label: label.to_string(),
+                    val: area_content.val,
+                    elements,
+                });
+            }
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // ラベルが一致する構造体をCompositionとして取り出す関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // label: &str - 取り出したい構造体のラベル
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<Structure> - 構造体の列
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn areas_of(&self, label: &str) -> Vec<Structure> {
+        self.areas
+            .iter()
+            .filter(|area| area.label == label)
+            .map(|area| {
+                let mut composition = Composition::new(
+                    area.elements
+                        .iter()
+                        .map(|element| Structure::Element(element.clone()))
+                        .collect(),
+                );
+                composition.val = area.val;
+                Structure::Composition(composition)
+            })
+            .collect()
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 記録に含まれる元素のうち, 指定した属性のものを取り出す関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // attr: &Attribute - 取り出したい元素の属性
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<Structure> - 元素列
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn elements_of(&self, attr: &Attribute) -> Vec<Structure> {
+        self.elements
+            .iter()
+            .filter(|element| &element.attr == attr)
+            .map(|element| Structure::Element(element.clone()))
+            .collect()
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 記録をJSON Linesの1行（改行なし）に変換する関数
+    // {"rule":..,"board_size":[n,m],"elements":{"c":[[y,x,val],..],..},"areas":[{"label":..,"val":..,"elements":[["c",y,x],..]},..]}
+    // 立体の盤面の元素は層を先頭に付けて[l,y,x,val], ["c3",l,y,x]と出力する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // String - JSON文字列
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn to_json_line(&self) -> String {
+        let mut line = String::new();
+        write!(line, "{{\"rule\":{},", json_string(&self.rule)).unwrap();
+        write!(
+            line,
+            "\"board_size\":[{},{}],",
+            self.board_size.0, self.board_size.1
+        )
+        .unwrap();
+
+        line.push_str("\"elements\":{");
+        let mut first_attr = true;
+        for attr in ATTRIBUTES.iter() {
+            let elements: Vec<&Element> =
+                self.elements.iter().filter(|e| &e.attr == attr).collect();
+            if elements.is_empty() {
+                continue;
+            }
+            if !first_attr {
+                line.push(',');
+            }
+            first_attr = false;
+            write!(line, "\"{:?}\":[", attr).unwrap();
+            for (i, element) in elements.iter().enumerate() {
+                if i != 0 {
+                    line.push(',');
+                }
+                if element.is_3d() {
+                    write!(line, "[{},", element.layer).unwrap();
+                } else {
+                    line.push('[');
+                }
+                write!(
+                    line,
+                    "{},{},{}]",
+                    element.coor.0,
+                    element.coor.1,
+                    json_value(element.val)
+                )
+                .unwrap();
+            }
+            line.push(']');
+        }
+        line.push_str("},");
+
+        line.push_str("\"areas\":[");
+        for (i, area) in self.areas.iter().enumerate() {
+            if i != 0 {
+                line.push(',');
+            }
+            write!(
+                line,
+                "{{\"label\":{},\"val\":{},\"elements\":[",
+                json_string(&area.label),
+                json_value(area.val)
+            )
+            .unwrap();
+            for (j, element) in area.elements.iter().enumerate() {
+                if j != 0 {
+                    line.push(',');
+                }
+                write!(line, "[\"{:?}\",", element.attr).unwrap();
+                if element.is_3d() {
+                    write!(line, "{},", element.layer).unwrap();
+                }
+                write!(line, "{},{}]", element.coor.0, element.coor.1).unwrap();
+            }
+            line.push_str("]}");
+        }
+        line.push_str("]}");
+
+        return line;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // to_json_lineで出力された1行を記録に戻す関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // line: &str - JSON文字列
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<BoardRecord, String> - 解析に失敗した場合はエラーメッセージ
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn from_json_line(line: &str) -> Result<BoardRecord, String> {
+        let mut parser = JsonParser {
+            chars: line.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.chars.len() {
+            return Err(format!("unexpected trailing characters at {}", parser.pos));
+        }
+
+        let rule = value.get("rule")?.as_str()?.to_string();
+        let size = value.get("board_size")?.as_array()?;
+        if size.len() != 2 {
+            return Err("board_size must have 2 entries".to_string());
+        }
+        let board_size = (size[0].as_i32()?, size[1].as_i32()?);
+
+        let mut elements: Vec<Element> = Vec::new();
+        if let JsonValue::Object(ref kinds) = value.get("elements")? {
+            for (key, list) in kinds.iter() {
+                let attr = parse_attribute(key)?;
+                for item in list.as_array()?.iter() {
+                    let item = item.as_array()?;
+                    let mut element = match item.len() {
+                        3 => Element::new(
+                            attr.clone(),
+                            Coordinate(item[0].as_i32()?, item[1].as_i32()?),
+                        ),
+                        4 => Element::new3(
+                            attr.clone(),
+                            Coordinate3(item[0].as_i32()?, item[1].as_i32()?, item[2].as_i32()?),
+                        ),
+                        _ => {
+                            return Err("element must be [y, x, val] or [l, y, x, val]".to_string())
+                        }
+                    };
+                    element.val = item[item.len() - 1].as_option_i32()?;
+                    elements.push(element);
+                }
+            }
+        } else {
+            return Err("elements must be an object".to_string());
+        }
+
+        let mut areas: Vec<AreaRecord> = Vec::new();
+        for area in value.get("areas")?.as_array()?.iter() {
+            let mut area_elements: Vec<Element> = Vec::new();
+            for item in area.get("elements")?.as_array()?.iter() {
+                let item = item.as_array()?;
+                if item.len() != 3 && item.len() != 4 {
+                    return Err("area element must be [attr, y, x] or [attr, l, y, x]".to_string());
+                }
+                let attr = parse_attribute(item[0].as_str()?)?;
+                match item.len() {
+                    3 => area_elements.push(Element::new(
+                        attr,
+                        Coordinate(item[1].as_i32()?, item[2].as_i32()?),
+                    )),
+                    4 => area_elements.push(Element::new3(
+                        attr,
+                        Coordinate3(item[1].as_i32()?, item[2].as_i32()?, item[3].as_i32()?),
+                    )),
+                    _ => {
+                        return Err(
+                            "area element must be [attr, y, x] or [attr, l, y, x]".to_string()
+                        )
+                    }
+                }
+            }
+            areas.push(AreaRecord {
+                label: area.get("label")?.as_str()?.to_string(),
+                val: area.get("val")?.as_option_i32()?,
+                elements: area_elements,
+            });
+        }
+
+        return Ok(BoardRecord {
+            rule,
+            board_size,
+            elements,
+            areas,
+        });
+    }
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// [overview]
+// JSON Lines形式のテキスト（data/*.txt）を記録の列に変換する関数, 空行は読み飛ばす
+// ---------------------------------------------------------------------------------------------------------------------
+// [params]
+// text: &str - ファイルの内容
+// ---------------------------------------------------------------------------------------------------------------------
+// [return]
+// Result<Vec<BoardRecord>, String> - 解析に失敗した場合は行番号付きのエラーメッセージ
+// ---------------------------------------------------------------------------------------------------------------------
+pub fn parse_json_lines(text: &str) -> Result<Vec<BoardRecord>, String> {
+    let mut records: Vec<BoardRecord> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record =
+            BoardRecord::from_json_line(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        records.push(record);
+    }
+    return Ok(records);
+}
+
+// 属性の文字列表現（Debugと同じ）から属性を復元する
+fn parse_attribute(s: &str) -> Result<Attribute, String> {
+    match s {
+        "p" => Ok(Attribute::P),
+        "c" => Ok(Attribute::C),
+        "hp" => Ok(Attribute::Hp),
+        "vp" => Ok(Attribute::Vp),
+        "hc" => Ok(Attribute::Hc),
+        "vc" => Ok(Attribute::Vc),
+        "xp" => Ok(Attribute::Xp),
+        "xc" => Ok(Attribute::Xc),
+        "xep" => Ok(Attribute::Xep),
+        "xec" => Ok(Attribute::Xec),
+        "gp" => Ok(Attribute::Gp),
+        "gc" => Ok(Attribute::Gc),
+        "gep" => Ok(Attribute::Gep),
+        "gec" => Ok(Attribute::Gec),
+        "p3" => Ok(Attribute::P3),
+        "c3" => Ok(Attribute::C3),
+        "fl" => Ok(Attribute::Fl),
+        "fr" => Ok(Attribute::Fr),
+        "fc" => Ok(Attribute::Fc),
+        "ds" => Ok(Attribute::Ds),
+        "db" => Ok(Attribute::Db),
+        "tnw" => Ok(Attribute::Tnw),
+        "tne" => Ok(Attribute::Tne),
+        "tsw" => Ok(Attribute::Tsw),
+        "tse" => Ok(Attribute::Tse),
+        _ => Err(format!("unknown attribute: {}", s)),
+    }
+}
+
+fn json_value(val: Option<i32>) -> String {
+    match val {
+        Some(v) => v.to_string(),
+        None => "null".to_string(),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut result = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(result, "\\u{:04x}", c as u32).unwrap(),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    return result;
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// ↓ 記録を読むための最小限のJSONパーサ
+
+enum JsonValue {
+    Null,
+    Number(i64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    fn get(&self, key: &str) -> Result<&JsonValue, String> {
+        if let JsonValue::Object(ref entries) = self {
+            for (k, v) in entries.iter() {
+                if k == key {
+                    return Ok(v);
+                }
+            }
+            return Err(format!("missing key: {}", key));
+        }
+        Err(format!("expected object for key: {}", key))
+    }
+
+    fn as_array(&self) -> Result<&Vec<JsonValue>, String> {
+        match self {
+            JsonValue::Array(ref items) => Ok(items),
+            _ => Err("expected array".to_string()),
+        }
+    }
+
+    fn as_str(&self) -> Result<&str, String> {
+        match self {
+            JsonValue::String(ref s) => Ok(s),
+            _ => Err("expected string".to_string()),
+        }
+    }
+
+    fn as_i32(&self) -> Result<i32, String> {
+        match self {
+            JsonValue::Number(v) => {
+                i32::try_from(*v).map_err(|_| "number out of range".to_string())
+            }
+            _ => Err("expected number".to_string()),
+        }
+    }
+
+    fn as_option_i32(&self) -> Result<Option<i32>, String> {
+        match self {
+            JsonValue::Null => Ok(None),
+            _ => self.as_i32().map(Some),
+        }
+    }
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            return Ok(());
+        }
+        Err(format!("expected '{}' at {}", c, self.pos))
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos) {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(JsonValue::String),
+            Some('n') => {
+                let literal: String = self.chars[self.pos..].iter().take(4).collect();
+                if literal != "null" {
+                    return Err(format!("unexpected literal at {}", self.pos));
+                }
+                self.pos += 4;
+                Ok(JsonValue::Null)
+            }
+            Some(c) if *c == '-' || c.is_ascii_digit() => self.parse_number(),
+            _ => Err(format!("unexpected character at {}", self.pos)),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, String> {
+        self.expect('{')?;
+        let mut entries: Vec<(String, JsonValue)> = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            entries.push((key, value));
+            self.skip_whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+                _ => return Err(format!("expected ',' or '}}' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, String> {
+        self.expect('[')?;
+        let mut items: Vec<JsonValue> = Vec::new();
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(format!("expected ',' or ']' at {}", self.pos)),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut result = String::new();
+        while let Some(&c) = self.chars.get(self.pos) {
+            self.pos += 1;
+            match c {
+                '"' => return Ok(result),
+                '\\' => {
+                    let escaped = self.chars.get(self.pos).cloned();
+                    self.pos += 1;
+                    match escaped {
+                        Some('"') => result.push('"'),
+                        Some('\\') => result.push('\\'),
+                        Some('/') => result.push('/'),
+                        Some('n') => result.push('\n'),
+                        Some('t') => result.push('\t'),
+                        Some('u') => {
+                            let hex: String = self.chars[self.pos..].iter().take(4).collect();
+                            let code = u32::from_str_radix(&hex, 16)
+                                .map_err(|_| format!("invalid escape at {}", self.pos))?;
+                            result.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                            self.pos += 4;
+                        }
+                        _ => return Err(format!("invalid escape at {}", self.pos)),
+                    }
+                }
+                c => result.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, String> {
+        let start = self.pos;
+        if self.chars.get(self.pos) == Some(&'-') {
+            self.pos += 1;
+        }
+        while self.pos < self.chars.len() && self.chars[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        let literal: String = self.chars[start..self.pos].iter().collect();
+        literal
+            .parse::<i64>()
+            .map(JsonValue::Number)
+            .map_err(|_| format!("invalid number at {}", start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::cube::initialize3;
+    use crate::common::dataclass::BoardSize3;
+    use crate::common::initialize::initialize;
+
+    #[test]
+    fn test_round_trip() {
+        let board_size = BoardSize(2, 2);
+        let (_, mut C, _, _) = initialize(&board_size);
+        for (i, cell) in C.iter_mut().enumerate() {
+            if let Structure::Element(ref mut cell_content) = cell {
+                cell_content.val = if i == 0 { None } else { Some(i as i32) };
+            }
+        }
+        let mut area = Composition::new(C[0..2].to_vec());
+        area.val = Some(2);
+        let areas = vec![Structure::Composition(area)];
+
+        let mut record = BoardRecord::new("test \"rule\"", &board_size);
+        record.add_elements(&C);
+        record.add_areas("black", &areas);
+
+        let line = record.to_json_line();
+        assert!(!line.contains('\n'));
+        let parsed = BoardRecord::from_json_line(&line).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.elements_of(&Attribute::C).len(), 4);
+        assert_eq!(parsed.areas_of("black").len(), 1);
+        assert_eq!(parsed.areas_of("white").len(), 0);
+
+        // 立体の盤面の元素は層も記録される
+        let (_, C3, _) = initialize3(&BoardSize3(2, 1, 1));
+        let mut record = BoardRecord::new("cube", &BoardSize(1, 1));
+        record.add_elements(&C3);
+        record.add_areas("black", &vec![Structure::Composition(Composition::new(C3))]);
+        let parsed = BoardRecord::from_json_line(&record.to_json_line()).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.elements[1].layer, 2);
+
+        // 入れ子の構造体の元素も失われない
+        let (_, C, _, _) = initialize(&BoardSize(2, 2));
+        let inner = Structure::Composition(Composition::new(C[0..2].to_vec()));
+        let outer = Composition::new(vec![inner, C[2].clone()]);
+        let mut record = BoardRecord::new("nested", &BoardSize(2, 2));
+        record.add_areas("room", &vec![Structure::Composition(outer)]);
+        assert_eq!(record.areas[0].elements.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_json_lines() {
+        let text = "{\"rule\":\"a\",\"board_size\":[1,1],\"elements\":{\"c\":[[1,1,3]]},\"areas\":[]}\n\n{\"rule\":\"b\",\"board_size\":[1,1],\"elements\":{},\"areas\":[]}\n";
+        let records = parse_json_lines(text).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].elements[0].val, Some(3));
+        assert!(parse_json_lines("{\"rule\":1}").is_err());
+
+        // 長さの合わない領域の元素はpanicせずにErrになる
+        for item in ["[]", "[\"c\",1]", "[\"c\",1,1,1,1]"] {
+            let line = format!(
+                "{{\"rule\":\"a\",\"board_size\":[1,1],\"elements\":{{}},\"areas\":[{{\"label\":\"room\",\"val\":null,\"elements\":[{}]}}]}}",
+                item
+            );
+            assert!(BoardRecord::from_json_line(&line).is_err());
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs;
+
+const HEADER: &str = "puzzle_check shard v1";
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 分割された列挙（シャード）の結果の要約
+// シャードiは通し番号の区間[total * i / count, total * (i + 1) / count)を担当する
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShardSummary {
+    pub rule: String,
+    pub seed: u64,
+    pub total: u64,
+    pub index: u64,
+    pub count: u64,
+    pub accepted: u64,
+}
+
+impl ShardSummary {
+    // コンストラクタ
+    pub fn new(rule: &str, seed: u64, total: u64, index: u64, count: u64, accepted: u64) -> Self {
+        ShardSummary {
+            rule: rule.to_string(),
+            seed,
+            total,
+            index,
+            count,
+            accepted,
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // シャードが担当する通し番号の区間を計算する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // total: u64 - 探索空間全体の大きさ
+    // index: u64 - シャードの番号, 0 <= index < count
+    // count: u64 - シャードの個数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // (u64, u64) - 区間[start, end)
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn range(total: u64, index: u64, count: u64) -> (u64, u64) {
+        let bound = |i: u64| (total as u128 * i as u128 / count as u128) as u64;
+        return (bound(index), bound(index + 1));
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // シャードの引数"i/k"を読み込む関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<(u64, u64), String> - (i, k), 0 <= i < kでない場合はエラーメッセージ
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn parse_spec(spec: &str) -> Result<(u64, u64), String> {
+        let invalid = || format!("invalid shard '{}', expected i/k with 0 <= i < k", spec);
+        let (index, count) = spec.split_once('/').ok_or_else(invalid)?;
+        let index: u64 = index.parse().map_err(|_| invalid())?;
+        let count: u64 = count.parse().map_err(|_| invalid())?;
+        if index >= count {
+            return Err(invalid());
+        }
+        return Ok((index, count));
+    }
+
+    // ファイル名に使うシャードの名前
+    pub fn name(rule: &str, index: u64, count: u64) -> String {
+        return format!("{}.shard-{}-of-{}", rule, index, count);
+    }
+
+    // 要約をファイルに保存する
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        let text = format!(
+            "{}\nrule {}\nseed {}\ntotal {}\nshard {} {}\naccepted {}\n",
+            HEADER, self.rule, self.seed, self.total, self.index, self.count, self.accepted
+        );
+        return fs::write(path, text);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 保存された要約を読み込む関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<ShardSummary, String> - 読み込みに失敗した場合はエラーメッセージ
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn load(path: &str) -> Result<ShardSummary, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(format!("{}: not a shard summary", path));
+        }
+        let mut summary = ShardSummary::new("", 0, 0, 0, 0, 0);
+        for line in lines {
+            let (key, value) = match line.split_once(' ') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            let parse = |s: &str| -> Result<u64, String> {
+                s.parse::<u64>()
+                    .map_err(|_| format!("{}: invalid number in '{}'", path, line))
+            };
+            match key {
+                "rule" => summary.rule = value.to_string(),
+                "seed" => summary.seed = parse(value)?,
+                "total" => summary.total = parse(value)?,
+                "shard" => {
+                    let (index, count) = value
+                        .split_once(' ')
+                        .ok_or(format!("{}: invalid shard '{}'", path, line))?;
+                    summary.index = parse(index)?;
+                    summary.count = parse(count)?;
+                }
+                "accepted" => summary.accepted = parse(value)?,
+                _ => return Err(format!("{}: unknown entry '{}'", path, key)),
+            }
+        }
+        return Ok(summary);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // シャードの要約を統合する関数
+    // 全てのシャードが同じルール・シード・探索空間で, 番号0..countが1つずつ揃っていることを確認する
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // summaries: &Vec<ShardSummary> - 各シャードの要約
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Result<u64, String> - 見つかった盤面の総数, 揃っていない場合はエラーメッセージ
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn merge(summaries: &Vec<ShardSummary>) -> Result<u64, String> {
+        let first = summaries.first().ok_or("no shard to merge")?;
+        let mut seen = vec![false; first.count as usize];
+        let mut accepted = 0;
+        for summary in summaries.iter() {
+            if summary.rule != first.rule
+                || summary.seed != first.seed
+                || summary.total != first.total
+                || summary.count != first.count
+            {
+                return Err(format!(
+                    "shard {}/{} was run with different settings than shard {}/{}",
+                    summary.index, summary.count, first.index, first.count
+                ));
+            }
+            if summary.index >= summary.count {
+                return Err(format!(
+                    "shard {}/{} is out of range",
+                    summary.index, summary.count
+                ));
+            }
+            if seen[summary.index as usize] {
+                return Err(format!(
+                    "shard {}/{} appears twice",
+                    summary.index, summary.count
+                ));
+            }
+            seen[summary.index as usize] = true;
+            accepted += summary.accepted;
+        }
+        if let Some(missing) = seen.iter().position(|&s| !s) {
+            return Err(format!("shard {}/{} is missing", missing, first.count));
+        }
+        return Ok(accepted);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let total = 10;
+        let mut covered = 0;
+        for i in 0..3 {
+            let (start, end) = ShardSummary::range(total, i, 3);
+            assert_eq!(start, covered);
+            covered = end;
+        }
+        assert_eq!(covered, total);
+
+        let summaries = vec![
+            ShardSummary::new("hitori", 1, total, 1, 2, 3),
+            ShardSummary::new("hitori", 1, total, 0, 2, 4),
+        ];
+        assert_eq!(ShardSummary::merge(&summaries), Ok(7));
+        assert!(ShardSummary::merge(&summaries[..1].to_vec()).is_err());
+        let broken = vec![ShardSummary::new("hitori", 1, total, 2, 2, 3)];
+        assert!(ShardSummary::merge(&broken).is_err());
+        assert_eq!(ShardSummary::parse_spec("1/2"), Ok((1, 2)));
+        assert!(ShardSummary::parse_spec("2/2").is_err());
+    }
+}
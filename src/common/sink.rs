@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 出力先
+// Stdout: 標準出力
+// File: ファイル（上書き）
+// Memory: メモリ上の列（テスト用）
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub enum SinkBackend {
+    Stdout,
+    File(String),
+    Memory(Arc<Mutex<Vec<String>>>),
+}
+
+enum Message {
+    Record(u64, String),
+    Watermark(u64),
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 盤面の出力を1つのスレッドにまとめる出力先
+// 盤面は1行ずつ書き込まれるので, 複数のスレッドから送っても行が混ざらない
+// orderedの場合は通し番号の順に並べ替えて出力する（watermarkより前の番号が全て揃ってから書き込む）
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct Sink {
+    sender: Sender<Message>,
+    handle: JoinHandle<io::Result<u64>>,
+}
+
+impl Sink {
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 出力用のスレッドを起動する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // backend: SinkBackend - 出力先
+    // ordered: bool - 通し番号の順に出力するか
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // io::Result<Sink> - ファイルを作成できなかった場合はエラー
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn new(backend: SinkBackend, ordered: bool) -> io::Result<Sink> {
+        let mut writer: Box<dyn FnMut(&str) -> io::Result<()> + Send> = match backend {
+            SinkBackend::Stdout => {
+                let mut out = BufWriter::new(io::stdout());
+                Box::new(move |line: &str| {
+                    writeln!(out, "{}", line)?;
+                    out.flush()
+                })
+            }
+            SinkBackend::File(path) => {
+                let mut out = BufWriter::new(fs::File::create(path)?);
+                Box::new(move |line: &str| {
+                    writeln!(out, "{}", line)?;
+                    out.flush()
+                })
+            }
+            SinkBackend::Memory(lines) => Box::new(move |line: &str| {
+                lines.lock().unwrap().push(line.to_string());
+                Ok(())
+            }),
+        };
+
+        let (sender, receiver) = channel::<Message>();
+        let handle = thread::spawn(move || -> io::Result<u64> {
+            let mut written = 0;
+            let mut pending: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+            for message in receiver {
+                match message {
+                    Message::Record(index, line) => {
+                        if ordered {
+                            pending.entry(index).or_default().push(line);
+                        } else {
+                            writer(&line)?;
+                            written += 1;
+                        }
+                    }
+                    Message::Watermark(upto) => {
+                        let rest = pending.split_off(&upto);
+                        for (_, lines) in std::mem::replace(&mut pending, rest) {
+                            for line in lines {
+                                writer(&line)?;
+                                written += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            for (_, lines) in pending {
+                for line in lines {
+                    writer(&line)?;
+                    written += 1;
+                }
+            }
+            return Ok(written);
+        });
+        return Ok(Sink { sender, handle });
+    }
+
+    // 通し番号indexの盤面を送る
+    pub fn send(&self, index: u64, line: String) {
+        self.sender
+            .send(Message::Record(index, line))
+            .expect("sink thread has stopped");
+    }
+
+    // 番号upto未満の盤面が全て送られたことを知らせる, orderedの場合はここまで書き込まれる
+    pub fn watermark(&self, upto: u64) {
+        self.sender
+            .send(Message::Watermark(upto))
+            .expect("sink thread has stopped");
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 残りの盤面を全て書き込んでスレッドを終了する関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // io::Result<u64> - 書き込んだ盤面の個数
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn finish(self) -> io::Result<u64> {
+        drop(self.sender);
+        return self.handle.join().expect("sink thread panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ordered_sink() {
+        let lines = Arc::new(Mutex::new(vec![]));
+        let sink = Sink::new(SinkBackend::Memory(lines.clone()), true).unwrap();
+        sink.send(5, "five".to_string());
+        sink.send(2, "two".to_string());
+        sink.send(1, "one".to_string());
+        sink.watermark(3);
+        sink.send(4, "four".to_string());
+        assert_eq!(sink.finish().unwrap(), 4);
+        assert_eq!(*lines.lock().unwrap(), vec!["one", "two", "four", "five"]);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::common::dataclass::{Attribute, BoardSize, Coordinate, Element};
+use crate::common::relationship::{Relation, Relationship};
+
+// ---------------------------------------------------------------------------------------------------------------------
+// 盤面の位相（端どうしの繋がり方）
+// 端を越えた先の元素は盤面の反対側の元素と同じものとして扱う
+// 元素の座標は盤面の中の代表（列は1..=m, Torusでは行も1..=n）で持ち, 隣接関係は一周した先の座標（像）とも比べる
+// ---------------------------------------------------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Topology {
+    // 長方形（端は繋がらない）
+    Plane,
+    // 左右の端が繋がる（横に一周する円筒）
+    Cylinder,
+    // 左右の端と上下の端が繋がる（トーラス）
+    Torus,
+    // 左右の端が上下を反転して繋がる（メビウスの帯）
+    Mobius,
+}
+
+impl Topology {
+    // 左右の端が繋がるか
+    pub fn wraps_columns(&self) -> bool {
+        return *self != Topology::Plane;
+    }
+
+    // 上下の端が繋がるか
+    pub fn wraps_rows(&self) -> bool {
+        return *self == Topology::Torus;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面を一周した先での, 同じ元素の別の座標（像）を求める関数
+    // メビウスの帯では, 2倍の座標で行iを2n+2-iに移す反転と列の平行移動を組み合わせる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // element: &Element - 元素
+    // board_size: &BoardSize - 盤面サイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<Element> - 像の列（元の座標は含まない）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn images(&self, element: &Element, board_size: &BoardSize) -> Vec<Element> {
+        let (n, m) = (board_size.0, board_size.1);
+        let shift = |e: &Element, di: i32, dj: i32| {
+            let mut image = e.clone();
+            image.coor = Coordinate(e.coor.0 + di, e.coor.1 + dj);
+            return image;
+        };
+        match self {
+            Topology::Plane => return vec![],
+            Topology::Cylinder => return vec![shift(element, 0, m), shift(element, 0, -m)],
+            Topology::Torus => {
+                let mut images = vec![];
+                for di in [-n, 0, n] {
+                    for dj in [-m, 0, m] {
+                        if (di, dj) != (0, 0) {
+                            images.push(shift(element, di, dj));
+                        }
+                    }
+                }
+                return images;
+            }
+            Topology::Mobius => {
+                let reflected = Topology::reflect(element, n);
+                return vec![shift(&reflected, 0, m), shift(&reflected, 0, -m)];
+            }
+        }
+    }
+
+    // 元素の行を上下に反転する（2倍の座標で行iを2n+2-iに移す）
+    fn reflect(element: &Element, n: i32) -> Element {
+        // 2倍の座標での行は2i+a
+        let a = match element.attr {
+            Attribute::P | Attribute::Hp => -1,
+            Attribute::C | Attribute::Vp | Attribute::Hc => 0,
+            Attribute::Vc => 1,
+            _ => panic!("位相は正方形の盤面の元素にだけ使えます！"),
+        };
+        let mut reflected = element.clone();
+        reflected.coor = Coordinate(n + 1 - element.coor.0 - a, element.coor.1);
+        return reflected;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面の中の代表の座標の範囲を求める関数（initialize_with_topologyが作る元素の行数と列数）
+    // 端が繋がる向きでは, 格子点は最後の行・列が最初の行・列と重なり, セル辺は端を越える辺が加わる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // attr: &Attribute - 元素の属性
+    // board_size: &BoardSize - 盤面サイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // (i32, i32) - (行数, 列数), 座標は1から数える
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn extent(&self, attr: &Attribute, board_size: &BoardSize) -> (i32, i32) {
+        let (n, m) = (board_size.0, board_size.1);
+        let point_n = if self.wraps_rows() { n } else { n + 1 };
+        let point_m = if self.wraps_columns() { m } else { m + 1 };
+        let cell_n = if self.wraps_rows() { n } else { n - 1 };
+        let cell_m = if self.wraps_columns() { m } else { m - 1 };
+        match attr {
+            Attribute::P => return (point_n, point_m),
+            Attribute::C => return (n, m),
+            Attribute::Hp => return (point_n, m),
+            Attribute::Vp => return (n, point_m),
+            Attribute::Hc => return (n, cell_m),
+            Attribute::Vc => return (cell_n, m),
+            _ => panic!("位相は正方形の盤面の元素にだけ使えます！"),
+        }
+    }
+
+    // 元素の盤面の中の代表（元の座標か像のうち, 代表の座標の範囲にあるもの）, どれも範囲の外なら元の元素
+    pub fn canonical(&self, element: &Element, board_size: &BoardSize) -> Element {
+        let (rows, columns) = self.extent(&element.attr, board_size);
+        let inside =
+            |e: &Element| (1..=rows).contains(&e.coor.0) && (1..=columns).contains(&e.coor.1);
+        if inside(element) {
+            return element.clone();
+        }
+        return self
+            .images(element, board_size)
+            .into_iter()
+            .find(|image| inside(image))
+            .unwrap_or_else(|| element.clone());
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 端の繋がりを考慮した辺の両端を求める関数（Element::endpointsの両端を盤面の中の代表に直す）
+    // 端を越える辺Hp(i, m)の端点P(i, m+1)は, 円筒ではP(i, 1)になる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // element: &Element - 辺
+    // board_size: &BoardSize - 盤面サイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Option<(Element, Element)> - 両端, 辺でなければNone
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn endpoints(
+        &self,
+        element: &Element,
+        board_size: &BoardSize,
+    ) -> Option<(Element, Element)> {
+        let (a, b) = element.endpoints()?;
+        if *self == Topology::Plane {
+            return Some((a, b));
+        }
+        return Some((
+            self.canonical(&a, board_size),
+            self.canonical(&b, board_size),
+        ));
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 端の繋がりを考慮した隣接関係を作る関数（H, V, Dなどを包む）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // R: Relationship - 隣接関係
+    // board_size: &BoardSize - 盤面サイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // impl Relation - XとY, またはXとYの像のいずれかがRを満たすとき成り立つ隣接関係
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn relation(&self, R: Relationship, board_size: &BoardSize) -> impl Relation + Copy {
+        let topology = *self;
+        let (n, m) = (board_size.0, board_size.1);
+        return move |X: &Element, Y: &Element| {
+            return R(X, Y)
+                || topology
+                    .images(Y, &BoardSize(n, m))
+                    .iter()
+                    .any(|image| R(X, image));
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::{combine, combine_with_topology};
+    use crate::common::dataclass::Structure;
+    use crate::common::initialize::{initialize, initialize_with_topology};
+    use crate::common::profile::Named;
+    use crate::common::relationship::{endpoint, D, H, M, V};
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn, TopologyCutoffFn};
+    use crate::specific::structure_functions::StructureFn;
+
+    #[test]
+    fn test_topology() {
+        let board_size = BoardSize(3, 4);
+        let c = |i, j| Element::new(Attribute::C, Coordinate(i, j));
+        let p = |i, j| Element::new(Attribute::P, Coordinate(i, j));
+
+        // 左右の端のセルは円筒とトーラスで隣接する
+        assert!(!Topology::Plane
+            .relation(H, &board_size)
+            .holds(&c(2, 4), &c(2, 1)));
+        assert!(Topology::Cylinder
+            .relation(H, &board_size)
+            .holds(&c(2, 4), &c(2, 1)));
+        assert!(!Topology::Cylinder
+            .relation(V, &board_size)
+            .holds(&c(3, 1), &c(1, 1)));
+        assert!(Topology::Torus
+            .relation(V, &board_size)
+            .holds(&c(3, 1), &c(1, 1)));
+        assert!(Topology::Torus
+            .relation(D, &board_size)
+            .holds(&c(3, 4), &c(1, 1)));
+
+        // メビウスの帯では上下が反転して繋がる
+        let mobius = Topology::Mobius.relation(H, &board_size);
+        assert!(mobius.holds(&c(1, 4), &c(3, 1)));
+        assert!(!mobius.holds(&c(1, 4), &c(1, 1)));
+        assert!(mobius.holds(&c(3, 1), &c(1, 4)));
+        // 右端の格子点辺Hp(1, 4)の端点P(1, 5)はP(4, 1)
+        let edge = Element::new(Attribute::Hp, Coordinate(1, 4));
+        assert!(Topology::Mobius
+            .relation(endpoint, &board_size)
+            .holds(&edge, &p(4, 1)));
+        assert!(Topology::Cylinder
+            .relation(endpoint, &board_size)
+            .holds(&edge, &p(1, 1)));
+
+        // 端を越える辺が加わり, 端で重なる格子点と格子点辺は1つになる
+        let (P, C, Ep, Ec) = initialize_with_topology(&board_size, &Topology::Torus);
+        assert_eq!((P.len(), C.len(), Ep.len(), Ec.len()), (12, 12, 24, 24));
+        let (P, _, Ep, Ec) = initialize_with_topology(&board_size, &Topology::Mobius);
+        assert_eq!((P.len(), Ep.len(), Ec.len()), (16, 28, 20));
+        assert_eq!(
+            initialize_with_topology(&board_size, &Topology::Plane),
+            initialize(&board_size)
+        );
+
+        // 1行3列の円筒ではセルが輪になる（連結な部分集合は3 + 3 + 1個）
+        let ring = BoardSize(1, 3);
+        let (_, C, _, _) = initialize_with_topology(&ring, &Topology::Cylinder);
+        let no_cutoff: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+        assert_eq!(combine(vec![H], vec![], &C, &no_cutoff).len(), 6);
+        assert_eq!(
+            combine_with_topology(
+                vec![H],
+                vec![],
+                &C,
+                &no_cutoff,
+                &vec![],
+                &Topology::Cylinder,
+                &ring
+            )
+            .len(),
+            7
+        );
+        let cell = Structure::Element(c(1, 3));
+        assert_eq!(
+            StructureFn::adjacent_with_topology(&cell, &C, &Topology::Cylinder, &ring).len(),
+            2
+        );
+
+        // 円筒の端を越える辺Hp(1, 3)の端点P(1, 4)はP(1, 1)として数える
+        let hp = |i, j| Structure::Element(Element::new(Attribute::Hp, Coordinate(i, j)));
+        let vp = |i, j| Structure::Element(Element::new(Attribute::Vp, Coordinate(i, j)));
+        assert_eq!(
+            Topology::Cylinder.endpoints(hp(1, 3).as_element().unwrap(), &ring),
+            Some((p(1, 3), p(1, 1)))
+        );
+        let (_, _, Ep, _) = initialize_with_topology(&ring, &Topology::Cylinder);
+        let graphs = |cutoff: Named<TopologyCutoffFn>| {
+            combine_with_topology(
+                vec![H, V, D],
+                vec![M],
+                &Ep,
+                &no_cutoff,
+                &vec![cutoff],
+                &Topology::Cylinder,
+                &ring,
+            )
+        };
+        let has = |found: &Vec<Structure>, edges: Vec<Structure>| {
+            found.iter().any(|g| {
+                g.elements().len() == edges.len()
+                    && edges
+                        .iter()
+                        .all(|e| g.elements().contains(&e.as_element().unwrap()))
+            })
+        };
+        // 閉路は三角柱の辺の閉路全て（三角形2, 四角形3, 五角形6, 六角形3）
+        let cycles = graphs(("Cutoff::only_cycle_on", Cutoff::only_cycle_on));
+        assert_eq!(cycles.len(), 14);
+        // 端を越える閉路: 上の輪と, 端を挟むセルC(1, 3)の周り
+        assert!(has(&cycles, vec![hp(1, 1), hp(1, 2), hp(1, 3)]));
+        assert!(has(&cycles, vec![hp(1, 3), hp(2, 3), vp(1, 3), vp(1, 1)]));
+        // 端を越えるパス: P(1, 2)からP(1, 1)を通ってP(2, 1)まで
+        let paths = graphs(("Cutoff::only_line_on", Cutoff::only_line_on));
+        assert!(has(&paths, vec![hp(1, 2), hp(1, 3), vp(1, 1)]));
+        assert!(!has(&paths, vec![hp(1, 1), hp(1, 2), hp(1, 3)]));
+    }
+}
@@ -0,0 +1,813 @@
+use crate::common::dataclass::{Attribute, Composition, Coordinate, Structure};
+use crate::common::operate_structures::OperateStructure;
+use crate::common::relationship::{relationship, Relationship};
+use rand::Rng;
+use std::collections::HashMap;
+
+// ZDDの節点の番号
+pub type ZddId = usize;
+
+// 節点: 変数varを含まない集合族lo, 含む集合族hi
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Node {
+    var: usize,
+    lo: ZddId,
+    hi: ZddId,
+}
+
+// 演算のキャッシュのキー
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Op {
+    Union,
+    Intersection,
+    Difference,
+    Join,
+    DisjointJoin,
+}
+
+// サイクル・パスの構築のメモのキー: (辺の位置, フロンティアの(頂点, 次数, ラベル), 出た端点の個数)
+type CurveKey = (usize, Vec<(usize, u8, u8)>, u8);
+// 連結な集合の構築のメモのキー: (元素の位置, フロンティアの(頂点, ラベル))
+type ConnectedKey = (usize, Vec<(usize, u8)>);
+
+// サイクル・パスの構築で作る形
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Shape {
+    Cycle,
+    Path,
+}
+
+// ---------------------------------------------------------------------------------------------------------------------
+// ゼロサプレス型二分決定グラフ（ZDD）による構造体の族
+// 変数はinitializeで作った元素列の位置で, 集合族の1つの集合が1つの構造体（元素の組）に対応する
+// 「Ep上の全てのサイクル」のような巨大な族も, 元素列の順に共通部分を共有するので小さく表せる
+// 節点は1つのZdd（節点表）に登録し, ZddIdで指す
+// フロンティア法の構築は元素列の順に進むので, 隣接する元素が列の中で近いほど速い
+// ---------------------------------------------------------------------------------------------------------------------
+pub struct Zdd {
+    pub elements: Vec<Structure>,
+    nodes: Vec<Node>,
+    unique: HashMap<Node, ZddId>,
+    cache: HashMap<(Op, ZddId, ZddId), ZddId>,
+}
+
+impl Zdd {
+    // 空の族 {}
+    pub const EMPTY: ZddId = 0;
+    // 空集合だけを含む族 {∅}
+    pub const BASE: ZddId = 1;
+
+    // コンストラクタ, 変数はelementsの位置
+    pub fn new(elements: &Vec<Structure>) -> Self {
+        let terminal = Node {
+            var: usize::MAX,
+            lo: 0,
+            hi: 0,
+        };
+        Zdd {
+            elements: elements.clone(),
+            nodes: vec![terminal, terminal],
+            unique: HashMap::new(),
+            cache: HashMap::new(),
+        }
+    }
+
+    // 節点を作る（hiが空の族なら節点を省く）
+    fn node(&mut self, var: usize, lo: ZddId, hi: ZddId) -> ZddId {
+        if hi == Zdd::EMPTY {
+            return lo;
+        }
+        let node = Node { var, lo, hi };
+        if let Some(&id) = self.unique.get(&node) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.unique.insert(node, id);
+        return id;
+    }
+
+    fn var(&self, f: ZddId) -> usize {
+        return self.nodes[f].var;
+    }
+
+    // 節点の個数（終端を含む）
+    pub fn size(&self) -> usize {
+        return self.nodes.len();
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 族の作成
+
+    // 変数の集合1つだけを含む族
+    pub fn single(&mut self, set: &Vec<usize>) -> ZddId {
+        let mut vars = set.clone();
+        vars.sort();
+        vars.dedup();
+        let mut f = Zdd::BASE;
+        for &var in vars.iter().rev() {
+            f = self.node(var, Zdd::EMPTY, f);
+        }
+        return f;
+    }
+
+    // 構造体に含まれる元素の変数の集合
+    pub fn vars_of(&self, structure: &Structure) -> Vec<usize> {
+        let mut vars: Vec<usize> = self
+            .elements
+            .iter()
+            .enumerate()
+            .filter(|(_, element)| match structure {
+                Structure::Composition(ref composition) => composition
+                    .entity
+                    .iter()
+                    .any(|x| OperateStructure::compare_structures(x, element)),
+                Structure::Element(_) => OperateStructure::compare_structures(structure, element),
+            })
+            .map(|(k, _)| k)
+            .collect();
+        vars.sort();
+        return vars;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 構造体の列（combineの結果など）から族を作る関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // structures: &Vec<Structure> - 構造体の列
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // ZddId - 各構造体に含まれる元素の集合からなる族
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn from_structures(&mut self, structures: &Vec<Structure>) -> ZddId {
+        let mut f = Zdd::EMPTY;
+        for structure in structures.iter() {
+            let vars = self.vars_of(structure);
+            let g = self.single(&vars);
+            f = self.union(f, g);
+        }
+        return f;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 元素の個数がmin以上max以下の集合全体の族を作る関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // min: usize - 最小の個数
+    // max: usize - 最大の個数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // ZddId - 族
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn size_between(&mut self, min: usize, max: usize) -> ZddId {
+        let n = self.elements.len();
+        // below[k]: 変数var..nから, 既にk個選んだ状態で作れる族
+        let mut below: Vec<ZddId> = (0..=n)
+            .map(|k| {
+                if min <= k && k <= max {
+                    Zdd::BASE
+                } else {
+                    Zdd::EMPTY
+                }
+            })
+            .collect();
+        for var in (0..n).rev() {
+            let mut current = vec![Zdd::EMPTY; n + 1];
+            for k in 0..=var {
+                current[k] = self.node(var, below[k], below[k + 1]);
+            }
+            below = current;
+        }
+        return below[0];
+    }
+
+    // 元素の端点（辺の元素の両端の座標と, 頂点の種類）
+    fn endpoints(structure: &Structure) -> ((bool, Coordinate), (bool, Coordinate)) {
+        if let Structure::Element(ref element) = structure {
+            let (a, b) = element
+                .endpoints()
+                .expect("cycles and paths are defined on Ep or Ec");
+            let on_points = matches!(a.attr, Attribute::P | Attribute::Xp | Attribute::Gp);
+            return ((on_points, a.coor), (on_points, b.coor));
+        }
+        panic!("cycles and paths are defined on elements");
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 辺の元素（EpまたはEc）の部分集合のうち, 単一閉曲線（サイクル）であるものの族を作る関数
+    // combine([H, V, D], Ep, [Cutoff::only_cycle])と同じ族になる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // ZddId - サイクルの族
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn cycles(&mut self) -> ZddId {
+        return self.curves(Shape::Cycle);
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 辺の元素（EpまたはEc）の部分集合のうち, 交差のない曲線（パス）であるものの族を作る関数
+    // combine([H, V, D], Ec, [Cutoff::only_line])と同じ族になる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // ZddId - パスの族
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn paths(&mut self) -> ZddId {
+        return self.curves(Shape::Path);
+    }
+
+    // フロンティア法で上から節点を作る
+    // 状態: フロンティアの頂点ごとの(頂点, 次数, 連結成分のラベル)と, 既にフロンティアを出たパスの端点の個数
+    fn curves(&mut self, shape: Shape) -> ZddId {
+        let mut vertex_of: HashMap<(bool, Coordinate), usize> = HashMap::new();
+        let mut edges: Vec<(usize, usize)> = vec![];
+        for structure in self.elements.iter() {
+            let (a, b) = Zdd::endpoints(structure);
+            let n = vertex_of.len();
+            let a = *vertex_of.entry(a).or_insert(n);
+            let n = vertex_of.len();
+            let b = *vertex_of.entry(b).or_insert(n);
+            edges.push((a, b));
+        }
+        // last[v]: 頂点vに接続する最後の辺の位置
+        let mut last = vec![0; vertex_of.len()];
+        for (i, &(a, b)) in edges.iter().enumerate() {
+            last[a] = i;
+            last[b] = i;
+        }
+        let mut memo: HashMap<CurveKey, ZddId> = HashMap::new();
+        return self.curves_from(shape, &edges, &last, 0, vec![], 0, &mut memo);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn curves_from(
+        &mut self,
+        shape: Shape,
+        edges: &Vec<(usize, usize)>,
+        last: &Vec<usize>,
+        i: usize,
+        state: Vec<(usize, u8, u8)>,
+        ends: u8,
+        memo: &mut HashMap<CurveKey, ZddId>,
+    ) -> ZddId {
+        if i == edges.len() {
+            return Zdd::EMPTY;
+        }
+        let key = (i, state, ends);
+        if let Some(&f) = memo.get(&key) {
+            return f;
+        }
+        let (_, state, ends) = key.clone();
+
+        let (a, b) = edges[i];
+        let mut frontier = state.clone();
+        for v in [a, b] {
+            if !frontier.iter().any(|&(w, _, _)| w == v) {
+                frontier.push((v, 0, 0));
+            }
+        }
+        frontier.sort();
+
+        let mut children = [Zdd::EMPTY; 2];
+        for (take, child) in children.iter_mut().enumerate() {
+            let mut next = frontier.clone();
+            let mut ends = ends;
+            if take == 1 {
+                let pa = next.iter().position(|&(w, _, _)| w == a).unwrap();
+                let pb = next.iter().position(|&(w, _, _)| w == b).unwrap();
+                let (la, lb) = (next[pa].2, next[pb].2);
+                next[pa].1 += 1;
+                next[pb].1 += 1;
+                if next[pa].1 > 2 || next[pb].1 > 2 {
+                    continue;
+                }
+                if la != 0 && la == lb {
+                    // 閉曲線になる: サイクルなら他に端が残っていないときだけ完成
+                    if shape == Shape::Cycle && next.iter().all(|&(_, d, _)| d != 1) {
+                        *child = Zdd::BASE;
+                    }
+                    continue;
+                }
+                let label = if la != 0 {
+                    la
+                } else if lb != 0 {
+                    lb
+                } else {
+                    u8::MAX
+                };
+                for x in next.iter_mut() {
+                    if x.2 != 0 && (x.2 == la || x.2 == lb) {
+                        x.2 = label;
+                    }
+                }
+                next[pa].2 = label;
+                next[pb].2 = label;
+            }
+
+            // 最後の辺を処理した頂点はフロンティアから出る
+            let leaving: Vec<(usize, u8, u8)> = next
+                .iter()
+                .copied()
+                .filter(|&(w, _, _)| last[w] == i)
+                .collect();
+            next.retain(|&(w, _, _)| last[w] != i);
+            let mut valid = true;
+            for &(_, degree, _) in leaving.iter() {
+                if degree != 1 {
+                    continue;
+                }
+                if shape == Shape::Cycle {
+                    valid = false;
+                    break;
+                }
+                ends += 1;
+                if ends > 2 {
+                    valid = false;
+                    break;
+                }
+            }
+            if !valid {
+                continue;
+            }
+            // 頂点が全てフロンティアを出た成分はパスとして完成している
+            let completed = leaving
+                .iter()
+                .any(|&(_, d, l)| d != 0 && !next.iter().any(|&(_, _, m)| m == l));
+            if completed {
+                // 完成した成分以外に辺がなければ, 残りの辺は全て含まない
+                if ends == 2 && next.iter().all(|&(_, d, _)| d == 0) {
+                    *child = Zdd::BASE;
+                }
+                continue;
+            }
+
+            // ラベルを振り直す
+            let mut map: HashMap<u8, u8> = HashMap::new();
+            for x in next.iter_mut() {
+                if x.2 != 0 {
+                    let n = map.len() as u8 + 1;
+                    x.2 = *map.entry(x.2).or_insert(n);
+                }
+            }
+            *child = self.curves_from(shape, edges, last, i + 1, next, ends, memo);
+        }
+        let f = self.node(i, children[0], children[1]);
+        memo.insert(key, f);
+        return f;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 元素の空でない部分集合のうち, 隣接関係Rで連結なものの族を作る関数
+    // combine(R, [M], elements, [Cutoff::non_cutoff])と同じ族になる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // R: &Vec<Relationship> - 連結性を判定する隣接関係
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // ZddId - 連結な部分集合の族
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn connected(&mut self, R: &Vec<Relationship>) -> ZddId {
+        let n = self.elements.len();
+        let mut neighbors: Vec<Vec<usize>> = vec![vec![]; n];
+        for (x, adjacent) in neighbors.iter_mut().enumerate() {
+            for y in 0..n {
+                if x != y
+                    && R.iter()
+                        .any(|&r| relationship(&self.elements[x], &self.elements[y], r))
+                {
+                    adjacent.push(y);
+                }
+            }
+        }
+        // last[v]: 頂点vかその隣の頂点のうち最後の位置, それを処理したらフロンティアから出る
+        let last: Vec<usize> = (0..n)
+            .map(|v| neighbors[v].iter().copied().fold(v, std::cmp::max))
+            .collect();
+        let mut memo: HashMap<ConnectedKey, ZddId> = HashMap::new();
+        return self.connected_from(&neighbors, &last, 0, vec![], &mut memo);
+    }
+
+    // 状態: フロンティアの頂点ごとの(頂点, 連結成分のラベル), 含まない頂点のラベルは0
+    fn connected_from(
+        &mut self,
+        neighbors: &Vec<Vec<usize>>,
+        last: &Vec<usize>,
+        i: usize,
+        state: Vec<(usize, u8)>,
+        memo: &mut HashMap<ConnectedKey, ZddId>,
+    ) -> ZddId {
+        if i == neighbors.len() {
+            return Zdd::EMPTY;
+        }
+        let key = (i, state);
+        if let Some(&f) = memo.get(&key) {
+            return f;
+        }
+        let state = key.1.clone();
+
+        let mut children = [Zdd::EMPTY; 2];
+        for (take, child) in children.iter_mut().enumerate() {
+            let mut next = state.clone();
+            if take == 1 {
+                let joined: Vec<u8> = next
+                    .iter()
+                    .filter(|&&(w, l)| l != 0 && neighbors[i].contains(&w))
+                    .map(|&(_, l)| l)
+                    .collect();
+                let label = joined.first().copied().unwrap_or(u8::MAX);
+                for x in next.iter_mut() {
+                    if joined.contains(&x.1) {
+                        x.1 = label;
+                    }
+                }
+                next.push((i, label));
+            } else {
+                next.push((i, 0));
+            }
+
+            let leaving: Vec<(usize, u8)> = next
+                .iter()
+                .copied()
+                .filter(|&(w, _)| last[w] <= i)
+                .collect();
+            next.retain(|&(w, _)| last[w] > i);
+            // フロンティアから消えた成分はもう広がらない
+            let closed: Vec<u8> = leaving
+                .iter()
+                .map(|&(_, l)| l)
+                .filter(|&l| l != 0 && !next.iter().any(|&(_, m)| m == l))
+                .collect();
+            if let Some(&first) = closed.first() {
+                let others =
+                    closed.iter().any(|&l| l != first) || next.iter().any(|&(_, l)| l != 0);
+                if !others {
+                    *child = Zdd::BASE;
+                }
+                continue;
+            }
+
+            let mut map: HashMap<u8, u8> = HashMap::new();
+            for x in next.iter_mut() {
+                if x.1 != 0 {
+                    let n = map.len() as u8 + 1;
+                    x.1 = *map.entry(x.1).or_insert(n);
+                }
+            }
+            *child = self.connected_from(neighbors, last, i + 1, next, memo);
+        }
+        let f = self.node(i, children[0], children[1]);
+        memo.insert(key, f);
+        return f;
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 族の演算
+
+    fn cached<F>(&mut self, op: Op, f: ZddId, g: ZddId, compute: F) -> ZddId
+    where
+        F: FnOnce(&mut Zdd) -> ZddId,
+    {
+        // 交換可能な演算は引数を並べ替えてキャッシュを共有する
+        let (f, g) = match op {
+            Op::Difference => (f, g),
+            _ => (std::cmp::min(f, g), std::cmp::max(f, g)),
+        };
+        if let Some(&h) = self.cache.get(&(op, f, g)) {
+            return h;
+        }
+        let h = compute(self);
+        self.cache.insert((op, f, g), h);
+        return h;
+    }
+
+    // 和集合 f ∪ g
+    pub fn union(&mut self, f: ZddId, g: ZddId) -> ZddId {
+        if f == Zdd::EMPTY || f == g {
+            return g;
+        }
+        if g == Zdd::EMPTY {
+            return f;
+        }
+        return self.cached(Op::Union, f, g, |zdd| {
+            let (vf, vg) = (zdd.var(f), zdd.var(g));
+            let (nf, ng) = (zdd.nodes[f], zdd.nodes[g]);
+            if vf < vg {
+                let lo = zdd.union(nf.lo, g);
+                zdd.node(vf, lo, nf.hi)
+            } else if vf > vg {
+                let lo = zdd.union(f, ng.lo);
+                zdd.node(vg, lo, ng.hi)
+            } else {
+                let lo = zdd.union(nf.lo, ng.lo);
+                let hi = zdd.union(nf.hi, ng.hi);
+                zdd.node(vf, lo, hi)
+            }
+        });
+    }
+
+    // 共通部分 f ∩ g
+    pub fn intersection(&mut self, f: ZddId, g: ZddId) -> ZddId {
+        if f == Zdd::EMPTY || g == Zdd::EMPTY {
+            return Zdd::EMPTY;
+        }
+        if f == g {
+            return f;
+        }
+        return self.cached(Op::Intersection, f, g, |zdd| {
+            let (vf, vg) = (zdd.var(f), zdd.var(g));
+            let (nf, ng) = (zdd.nodes[f], zdd.nodes[g]);
+            if vf < vg {
+                zdd.intersection(nf.lo, g)
+            } else if vf > vg {
+                zdd.intersection(f, ng.lo)
+            } else {
+                let lo = zdd.intersection(nf.lo, ng.lo);
+                let hi = zdd.intersection(nf.hi, ng.hi);
+                zdd.node(vf, lo, hi)
+            }
+        });
+    }
+
+    // 差集合 f \ g
+    pub fn difference(&mut self, f: ZddId, g: ZddId) -> ZddId {
+        if f == Zdd::EMPTY || f == g {
+            return Zdd::EMPTY;
+        }
+        if g == Zdd::EMPTY {
+            return f;
+        }
+        return self.cached(Op::Difference, f, g, |zdd| {
+            let (vf, vg) = (zdd.var(f), zdd.var(g));
+            let (nf, ng) = (zdd.nodes[f], zdd.nodes[g]);
+            if vf < vg {
+                let lo = zdd.difference(nf.lo, g);
+                zdd.node(vf, lo, nf.hi)
+            } else if vf > vg {
+                zdd.difference(f, ng.lo)
+            } else {
+                let lo = zdd.difference(nf.lo, ng.lo);
+                let hi = zdd.difference(nf.hi, ng.hi);
+                zdd.node(vf, lo, hi)
+            }
+        });
+    }
+
+    // 結合 {x ∪ y | x ∈ f, y ∈ g}
+    pub fn join(&mut self, f: ZddId, g: ZddId) -> ZddId {
+        return self.join_with(f, g, Op::Join);
+    }
+
+    // 互いに素な結合 {x ∪ y | x ∈ f, y ∈ g, x ∩ y = ∅}, 重ならない構造体を並べるときに使う
+    pub fn disjoint_join(&mut self, f: ZddId, g: ZddId) -> ZddId {
+        return self.join_with(f, g, Op::DisjointJoin);
+    }
+
+    fn join_with(&mut self, f: ZddId, g: ZddId, op: Op) -> ZddId {
+        if f == Zdd::EMPTY || g == Zdd::EMPTY {
+            return Zdd::EMPTY;
+        }
+        if f == Zdd::BASE {
+            return g;
+        }
+        if g == Zdd::BASE {
+            return f;
+        }
+        return self.cached(op, f, g, |zdd| {
+            let (f, g) = if zdd.var(f) <= zdd.var(g) {
+                (f, g)
+            } else {
+                (g, f)
+            };
+            let (nf, ng) = (zdd.nodes[f], zdd.nodes[g]);
+            if nf.var < ng.var {
+                let lo = zdd.join_with(nf.lo, g, op);
+                let hi = zdd.join_with(nf.hi, g, op);
+                zdd.node(nf.var, lo, hi)
+            } else {
+                let lo = zdd.join_with(nf.lo, ng.lo, op);
+                let hi_lo = zdd.join_with(nf.hi, ng.lo, op);
+                let lo_hi = zdd.join_with(nf.lo, ng.hi, op);
+                let mut hi = zdd.union(hi_lo, lo_hi);
+                if op == Op::Join {
+                    let hi_hi = zdd.join_with(nf.hi, ng.hi, op);
+                    hi = zdd.union(hi, hi_hi);
+                }
+                zdd.node(nf.var, lo, hi)
+            }
+        });
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // fの構造体を重ならないように0個以上並べてできる集合の族を作る関数
+    // 例えばfが部屋の族のとき, 結果に全ての元素の集合が含まれれば盤面を部屋で敷き詰められる
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // f: ZddId - 構造体の族
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // ZddId - 並べてできる集合の族（空集合を含む）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn tilings(&mut self, f: ZddId) -> ZddId {
+        let mut tiled = Zdd::BASE;
+        loop {
+            let extended = self.disjoint_join(tiled, f);
+            let next = self.union(tiled, extended);
+            if next == tiled {
+                return tiled;
+            }
+            tiled = next;
+        }
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // ↓ 族の参照
+
+    // 節点ごとの集合の個数
+    fn counts(&self, f: ZddId) -> HashMap<ZddId, u128> {
+        let mut counts: HashMap<ZddId, u128> = HashMap::new();
+        counts.insert(Zdd::EMPTY, 0);
+        counts.insert(Zdd::BASE, 1);
+        let mut stack = vec![f];
+        while let Some(&g) = stack.last() {
+            if counts.contains_key(&g) {
+                stack.pop();
+                continue;
+            }
+            let node = self.nodes[g];
+            match (counts.get(&node.lo), counts.get(&node.hi)) {
+                (Some(&lo), Some(&hi)) => {
+                    counts.insert(g, lo + hi);
+                    stack.pop();
+                }
+                (lo, hi) => {
+                    if lo.is_none() {
+                        stack.push(node.lo);
+                    }
+                    if hi.is_none() {
+                        stack.push(node.hi);
+                    }
+                }
+            }
+        }
+        return counts;
+    }
+
+    // 族に含まれる集合の個数
+    pub fn count(&self, f: ZddId) -> u128 {
+        return self.counts(f)[&f];
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 族から一様ランダムに集合を1つ選ぶ関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // f: ZddId - 族
+    // rng: &mut R - 乱数生成器
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Option<Vec<usize>> - 選んだ集合の変数, 族が空ならNone
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn sample<R: Rng>(&self, f: ZddId, rng: &mut R) -> Option<Vec<usize>> {
+        let counts = self.counts(f);
+        if counts[&f] == 0 {
+            return None;
+        }
+        let mut set = vec![];
+        let mut g = f;
+        while g != Zdd::BASE {
+            let node = self.nodes[g];
+            let hi = counts[&node.hi];
+            if rng.gen_range(0..counts[&g]) < hi {
+                set.push(node.var);
+                g = node.hi;
+            } else {
+                g = node.lo;
+            }
+        }
+        return Some(set);
+    }
+
+    // 族に含まれる集合を全て列挙する
+    pub fn sets(&self, f: ZddId) -> Vec<Vec<usize>> {
+        if f == Zdd::EMPTY {
+            return vec![];
+        }
+        if f == Zdd::BASE {
+            return vec![vec![]];
+        }
+        let node = self.nodes[f];
+        let mut sets = self.sets(node.lo);
+        for mut set in self.sets(node.hi) {
+            set.insert(0, node.var);
+            sets.push(set);
+        }
+        return sets;
+    }
+
+    // 変数の集合を構造体にする
+    pub fn structure(&self, set: &Vec<usize>) -> Structure {
+        return Structure::Composition(Composition::new(
+            set.iter().map(|&var| self.elements[var].clone()).collect(),
+        ));
+    }
+
+    // 族に含まれる集合を全て構造体として列挙する
+    pub fn to_structures(&self, f: ZddId) -> Vec<Structure> {
+        return self.sets(f).iter().map(|set| self.structure(set)).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::combine::combine;
+    use crate::common::dataclass::BoardSize;
+    use crate::common::frontier::Frontier;
+    use crate::common::initialize::initialize;
+    use crate::common::profile::Named;
+    use crate::common::relationship::{D, H, M, V};
+    use crate::named;
+    use crate::specific::cutoff::{Cutoff, CutoffFn};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_zdd() {
+        let (_, C, Ep, Ec) = initialize(&BoardSize(2, 3));
+        let R: Vec<Relationship> = vec![H, V, D];
+        let cycle: Vec<Named<CutoffFn>> = named![Cutoff::only_cycle];
+        let line: Vec<Named<CutoffFn>> = named![Cutoff::only_line];
+        let area: Vec<Named<CutoffFn>> = named![Cutoff::non_cutoff];
+
+        // フロンティア法で作った族はcombineの結果と一致する
+        let as_sets = |zdd: &Zdd, structures: &Vec<Structure>| -> HashSet<Vec<usize>> {
+            structures.iter().map(|s| zdd.vars_of(s)).collect()
+        };
+        let mut zdd = Zdd::new(&Ep);
+        let cycles = zdd.cycles();
+        assert_eq!(zdd.count(cycles), 40);
+        let (_, _, Ep_small, _) = initialize(&BoardSize(2, 2));
+        let mut small = Zdd::new(&Ep_small);
+        let small_cycles = small.cycles();
+        let expected = combine(R.clone(), vec![M], &Ep_small, &cycle);
+        assert_eq!(
+            small.sets(small_cycles).into_iter().collect::<HashSet<_>>(),
+            as_sets(&small, &expected)
+        );
+
+        let mut zdd = Zdd::new(&Ec);
+        let paths = zdd.paths();
+        let expected = combine(R, vec![M], &Ec, &line);
+        assert_eq!(
+            zdd.sets(paths).into_iter().collect::<HashSet<_>>(),
+            as_sets(&zdd, &expected)
+        );
+
+        // 大きな盤面ではフロンティア法による個数と一致する
+        let (_, _, Ep_large, Ec_large) = initialize(&BoardSize(3, 3));
+        let mut large = Zdd::new(&Ep_large);
+        let large_cycles = large.cycles();
+        assert_eq!(
+            large.count(large_cycles),
+            Frontier::count_cycles(&BoardSize(3, 3), &Attribute::P)
+        );
+        let mut large = Zdd::new(&Ec_large);
+        let large_paths = large.paths();
+        assert_eq!(
+            large.count(large_paths),
+            Frontier::count_paths(&BoardSize(3, 3), &Attribute::C)
+        );
+
+        let mut zdd = Zdd::new(&C);
+        let areas = zdd.connected(&vec![H, V]);
+        let expected = combine(vec![H, V], vec![M], &C, &area);
+        assert_eq!(
+            zdd.sets(areas).into_iter().collect::<HashSet<_>>(),
+            as_sets(&zdd, &expected)
+        );
+        assert_eq!(zdd.from_structures(&expected), areas);
+
+        // 大きさ2の連結な領域（ドミノ）は7個, ドミノで2×3を敷き詰められる
+        let two = zdd.size_between(2, 2);
+        let dominoes = zdd.intersection(areas, two);
+        assert_eq!(zdd.count(dominoes), 7);
+        let tiled = zdd.tilings(dominoes);
+        let full = zdd.single(&(0..C.len()).collect());
+        assert_eq!(zdd.intersection(tiled, full), full);
+        let pairs = zdd.disjoint_join(dominoes, dominoes);
+        let joined = zdd.join(dominoes, dominoes);
+        assert!(zdd.count(pairs) < zdd.count(joined));
+        assert_eq!(zdd.difference(pairs, joined), Zdd::EMPTY);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let set = zdd.sample(dominoes, &mut rng).unwrap();
+        assert!(zdd.sets(dominoes).contains(&set));
+    }
+}
@@ -1,10 +1,15 @@
-use crate::common::dataclass::{Attribute, Coordinate, Element, Structure};
+use crate::common::dataclass::{BoardSize, Coordinate, Structure};
 use crate::common::relationship::{relationship, Relationship, D, H, M, V};
+use crate::common::topology::Topology;
 use std::collections::HashMap;
 
 // cutoff関数の型
+// 構造体だけを受け取り, 端点の次数など構造体の中で数えるので, 盤面の隣接リストは使わない（連結性はcombineが確かめる）
 pub type CutoffFn = fn(&Structure) -> bool;
 
+// 盤面の位相を受け取るcutoff関数の型（端の繋がった盤面でonly_cycle, only_lineの代わりにcombine_with_topologyに渡す）
+pub type TopologyCutoffFn = fn(&Structure, &Topology, &BoardSize) -> bool;
+
 pub struct Cutoff {}
 
 impl Cutoff {
@@ -40,29 +45,12 @@ impl Cutoff {
     // bool - サイクルであるときtrue
     // ---------------------------------------------------------------------------------------------------------------------
     pub fn only_cycle(G: &Structure) -> bool {
-        let mut counts: HashMap<Coordinate, i32> = HashMap::new();
-        if let Structure::Composition(ref g) = G {
-            if g.entity.len() % 2 == 1 {
-                return false;
-            }
-            for edge in &g.entity {
-                if let Structure::Element(ref e) = edge {
-                    match e.attr {
-                        Attribute::Hp | Attribute::Hc => {
-                            let points = e.coor.horizon_points();
-                            *counts.entry(points.0.clone()).or_insert(0) += 1;
-                            *counts.entry(points.1.clone()).or_insert(0) += 1;
-                        }
-                        Attribute::Vp | Attribute::Vc => {
-                            let points = e.coor.vertical_points();
-                            *counts.entry(points.0.clone()).or_insert(0) += 1;
-                            *counts.entry(points.1.clone()).or_insert(0) += 1;
-                        }
-                        _ => panic!(), // _と=>の間にスペースを追加
-                    }
-                }
-            }
-        }
+        return Cutoff::only_cycle_on(G, &Topology::Plane, &BoardSize(0, 0));
+    }
+
+    // 端の繋がった盤面でグラフがサイクルであるか（端を越える辺の端点は盤面の中の代表で数える）
+    pub fn only_cycle_on(G: &Structure, topology: &Topology, board_size: &BoardSize) -> bool {
+        let counts = Cutoff::degrees(G, topology, board_size);
         let result = counts.values().all(|&count| count == 2);
 
         return result;
@@ -80,26 +68,12 @@ impl Cutoff {
     // bool - パスであるときtrue
     // ---------------------------------------------------------------------------------------------------------------------
     pub fn only_line(G: &Structure) -> bool {
-        let mut counts: HashMap<Coordinate, i32> = HashMap::new();
-        if let Structure::Composition(ref g) = G {
-            for edge in &g.entity {
-                if let Structure::Element(ref e) = edge {
-                    match e.attr {
-                        Attribute::Hp | Attribute::Hc => {
-                            let points = e.coor.horizon_points();
-                            *counts.entry(points.0.clone()).or_insert(0) += 1;
-                            *counts.entry(points.1.clone()).or_insert(0) += 1;
-                        }
-                        Attribute::Vp | Attribute::Vc => {
-                            let points = e.coor.vertical_points();
-                            *counts.entry(points.0.clone()).or_insert(0) += 1;
-                            *counts.entry(points.1.clone()).or_insert(0) += 1;
-                        }
-                        _ => panic!(), // _と=>の間にスペースを追加
-                    }
-                }
-            }
-        }
+        return Cutoff::only_line_on(G, &Topology::Plane, &BoardSize(0, 0));
+    }
+
+    // 端の繋がった盤面でグラフがパスであるか（端を越える辺の端点は盤面の中の代表で数える）
+    pub fn only_line_on(G: &Structure, topology: &Topology, board_size: &BoardSize) -> bool {
+        let counts = Cutoff::degrees(G, topology, board_size);
         // 個数が1か2であることを確認
         let valid_counts = counts.values().all(|&count| count == 1 || count == 2);
 
@@ -110,6 +84,27 @@ impl Cutoff {
         return valid_counts && single_counts == 2;
     }
 
+    // グラフの各頂点の次数（頂点の座標ごとの, その頂点を端に持つ辺の本数）, 平面ではboard_sizeは使わない
+    fn degrees(
+        G: &Structure,
+        topology: &Topology,
+        board_size: &BoardSize,
+    ) -> HashMap<Coordinate, i32> {
+        let mut counts: HashMap<Coordinate, i32> = HashMap::new();
+        if let Structure::Composition(ref g) = G {
+            for edge in &g.entity {
+                if let Structure::Element(ref e) = edge {
+                    let points = topology
+                        .endpoints(e, board_size)
+                        .expect("辺ではない元素です！");
+                    *counts.entry(points.0.coor).or_insert(0) += 1;
+                    *counts.entry(points.1.coor).or_insert(0) += 1;
+                }
+            }
+        }
+        return counts;
+    }
+
     // ---------------------------------------------------------------------------------------------------------------------
     // ↓　for area
 
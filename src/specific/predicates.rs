@@ -1,36 +1,66 @@
+use crate::common::assignment::Assignment;
 use crate::common::dataclass::{Attribute, BoardSize, Coordinate, Element, Structure};
-use crate::common::operate_structures::OperateStructure;
+use crate::common::profile::Profile;
 use crate::common::relationship::{relationship, Relationship, D, H, M, V};
+use std::collections::HashSet;
 
 pub struct Predicates {}
 impl Predicates {
     // ---------------------------------------------------------------------------------------------------------------------
     // [overview]
     // ある構造体を受け取り, その構造体に含まれる元素の解が全て異なることを確認する関数
+    // 値の割り当てられていない元素（hitoriの黒マスなど, 割り当てでNoneの元素）は比べない
     // ---------------------------------------------------------------------------------------------------------------------
     // [params]
-    // compares: &Vec<Structure> - 元素列
+    // assignment: &Assignment - 元素の値の割り当て
     // parent: &Structure - 構造体
     // ---------------------------------------------------------------------------------------------------------------------
     // [return]
     // bool - 全て異なるときにtrue
     // ---------------------------------------------------------------------------------------------------------------------
-    pub fn all_different(compares: &Vec<Structure>, parent: &Structure) -> bool {
-        let contains = OperateStructure::extract_contains_structures(compares, parent);
+    pub fn all_different(assignment: &Assignment, parent: &Structure) -> bool {
+        let mut seen: HashSet<i32> = HashSet::new();
+        let result = assignment
+            .values_of(parent)
+            .into_iter()
+            .flatten()
+            .all(|val| seen.insert(val));
+        return Profile::check_named("predicate", "Predicates::all_different", result, || {
+            format!("{:?}", parent)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::initialize::initialize;
+
+    #[test]
+    fn test_all_different() {
+        let board_size = BoardSize(3, 3);
+        let (_, C, _, _) = initialize(&board_size);
+        let cell = |i, j| Structure::Element(Element::new(Attribute::C, Coordinate(i, j)));
+        let row: Structure = vec![cell(1, 1), cell(1, 2), cell(1, 3)].into();
+
+        // 1行目の(1, 1)と(1, 3)が黒マス: 黒マスは割り当てに入らないので, 残りのセルだけを比べる
+        let mut independent_C: Vec<Structure> = C
+            .iter()
+            .filter(|c| **c != cell(1, 1) && **c != cell(1, 3))
+            .cloned()
+            .collect();
+        for (k, c) in independent_C.iter_mut().enumerate() {
+            c.set_val(Some(k as i32 % 3 + 1));
+        }
+        let assignment = Assignment::from_structures(&board_size, &[&independent_C]);
+        assert!(Predicates::all_different(&assignment, &row));
 
-        for x in contains.iter() {
-            for y in contains.iter() {
-                if x != y {
-                    if let (Structure::Element(ref x_content), Structure::Element(ref y_content)) =
-                        (x, y)
-                    {
-                        if x_content.val == y_content.val {
-                            return false;
-                        }
-                    }
-                }
-            }
+        // 値の入ったセルどうしが同じなら異なるとはいえない
+        let mut full_C = C.clone();
+        for c in full_C.iter_mut() {
+            c.set_val(Some(1));
         }
-        return true;
+        let assignment = Assignment::from_structures(&board_size, &[&full_C]);
+        assert!(!Predicates::all_different(&assignment, &row));
     }
 }
@@ -1,5 +1,9 @@
+use crate::common::board::{Adjacency, Board};
 use crate::common::dataclass::{Attribute, BoardSize, Coordinate, Element, Structure};
+use crate::common::graph::PlanarGraph;
+use crate::common::initialize::Mask;
 use crate::common::relationship::{relationship, Relationship, D, H, M, V};
+use crate::common::topology::Topology;
 use std::collections::HashMap;
 
 pub struct StructureFn {}
@@ -15,44 +19,60 @@ impl StructureFn {
     // [params]
     // cell: &Structure - cycleを調べたい細胞
     // Ep: &Vec<Structure> - 格子点辺の集合
-    // board_size: &BoardSize - 盤面のサイズ
+    // board: &Board - 盤面（ループの外で一度だけ作る）
     // ---------------------------------------------------------------------------------------------------------------------
     // [return]
     // i32 - cycleの結果
     // ---------------------------------------------------------------------------------------------------------------------
-    pub fn cycle(cell: &Structure, Ep: &Vec<Structure>, board_size: &BoardSize) -> i32 {
+    pub fn cycle(cell: &Structure, Ep: &Vec<Structure>, board: &Board) -> i32 {
         if let Structure::Element(ref cell_content) = cell {
             if cell_content.attr != Attribute::C {
                 panic!("cycleでC以外の要素が渡されました！");
             }
-            let x = cell_content.coor.1;
-            let y = cell_content.coor.0;
-            let n = board_size.0;
-            let m = board_size.1;
-
-            let h_top = (y - 1) * m + x - 1;
-            let h_bottom = y * m + x - 1;
-            let v_top = m * (n + 1) + (y - 1) * (m + 1) + x - 1;
-            let v_bottom = m * (n + 1) + (y - 1) * (m + 1) + x;
 
+            // セルに接する格子点辺（Epの中での位置はBoardが知っている）の値を足し合わせる
+            let k = board.index_of(cell).unwrap();
             let mut result = 0;
-
-            if let Structure::Element(ref ep_content) = Ep[h_top as usize] {
-                result += ep_content.val.unwrap();
+            for &edge in board.neighbours(k, Adjacency::Borders).iter() {
+                result += Ep[board.offset(edge)].val().unwrap();
             }
-            if let Structure::Element(ref ep_content) = Ep[h_bottom as usize] {
-                result += ep_content.val.unwrap();
-            }
-            if let Structure::Element(ref ep_content) = Ep[v_top as usize] {
-                result += ep_content.val.unwrap();
+
+            return result;
+        }
+        unreachable!("cycleでC以外の要素が渡されました！");
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 平面グラフの面の周りの辺の本数を計上する関数（cycleの平面グラフ版）
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // cell: &Structure - cycleを調べたい面（Gc）
+    // Ep: &Vec<Structure> - 辺（Gep）の集合
+    // graph: &PlanarGraph - 平面グラフ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // i32 - cycleの結果
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn cycle_on_graph(cell: &Structure, Ep: &Vec<Structure>, graph: &PlanarGraph) -> i32 {
+        if let Structure::Element(ref cell_content) = cell {
+            if cell_content.attr != Attribute::Gc {
+                panic!("cycle_on_graphでGc以外の要素が渡されました！");
             }
-            if let Structure::Element(ref ep_content) = Ep[v_bottom as usize] {
-                result += ep_content.val.unwrap();
+
+            let values: HashMap<Coordinate, Option<i32>> = Ep
+                .iter()
+                .filter_map(|edge| edge.as_element())
+                .map(|edge| (edge.coor.clone(), edge.val))
+                .collect();
+            let mut result = 0;
+            for (u, v) in graph.face_edges(cell_content.coor.0 as usize) {
+                result += values[&Coordinate(u as i32, v as i32)].unwrap();
             }
 
             return result;
         }
-        unreachable!("cycleでC以外の要素が渡されました！");
+        unreachable!("cycle_on_graphでGc以外の要素が渡されました！");
     }
 
     // ---------------------------------------------------------------------------------------------------------------------
@@ -82,6 +102,31 @@ impl StructureFn {
         panic!("is_sideがcell以外に対して呼ばれました！");
     }
 
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 盤面の形（マスク）に沿って, ある細胞が盤面の端であるか否かを確かめる関数
+    // is_sideと同じく, 右または下に細胞が存在しないとき端とする
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // cell: &Structure - 端であるか否かを確かめたい細胞
+    // mask: &Mask - 盤面の形
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // bool - 細胞が端である場合true
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn is_side_masked(cell: &Structure, mask: &Mask) -> bool {
+        if let Structure::Element(ref cell_content) = cell {
+            if cell_content.attr != Attribute::C {
+                panic!("is_side_maskedがcell以外に対して呼ばれました！");
+            }
+            let x = cell_content.coor.1;
+            let y = cell_content.coor.0;
+
+            return !mask.contains(y, x + 1) || !mask.contains(y + 1, x);
+        }
+        panic!("is_side_maskedがcell以外に対して呼ばれました！");
+    }
+
     // ---------------------------------------------------------------------------------------------------------------------
     // ↓ for graph
 
@@ -96,58 +141,23 @@ impl StructureFn {
     // Vec<Structure> - グラフの端の元素の配列(2つ)
     // ---------------------------------------------------------------------------------------------------------------------
     pub fn line_edgepoints(line: &Structure) -> Vec<Structure> {
-        let mut counts: HashMap<Coordinate, i32> = HashMap::new();
-        let mut is_point = true;
+        let mut counts: HashMap<Element, i32> = HashMap::new();
         if let Structure::Composition(ref l) = line {
             for edge in &l.entity {
                 if let Structure::Element(ref e) = edge {
-                    match e.attr {
-                        Attribute::Hp | Attribute::Hc => {
-                            let points = e.coor.horizon_points();
-                            *counts.entry(points.0.clone()).or_insert(0) += 1;
-                            *counts.entry(points.1.clone()).or_insert(0) += 1;
-                            if e.attr == Attribute::Hp {
-                                is_point = true;
-                            } else {
-                                is_point = false;
-                            }
-                        }
-                        Attribute::Vp | Attribute::Vc => {
-                            let points = e.coor.vertical_points();
-                            *counts.entry(points.0.clone()).or_insert(0) += 1;
-                            *counts.entry(points.1.clone()).or_insert(0) += 1;
-                            if e.attr == Attribute::Vp {
-                                is_point = true;
-                            } else {
-                                is_point = false;
-                            }
-                        }
-                        _ => unreachable!(),
-                    }
+                    let points = e.endpoints().expect("辺ではない元素です！");
+                    *counts.entry(points.0).or_insert(0) += 1;
+                    *counts.entry(points.1).or_insert(0) += 1;
                 }
             }
         }
 
-        let single_coors: Vec<Coordinate> = counts
-            .iter()
-            .filter(|(_, &count)| count == 1)
-            .map(|(coor, _)| coor.clone())
+        let result: Vec<Structure> = counts
+            .into_iter()
+            .filter(|(_, count)| *count == 1)
+            .map(|(edgepoint, _)| Structure::Element(edgepoint))
             .collect();
 
-        let mut result: Vec<Structure> = Vec::new();
-
-        if is_point {
-            for coor in single_coors.iter() {
-                let edgepoint = Structure::Element(Element::new(Attribute::P, coor.clone()));
-                result.push(edgepoint);
-            }
-        } else {
-            for coor in single_coors.iter() {
-                let edgepoint = Structure::Element(Element::new(Attribute::C, coor.clone()));
-                result.push(edgepoint);
-            }
-        }
-
         return result;
     }
     // ---------------------------------------------------------------------------------------------------------------------
@@ -156,43 +166,90 @@ impl StructureFn {
     //TODO: C以外も実装
     // ---------------------------------------------------------------------------------------------------------------------
     // [overview]
-    // ある元素に隣接する（縦横）元素を返す関数
+    // 元素列のうち, ある元素に隣接する（縦横）元素を返す関数, 隣接するかは盤面の隣接リストで確かめる
     // ---------------------------------------------------------------------------------------------------------------------
     // [params]
-    // compares: &Structure - 中心となる元素
-    // parent: &Vec<Structure> - 元素と同一の元素列
+    // element: &Structure - 中心となる元素
+    // parent: &Vec<Structure> - 隣接する元素を探す元素列（C, independent_Cなど, 盤面の一部でも順が違ってもよい）
+    // board: &Board - 盤面
     // ---------------------------------------------------------------------------------------------------------------------
     // [return]
-    // bool - 隣接する元素
-    // ---------------------------------------------------------------------------------------------------------------------
-    pub fn adjacent(element: &Structure, parent: &Vec<Structure>) -> Vec<Structure> {
-        if let Structure::Element(ref element_content) = element {
-            match element_content.attr {
-                Attribute::C => {
-                    let x = element_content.coor.1;
-                    let y = element_content.coor.0;
-                    let mut result: Vec<Structure> = Vec::new();
-                    let coor_top = Coordinate(y - 1, x);
-                    let coor_bottom = Coordinate(y + 1, x);
-                    let coor_left = Coordinate(y, x - 1);
-                    let coor_right = Coordinate(y, x + 1);
-
-                    for child in parent.iter() {
-                        if let Structure::Element(ref child_content) = child {
-                            if child_content.coor == coor_top
-                                || child_content.coor == coor_bottom
-                                || child_content.coor == coor_left
-                                || child_content.coor == coor_right
-                            {
-                                result.push(child.clone());
-                            }
-                        }
-                    }
-                    return result;
-                }
-                _ => unreachable!(),
-            }
+    // Vec<Structure> - parentの元素のうち隣接するもの（parentの順）
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn adjacent(element: &Structure, parent: &Vec<Structure>, board: &Board) -> Vec<Structure> {
+        if element.as_element().map(|e| &e.attr) != Some(&Attribute::C) {
+            unreachable!();
+        }
+        let k = board.index_of(element).expect("盤面にない元素です！");
+        let mut neighbours = board.neighbours_with(k, Adjacency::H, &Attribute::C);
+        neighbours.extend(board.neighbours_with(k, Adjacency::V, &Attribute::C));
+        return parent
+            .iter()
+            .filter(|child| {
+                board
+                    .index_of(child)
+                    .map_or(false, |x| neighbours.contains(&x))
+            })
+            .cloned()
+            .collect();
+    }
+
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [overview]
+    // 端の繋がりを考慮して, 細胞に上下左右で隣接する細胞を返す関数
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [params]
+    // element: &Structure - 細胞
+    // parent: &Vec<Structure> - 隣接する細胞を探す集合
+    // topology: &Topology - 盤面の位相
+    // board_size: &BoardSize - 盤面のサイズ
+    // ---------------------------------------------------------------------------------------------------------------------
+    // [return]
+    // Vec<Structure> - 隣接する細胞
+    // ---------------------------------------------------------------------------------------------------------------------
+    pub fn adjacent_with_topology(
+        element: &Structure,
+        parent: &Vec<Structure>,
+        topology: &Topology,
+        board_size: &BoardSize,
+    ) -> Vec<Structure> {
+        if element.as_element().map(|e| &e.attr) != Some(&Attribute::C) {
+            unreachable!();
+        }
+        let R = [
+            topology.relation(H, board_size),
+            topology.relation(V, board_size),
+        ];
+        return parent
+            .iter()
+            .filter(|child| child.as_element().map(|c| &c.attr) == Some(&Attribute::C))
+            .filter(|child| R.iter().any(|&r| relationship(element, child, r)))
+            .cloned()
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjacent() {
+        let board = Board::new(&BoardSize(3, 3));
+        let (_, C, _, _) = board.parts();
+        let cell = |i, j| Structure::Element(Element::new(Attribute::C, Coordinate(i, j)));
+
+        // 盤面の一部で順の違う元素列（黒マスを除いたindependent_Cなど）からも, 隣接する元素だけを取り出す
+        let mut parent: Vec<Structure> = C.iter().filter(|c| **c != cell(1, 2)).cloned().collect();
+        parent.reverse();
+        for c in parent.iter_mut() {
+            c.set_val(Some(1));
+        }
+        let mut found = StructureFn::adjacent(&cell(2, 2), &parent, &board);
+        assert!(found.iter().all(|c| c.val() == Some(1)));
+        for c in found.iter_mut() {
+            c.set_val(None);
         }
-        unreachable!();
+        assert_eq!(found, vec![cell(3, 2), cell(2, 3), cell(2, 1)]);
     }
 }